@@ -2,11 +2,11 @@
 // Licensed under the Apache License, Version 2.0.
 
 use dora::{
-    build_artifact,
-    primitives::{Address, Bytecode, Bytes, Bytes32, SpecId},
+    CompileOptions, artifact_key_with_options, build_artifact_with_options,
+    primitives::{Address, Bytecode, Bytes32, OptimizationLevel, SpecId, keccak256},
     runtime::{
         ExitStatusCode,
-        artifact::{Artifact, SymbolArtifact},
+        artifact::{Artifact, ArtifactKey, SymbolArtifact},
         call::CallKind,
         context::{Contract, RuntimeContext},
         db::MemoryDB,
@@ -26,16 +26,32 @@ mod host;
 mod tests;
 
 lazy_static! {
-    static ref ARTIFACTS: Mutex<FxHashMap<Bytes, SymbolArtifact>> =
+    static ref ARTIFACTS: Mutex<FxHashMap<ArtifactKey, SymbolArtifact>> =
         Mutex::new(FxHashMap::default());
 }
 
 #[evmc_declare_vm("dora", "evm, ewasm, precompiles", "12.0.0")]
-pub struct DoraVM {}
+pub struct DoraVM {
+    /// The optimization level of the compiled code, which can be set by the `opt-level` option
+    /// e.g., `evmc_set_option(vm, "opt-level", "aggressive")`.
+    opt_level: OptimizationLevel,
+}
 
 impl EvmcVm for DoraVM {
     fn init() -> Self {
-        Self {}
+        Self {
+            opt_level: OptimizationLevel::default(),
+        }
+    }
+
+    fn set_option(&mut self, key: &str, value: &str) -> Result<(), SetOptionError> {
+        match key {
+            "opt-level" => {
+                self.opt_level = value.parse().map_err(|_| SetOptionError::InvalidValue)?;
+                Ok(())
+            }
+            _ => Err(SetOptionError::InvalidKey),
+        }
     }
 
     fn execute<'a>(
@@ -68,19 +84,28 @@ impl EvmcVm for DoraVM {
             spec_id,
             message.gas() as u64,
         );
+        // The artifacts are keyed by the code hash, the revision and the compile options, thus the
+        // same code executed with different revisions or optimization levels never shares the
+        // machine code.
+        let opts = CompileOptions::default().opt_level(self.opt_level);
+        let key = artifact_key_with_options(
+            &runtime_context.contract.code,
+            keccak256(code),
+            runtime_context.inner.spec_id,
+            &opts,
+        );
         let mut artifacts = ARTIFACTS.lock().unwrap();
-        let artifact = if let Some(artifact) =
-            artifacts.get(runtime_context.contract.code.bytecode().as_ref())
-        {
+        let artifact = if let Some(artifact) = artifacts.get(&key) {
             artifact.clone()
         } else {
-            let Ok(artifact) = build_artifact::<MemoryDB>(
+            let Ok(artifact) = build_artifact_with_options::<MemoryDB>(
                 &runtime_context.contract.code,
                 runtime_context.inner.spec_id,
+                &opts,
             ) else {
                 return ExecutionResult::failure();
             };
-            artifacts.insert(runtime_context.contract.code.bytes(), artifact.clone());
+            artifacts.insert(key, artifact.clone());
             artifact
         };
         drop(artifacts);
@@ -5,3 +5,21 @@ fn test_doravm_lifecycle() {
     let doravm = evmc_create_doravm();
     __evmc_destroy(doravm as _);
 }
+
+#[test]
+fn test_doravm_set_opt_level() {
+    let mut doravm = DoraVM::init();
+    assert_eq!(doravm.opt_level, OptimizationLevel::Default);
+    assert!(doravm.set_option("opt-level", "aggressive").is_ok());
+    assert_eq!(doravm.opt_level, OptimizationLevel::Aggressive);
+    assert!(doravm.set_option("opt-level", "0").is_ok());
+    assert_eq!(doravm.opt_level, OptimizationLevel::None);
+    assert!(matches!(
+        doravm.set_option("opt-level", "fast"),
+        Err(SetOptionError::InvalidValue)
+    ));
+    assert!(matches!(
+        doravm.set_option("unknown", "1"),
+        Err(SetOptionError::InvalidKey)
+    ));
+}
@@ -0,0 +1,261 @@
+//! The Solidity ABI encoding of the call data and the decoding of the return data, the event logs
+//! and the revert data used by `dora run --sig`, `--abi` and `--function`.
+
+use alloy_dyn_abi::{DynSolValue, EventExt, FunctionExt, JsonAbiExt, Specifier};
+use alloy_json_abi::{Function, JsonAbi, Param};
+use alloy_sol_types::{Panic, Revert, SolError};
+use anyhow::{Context, Result, bail, ensure};
+use dora_primitives::Log;
+use dora_runtime::{ExecutionResult, result::Output};
+
+/// The contract ABI from the ABI JSON file or the function signature.
+#[derive(Debug, Clone, Default)]
+pub struct Abi {
+    abi: JsonAbi,
+}
+
+impl Abi {
+    /// Reads the ABI JSON file, which is either the ABI array or the compiler artifact containing
+    /// the `abi` field.
+    pub fn read(path: &str) -> Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read the ABI file: {path}"))?;
+        let value: serde_json::Value =
+            serde_json::from_str(&json).with_context(|| format!("Invalid ABI file: {path}"))?;
+        let value = match value {
+            serde_json::Value::Object(mut artifact) if artifact.contains_key("abi") => {
+                artifact.remove("abi").unwrap_or_default()
+            }
+            value => value,
+        };
+        let abi = serde_json::from_value(value).with_context(|| format!("Invalid ABI: {path}"))?;
+        Ok(Self { abi })
+    }
+
+    /// Adds the function given by the signature e.g., `transfer(address,uint256)` or
+    /// `function balanceOf(address) returns (uint256)`.
+    pub fn add_signature(&mut self, signature: &str) -> Result<()> {
+        let function = Function::parse(signature)
+            .with_context(|| format!("Invalid function signature: {signature}"))?;
+        let overloads = self.abi.functions.entry(function.name.clone()).or_default();
+        if !overloads
+            .iter()
+            .any(|f| f.selector() == function.selector())
+        {
+            overloads.push(function);
+        }
+        Ok(())
+    }
+
+    /// Encodes the call data of the function given by the name, or by the signature to select
+    /// one of the overloaded functions.
+    pub fn encode_call(&self, function: &str, args: &[String]) -> Result<Vec<u8>> {
+        let function = self.function(function)?;
+        let values = coerce_args(&function.inputs, args)
+            .with_context(|| format!("Failed to encode the call of {}", function.signature()))?;
+        Ok(function.abi_encode_input(&values)?)
+    }
+
+    /// Encodes the constructor arguments appended to the init code.
+    pub fn encode_constructor(&self, args: &[String]) -> Result<Vec<u8>> {
+        let Some(constructor) = &self.abi.constructor else {
+            bail!("The ABI has no constructor");
+        };
+        let values = coerce_args(&constructor.inputs, args)
+            .context("Failed to encode the constructor arguments")?;
+        Ok(constructor.abi_encode_input(&values)?)
+    }
+
+    /// Describes the return data, the event logs and the revert data of the call with the input
+    /// in the human-readable form, one line per item.
+    pub fn describe(&self, input: &[u8], result: &ExecutionResult) -> Vec<String> {
+        let mut lines = Vec::new();
+        match result {
+            ExecutionResult::Success { output, .. } => {
+                if let (Output::Call(data), Some(function)) =
+                    (output, self.function_by_selector(input))
+                {
+                    match function.abi_decode_output(data, true) {
+                        Ok(values) => lines.push(format!(
+                            "Returns: ({})",
+                            format_params(&function.outputs, &values)
+                        )),
+                        Err(err) => lines.push(format!(
+                            "Failed to decode the return data of {}: {err}",
+                            function.signature()
+                        )),
+                    }
+                }
+                for (index, log) in result.logs().iter().enumerate() {
+                    lines.push(format!(
+                        "Log {index} from {}: {}",
+                        log.address,
+                        self.describe_log(log)
+                    ));
+                }
+            }
+            ExecutionResult::Revert { output, .. } => {
+                lines.push(format!("Reverted: {}", self.describe_revert(output)));
+            }
+            ExecutionResult::Halt { .. } | ExecutionResult::FatalExternalError => {}
+        }
+        lines
+    }
+
+    fn function(&self, function: &str) -> Result<&Function> {
+        if function.contains('(') {
+            let selector = Function::parse(function)
+                .with_context(|| format!("Invalid function signature: {function}"))?
+                .selector();
+            return self
+                .abi
+                .functions()
+                .find(|f| f.selector() == selector)
+                .with_context(|| format!("The function {function} is not found in the ABI"));
+        }
+        match self.abi.function(function).map(Vec::as_slice) {
+            Some([function]) => Ok(function),
+            Some(overloads) if !overloads.is_empty() => bail!(
+                "The function {function} is overloaded, use one of the signatures: {}",
+                overloads
+                    .iter()
+                    .map(Function::signature)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            _ => bail!("The function {function} is not found in the ABI"),
+        }
+    }
+
+    fn function_by_selector(&self, input: &[u8]) -> Option<&Function> {
+        let selector = input.get(..4)?;
+        self.abi
+            .functions()
+            .find(|f| f.selector().as_slice() == selector)
+    }
+
+    fn describe_log(&self, log: &Log) -> String {
+        let topics = log.topics();
+        let decoded = topics.first().and_then(|topic| {
+            let event = self
+                .abi
+                .events()
+                .find(|event| !event.anonymous && event.selector() == *topic)?;
+            let decoded = event
+                .decode_log_parts(topics.iter().copied(), &log.data.data, true)
+                .ok()?;
+            Some((event, decoded))
+        });
+        if let Some((event, decoded)) = decoded {
+            // Restore the declaration order of the indexed and non-indexed parameters.
+            let (mut indexed, mut body) = (decoded.indexed.iter(), decoded.body.iter());
+            let params = event
+                .inputs
+                .iter()
+                .filter_map(|param| {
+                    let value = if param.indexed {
+                        indexed.next()
+                    } else {
+                        body.next()
+                    }?;
+                    Some(format_named(&param.name, value))
+                })
+                .collect::<Vec<_>>();
+            return format!("{}({})", event.name, params.join(", "));
+        }
+        format!(
+            "topics [{}] data 0x{}",
+            topics
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", "),
+            hex::encode(&log.data.data)
+        )
+    }
+
+    fn describe_revert(&self, data: &[u8]) -> String {
+        if data.is_empty() {
+            return "empty revert data".to_string();
+        }
+        if let Ok(revert) = Revert::abi_decode(data, true) {
+            return format!("Error({:?})", revert.reason);
+        }
+        if let Ok(panic) = Panic::abi_decode(data, true) {
+            return match panic.kind() {
+                Some(kind) => format!("Panic({:#x}): {}", panic.code, kind.as_str()),
+                None => format!("Panic({:#x})", panic.code),
+            };
+        }
+        if let Some(selector) = data.get(..4) {
+            for error in self.abi.errors() {
+                if error.selector().as_slice() != selector {
+                    continue;
+                }
+                if let Ok(values) = error.abi_decode_input(&data[4..], true) {
+                    return format!("{}({})", error.name, format_params(&error.inputs, &values));
+                }
+            }
+        }
+        format!("0x{}", hex::encode(data))
+    }
+}
+
+fn coerce_args(params: &[Param], args: &[String]) -> Result<Vec<DynSolValue>> {
+    ensure!(
+        params.len() == args.len(),
+        "Expected {} arguments, got {}",
+        params.len(),
+        args.len()
+    );
+    params
+        .iter()
+        .zip(args)
+        .map(|(param, arg)| {
+            let ty = param.resolve()?;
+            ty.coerce_str(arg)
+                .with_context(|| format!("Invalid argument of type {ty}: {arg}"))
+        })
+        .collect()
+}
+
+fn format_params(params: &[Param], values: &[DynSolValue]) -> String {
+    params
+        .iter()
+        .zip(values)
+        .map(|(param, value)| format_named(&param.name, value))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn format_named(name: &str, value: &DynSolValue) -> String {
+    if name.is_empty() {
+        format_value(value)
+    } else {
+        format!("{name}: {}", format_value(value))
+    }
+}
+
+fn format_value(value: &DynSolValue) -> String {
+    let join = |values: &[DynSolValue]| {
+        values
+            .iter()
+            .map(format_value)
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    match value {
+        DynSolValue::Bool(value) => value.to_string(),
+        DynSolValue::Int(value, _) => value.to_string(),
+        DynSolValue::Uint(value, _) => value.to_string(),
+        DynSolValue::FixedBytes(word, size) => format!("0x{}", hex::encode(&word[..*size])),
+        DynSolValue::Address(address) => address.to_checksum(None),
+        DynSolValue::Bytes(bytes) => format!("0x{}", hex::encode(bytes)),
+        DynSolValue::String(string) => format!("{string:?}"),
+        DynSolValue::Array(values) | DynSolValue::FixedArray(values) => {
+            format!("[{}]", join(values))
+        }
+        DynSolValue::Tuple(values) => format!("({})", join(values)),
+        value => format!("{value:?}"),
+    }
+}
@@ -0,0 +1,154 @@
+//! The JSON file of the block and transaction environment used by `dora run --env`.
+
+use anyhow::{Context, Result};
+use dora_primitives::{AccessList, Address, B256, Bytes, Env, SpecId, TxKind, U256};
+use serde::Deserialize;
+
+/// The block and transaction environment, all the fields are optional and override the values
+/// given by the command line arguments, see below:
+///
+/// ```json
+/// {
+///     "chainId": "0x1",
+///     "block": {
+///         "number": "0x1",
+///         "coinbase": "0x2adc25665018aa1fe0e6bc666dac8fc2697ff9ba",
+///         "timestamp": "0x3e8",
+///         "gasLimit": "0x1c9c380",
+///         "baseFee": "0x7",
+///         "prevrandao": "0x0000000000000000000000000000000000000000000000000000000000020000",
+///         "excessBlobGas": "0x0"
+///     },
+///     "tx": {
+///         "caller": "0xa94f5374fce5edbc8e2a8697c15331677e6ebf0b",
+///         "to": "0x1000000000000000000000000000000000000000",
+///         "value": "0x0",
+///         "gasLimit": "0x989680",
+///         "gasPrice": "0xa",
+///         "data": "0x",
+///         "nonce": "0x0"
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct EnvFile {
+    pub chain_id: Option<U256>,
+    #[serde(default)]
+    pub block: BlockEnvFile,
+    #[serde(default)]
+    pub tx: TxEnvFile,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct BlockEnvFile {
+    pub number: Option<U256>,
+    pub coinbase: Option<Address>,
+    pub timestamp: Option<U256>,
+    pub gas_limit: Option<U256>,
+    pub base_fee: Option<U256>,
+    pub difficulty: Option<U256>,
+    pub prevrandao: Option<B256>,
+    pub excess_blob_gas: Option<U256>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct TxEnvFile {
+    pub caller: Option<Address>,
+    pub to: Option<Address>,
+    pub value: Option<U256>,
+    pub gas_limit: Option<U256>,
+    pub gas_price: Option<U256>,
+    pub gas_priority_fee: Option<U256>,
+    pub data: Option<Bytes>,
+    pub nonce: Option<U256>,
+    pub access_list: Option<AccessList>,
+    pub blob_hashes: Option<Vec<B256>>,
+    pub max_fee_per_blob_gas: Option<U256>,
+}
+
+impl EnvFile {
+    /// Reads the environment file.
+    pub fn read(path: &str) -> Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read the env file: {path}"))?;
+        serde_json::from_str(&json).with_context(|| format!("Invalid env file: {path}"))
+    }
+
+    /// Overrides the environment with the fields present in the file.
+    pub fn apply(self, env: &mut Env, spec_id: SpecId) -> Result<()> {
+        if let Some(chain_id) = self.chain_id {
+            env.cfg.chain_id = to_u64(chain_id, "chainId")?;
+            env.tx.chain_id = Some(env.cfg.chain_id);
+        }
+        let block = self.block;
+        if let Some(number) = block.number {
+            env.block.number = number;
+        }
+        if let Some(coinbase) = block.coinbase {
+            env.block.coinbase = coinbase;
+        }
+        if let Some(timestamp) = block.timestamp {
+            env.block.timestamp = timestamp;
+        }
+        if let Some(gas_limit) = block.gas_limit {
+            env.block.gas_limit = gas_limit;
+        }
+        if let Some(base_fee) = block.base_fee {
+            env.block.basefee = base_fee;
+        }
+        if let Some(difficulty) = block.difficulty {
+            env.block.difficulty = difficulty;
+        }
+        if let Some(prevrandao) = block.prevrandao {
+            env.block.prevrandao = Some(prevrandao);
+        }
+        if let Some(excess_blob_gas) = block.excess_blob_gas {
+            env.block.set_blob_excess_gas_and_price(
+                to_u64(excess_blob_gas, "block.excessBlobGas")?,
+                spec_id.is_enabled_in(SpecId::PRAGUE),
+            );
+        }
+        let tx = self.tx;
+        if let Some(caller) = tx.caller {
+            env.tx.caller = caller;
+        }
+        if let Some(to) = tx.to {
+            env.tx.transact_to = TxKind::Call(to);
+        }
+        if let Some(value) = tx.value {
+            env.tx.value = value;
+        }
+        if let Some(gas_limit) = tx.gas_limit {
+            env.tx.gas_limit = to_u64(gas_limit, "tx.gasLimit")?;
+        }
+        if let Some(gas_price) = tx.gas_price {
+            env.tx.gas_price = gas_price;
+        }
+        if tx.gas_priority_fee.is_some() {
+            env.tx.gas_priority_fee = tx.gas_priority_fee;
+        }
+        if let Some(data) = tx.data {
+            env.tx.data = data;
+        }
+        if let Some(nonce) = tx.nonce {
+            env.tx.nonce = Some(to_u64(nonce, "tx.nonce")?);
+        }
+        if let Some(access_list) = tx.access_list {
+            env.tx.access_list = access_list.0;
+        }
+        if let Some(blob_hashes) = tx.blob_hashes {
+            env.tx.blob_hashes = blob_hashes;
+        }
+        if tx.max_fee_per_blob_gas.is_some() {
+            env.tx.max_fee_per_blob_gas = tx.max_fee_per_blob_gas;
+        }
+        Ok(())
+    }
+}
+
+fn to_u64(value: U256, field: &str) -> Result<u64> {
+    u64::try_from(value).with_context(|| format!("The env field {field} overflows u64: {value}"))
+}
@@ -1,11 +1,31 @@
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 use clap::{Args, Parser, Subcommand};
+use dora::{
+    CompileOptions, EmitStage, VM, VMContext, assemble, compile_handler_with_options,
+    compile_stages,
+};
+use dora_compiler::evm::disasm::Disassembly;
 use dora_primitives::spec::SpecId;
-use dora_primitives::{Address, Bytecode, Bytes, Env, TxKind, U256};
+use dora_primitives::{
+    Address, Bytecode, Bytes, Env, OptimizationLevel, TxKind, U256, WASM_MAGIC_BYTES,
+};
 use dora_runtime::db::MemoryDB;
+use dora_runtime::genesis::Prestate;
+use dora_runtime::inspector::{Eip3155Tracer, GasProfile, GasProfiler};
+use dora_runtime::result::{ExecutionResult, Output};
+use dora_tools::t8n::{self, T8nInput};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use tracing::{error, info};
 
+mod abi;
+mod env;
+#[cfg(test)]
+mod tests;
+
+use abi::Abi;
+use env::EnvFile;
+
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 #[command(propagate_version = true)]
@@ -18,6 +38,116 @@ struct Cli {
 enum Commands {
     /// Run Dora EVM or WASM bytecode with given parameters
     Run(RunArgs),
+    /// Compile Dora EVM or WASM bytecode and emit the IR of the compile pipeline stages
+    Compile(CompileArgs),
+    /// Disassemble EVM bytecode with the basic blocks and the static jump targets
+    Disasm(DisasmArgs),
+    /// Assemble the EVM assembly source into the bytecode
+    Asm(AsmArgs),
+    /// Run the state transition of a block, compatible with the geth `evm t8n` tool
+    T8n(T8nArgs),
+}
+
+#[derive(Args)]
+struct T8nArgs {
+    /// Path to the pre-state allocation file, or `stdin` to read it from the standard input
+    #[arg(long = "input.alloc", default_value = "alloc.json")]
+    input_alloc: String,
+
+    /// Path to the block environment file, or `stdin` to read it from the standard input
+    #[arg(long = "input.env", default_value = "env.json")]
+    input_env: String,
+
+    /// Path to the transactions file in the JSON format or the hex string of the RLP encoded
+    /// transaction list, or `stdin` to read it from the standard input
+    #[arg(long = "input.txs", default_value = "txs.json")]
+    input_txs: String,
+
+    /// Directory to write the output files to
+    #[arg(long = "output.basedir", default_value = ".")]
+    output_basedir: PathBuf,
+
+    /// File name of the execution result, or `stdout`/`stderr` to print it
+    #[arg(long = "output.result", default_value = "result.json")]
+    output_result: String,
+
+    /// File name of the post-state allocation, or `stdout`/`stderr` to print it
+    #[arg(long = "output.alloc", default_value = "alloc.json")]
+    output_alloc: String,
+
+    /// File name of the RLP encoded transactions of the block body, or `stdout`/`stderr` to print it
+    #[arg(long = "output.body")]
+    output_body: Option<String>,
+
+    /// Fork name of the rules, e.g., Shanghai, Cancun or Prague, the environment of Cancun and
+    /// later forks requires the `parentBeaconBlockRoot`
+    #[arg(long = "state.fork", default_value = "Cancun")]
+    state_fork: String,
+
+    /// Chain ID
+    #[arg(long = "state.chainid", default_value = "1")]
+    state_chainid: u64,
+
+    /// Block reward in wei paid to the coinbase before the merge, -1 disables the reward
+    #[arg(
+        long = "state.reward",
+        default_value = "0",
+        allow_negative_numbers = true
+    )]
+    state_reward: i64,
+}
+
+#[derive(Args)]
+struct AsmArgs {
+    /// Path to the assembly source file
+    source: PathBuf,
+
+    /// Write the bytecode to the file instead of printing the hex text of it
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct DisasmArgs {
+    /// Path to the contract file (EVM hex bin format file)
+    /// or hex text of the contract (Classified by 0x prefix)
+    contract: String,
+
+    /// Whether the contract file is hex text format (Default is false)
+    #[clap(long)]
+    hex_file: bool,
+
+    /// Write the control-flow graph in the Graphviz DOT format to the file
+    #[arg(long)]
+    dot: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct CompileArgs {
+    /// Path to the contract file (EVM hex bin format file or WASM files).
+    /// or hex text of the contract (Classified by 0x prefix)
+    contract: String,
+
+    /// Whether the contract file is hex text format (Default is false)
+    #[clap(long)]
+    hex_file: bool,
+
+    /// VM Spec id
+    #[arg(long, default_value = "CANCUN")]
+    spec_id: SpecId,
+
+    /// Optimization level of the compiler (none, less, default, aggressive or 0-3)
+    #[arg(long, default_value = "default")]
+    opt_level: OptimizationLevel,
+
+    /// Comma separated compile stages to emit (source, dora, builtin, llvm, llvm-ir, obj)
+    #[arg(long, value_delimiter = ',', default_value = "llvm-ir")]
+    emit: Vec<EmitStage>,
+
+    /// Directory to write the stage outputs to as `<contract>.<stage>.<ext>` files, the textual
+    /// stages are printed to the standard output when it is not set
+    #[arg(long, short)]
+    output_dir: Option<PathBuf>,
 }
 
 #[derive(Args)]
@@ -65,6 +195,65 @@ struct RunArgs {
     /// VM Spec id
     #[arg(long, default_value = "CANCUN")]
     spec_id: SpecId,
+
+    /// Optimization level of the compiler (none, less, default, aggressive or 0-3)
+    #[arg(long, default_value = "default")]
+    opt_level: OptimizationLevel,
+
+    /// Print the EIP-3155 JSON trace of the execution to the standard error, which is separated from
+    /// the logs and the result output
+    #[arg(long)]
+    trace_json: bool,
+    /// Print the gas profile summary per opcode, per program counter and per call frame
+    #[arg(long)]
+    gas_profile: bool,
+
+    /// Write the gas profile in the folded stack format to the file, which can be consumed by the flamegraph tools
+    #[arg(long)]
+    folded_stacks: Option<String>,
+
+    /// Load the accounts and the block hashes from the geth-style alloc JSON file,
+    /// the accounts in the pre-state override the contract account
+    #[arg(long)]
+    prestate: Option<String>,
+
+    /// Load the block and transaction environment from the JSON file,
+    /// the fields in the file override the command line arguments
+    #[arg(long)]
+    env: Option<String>,
+
+    /// Write the committed state in the geth-style alloc JSON format to the file
+    #[arg(long)]
+    dump_state: Option<String>,
+
+    /// Deploy the contract by running it as the init code of a create transaction,
+    /// the call data is appended to the EVM init code as the constructor arguments, the WASM
+    /// module takes no constructor arguments
+    #[arg(long)]
+    create: bool,
+
+    /// Call data in hex format (0x prefixed) of the follow-up call against the deployed contract,
+    /// can be repeated to run multiple calls in order in the same state
+    #[arg(long = "call", requires = "create")]
+    calls: Vec<String>,
+
+    /// Function signature to encode the call data from the arguments, e.g., "transfer(address,uint256)",
+    /// the return types can be given to decode the return data, e.g., "balanceOf(address) returns (uint256)"
+    #[arg(long, conflicts_with_all = ["calldata", "function", "create"])]
+    sig: Option<String>,
+
+    /// Path to the ABI JSON file or the compiler artifact containing the ABI, which is used to encode
+    /// the call data or the constructor arguments and to decode the return data, logs and revert data
+    #[arg(long)]
+    abi: Option<String>,
+
+    /// Name or signature of the function in the ABI file to call with the arguments
+    #[arg(long, requires = "abi", conflicts_with_all = ["calldata", "create"])]
+    function: Option<String>,
+
+    /// Arguments of the function, or the constructor arguments in the create mode
+    #[arg(long, num_args = 1.., allow_negative_numbers = true)]
+    args: Vec<String>,
 }
 
 fn main() -> Result<()> {
@@ -72,80 +261,292 @@ fn main() -> Result<()> {
 
     let cli = Cli::parse();
     match &cli.command {
+        Commands::Compile(compile_args) => compile(compile_args)?,
+        Commands::Disasm(disasm_args) => disasm(disasm_args)?,
+        Commands::Asm(asm_args) => asm(asm_args)?,
+        Commands::T8n(t8n_args) => t8n(t8n_args)?,
         Commands::Run(run_args) => {
-            let bytecode: Vec<u8>;
-            // Check hex flag
-            if !run_args.hex_file {
-                // Check contract string
-                match run_args.contract.strip_prefix("0x") {
-                    None => {
-                        // Parse contract bytecode
-                        bytecode = std::fs::read(&run_args.contract).with_context(|| {
-                            format!(
-                                "Failed to read contract bytecode file: {}",
-                                run_args.contract
-                            )
-                        })?;
-                    }
-                    Some(contract_hex) => {
-                        // Convert contract hex text into bytecode
-                        bytecode = hex::decode(contract_hex).with_context(|| {
-                            format!("Invalid contract hex text: {contract_hex}")
-                        })?;
-                    }
-                }
-            } else {
-                // Parse contract hex file
-                let contract_hex_text =
-                    std::fs::read_to_string(&run_args.contract).with_context(|| {
-                        format!("Failed to read contract hex file: {}", run_args.contract)
-                    })?;
-                bytecode = hex::decode(
-                    contract_hex_text
-                        .strip_prefix("0x")
-                        .unwrap_or(&contract_hex_text),
-                )
-                .with_context(|| format!("Invalid contract hex file: {}", run_args.contract))?;
+            let bytecode = read_contract(&run_args.contract, run_args.hex_file)?;
+
+            // Parse ABI
+            let mut abi = match &run_args.abi {
+                Some(path) => Abi::read(path)?,
+                None => Abi::default(),
+            };
+            if let Some(sig) = &run_args.sig {
+                abi.add_signature(sig)?;
             }
 
             // Parse calldata
-            let calldata = run_args
-                .calldata
-                .as_ref()
-                .map(|data| {
-                    hex::decode(data.strip_prefix("0x").unwrap_or(data))
-                        .expect("Invalid calldata hex")
-                })
-                .unwrap_or_default();
+            let calldata =
+                if let Some(function) = run_args.sig.as_ref().or(run_args.function.as_ref()) {
+                    abi.encode_call(function, &run_args.args)?
+                } else if run_args.create && run_args.abi.is_some() && !run_args.args.is_empty() {
+                    abi.encode_constructor(&run_args.args)?
+                } else if !run_args.args.is_empty() {
+                    bail!("The arguments require --sig, --function, or --create with --abi");
+                } else {
+                    run_args
+                        .calldata
+                        .as_ref()
+                        .map(|data| {
+                            hex::decode(data.strip_prefix("0x").unwrap_or(data))
+                                .expect("Invalid calldata hex")
+                        })
+                        .unwrap_or_default()
+                };
 
             // Parse sender
             let sender = Address::from_str(run_args.sender.as_str())
                 .with_context(|| format!("Invalid sender address: {}", run_args.sender))?;
 
-            let address = Address::default();
-
             // Set Env
             let mut env = Env::default();
             env.tx.gas_limit = run_args.gas_limit;
             env.tx.value = U256::from_str(&run_args.value).context("Failed to parse value")?;
             env.tx.caller = sender;
-            env.tx.transact_to = TxKind::Call(address);
+            env.tx.transact_to = TxKind::Call(Address::default());
             env.tx.data = Bytes::from(calldata);
             env.block.number = U256::from(run_args.block_number);
             env.block.timestamp = U256::from(run_args.timestamp);
+            if let Some(path) = &run_args.env {
+                EnvFile::read(path)?.apply(&mut env, run_args.spec_id)?;
+            }
+            if run_args.create {
+                // The EVM init code is followed by the constructor arguments, while the WASM
+                // module is deployed as it is and its `deploy` function takes no arguments.
+                if bytecode.starts_with(&WASM_MAGIC_BYTES) && !env.tx.data.is_empty() {
+                    bail!("The constructor arguments are not supported for the WASM deployment");
+                }
+                let mut data = bytecode.clone();
+                data.extend_from_slice(&env.tx.data);
+                env.tx.data = Bytes::from(data);
+                env.tx.transact_to = TxKind::Create;
+            }
+            let address = match env.tx.transact_to {
+                TxKind::Call(address) => address,
+                TxKind::Create => Address::default(),
+            };
             // Set DB
-            let db = MemoryDB::new().with_contract(address, Bytecode::new(bytecode.into()));
+            let code = Bytecode::new(bytecode.into());
+            let gas_profiling = run_args.gas_profile || run_args.folded_stacks.is_some();
+            let mut db = if run_args.create {
+                MemoryDB::new()
+            } else {
+                MemoryDB::new().with_contract(address, code)
+            };
+            if let Some(path) = &run_args.prestate {
+                let json = std::fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read the prestate file: {path}"))?;
+                let prestate = Prestate::from_json(&json)
+                    .with_context(|| format!("Invalid prestate file: {path}"))?;
+                db.insert_prestate(prestate);
+            }
+            let mut profiler = GasProfiler::new();
+            let ctx = VMContext::new(
+                db,
+                env,
+                run_args.spec_id,
+                compile_handler_with_options(
+                    CompileOptions::from_env().opt_level(run_args.opt_level),
+                ),
+            );
             // Run the contract
-            match dora::run(env, db, run_args.spec_id) {
-                Ok(result) => {
-                    info!("Execution result: {:#?}", result);
+            let tracer = run_args.trace_json.then(|| {
+                Eip3155Tracer::new(std::io::stderr()).with_fork(format!("{:?}", run_args.spec_id))
+            });
+            let ctx = match tracer {
+                Some(tracer) if gas_profiling => ctx.with_inspector((tracer, &mut profiler)),
+                Some(tracer) => ctx.with_inspector(tracer),
+                None if gas_profiling => ctx.with_inspector(&mut profiler),
+                None => ctx,
+            };
+            let mut vm = VM::new(ctx);
+            let mut results = vec![(vm.context.env.tx.data.clone(), vm.transact_commit())];
+            if let (
+                _,
+                Ok(ExecutionResult::Success {
+                    output: Output::Create(runtime_code, Some(deployed)),
+                    ..
+                }),
+            ) = &results[0]
+            {
+                let deployed = *deployed;
+                info!("Deployed contract address: {deployed}");
+                info!("Deployed runtime code: {runtime_code}");
+                // Run the follow-up calls against the deployed contract in the same state.
+                for calldata in &run_args.calls {
+                    let calldata = hex::decode(calldata.strip_prefix("0x").unwrap_or(calldata))
+                        .with_context(|| format!("Invalid call data hex: {calldata}"))?;
+                    let tx = &mut vm.context.env.tx;
+                    tx.transact_to = TxKind::Call(deployed);
+                    tx.data = Bytes::from(calldata);
+                    tx.value = U256::ZERO;
+                    // The caller nonce is bumped by the previous transactions.
+                    tx.nonce = None;
+                    results.push((tx.data.clone(), vm.transact_commit()));
                 }
-                Err(e) => {
-                    error!("Execution failed: {}", e);
-                    std::process::exit(1);
+            } else if run_args.create && !run_args.calls.is_empty() {
+                error!("Deployment failed, skip the follow-up calls");
+            }
+            let db = vm.into_context().db;
+            if gas_profiling && results.iter().all(|(_, result)| result.is_ok()) {
+                write_gas_profile(run_args, &profiler.into_profile())?;
+            }
+            if let Some(path) = &run_args.dump_state {
+                let json = serde_json::to_string_pretty(&db.dump_state())?;
+                std::fs::write(path, json)
+                    .with_context(|| format!("Failed to write the state file: {path}"))?;
+            }
+            for (input, result) in results {
+                match result {
+                    Ok(result) => {
+                        info!("Execution result: {:#?}", result);
+                        for line in abi.describe(&input, &result) {
+                            info!("{line}");
+                        }
+                    }
+                    Err(e) => {
+                        error!("Execution failed: {}", e);
+                        std::process::exit(1);
+                    }
                 }
             }
         }
     }
     Ok(())
 }
+
+fn read_contract(contract: &str, hex_file: bool) -> Result<Vec<u8>> {
+    let bytecode: Vec<u8>;
+    // Check hex flag
+    if !hex_file {
+        // Check contract string
+        match contract.strip_prefix("0x") {
+            None => {
+                // Parse contract bytecode
+                bytecode = std::fs::read(contract).with_context(|| {
+                    format!("Failed to read contract bytecode file: {}", contract)
+                })?;
+            }
+            Some(contract_hex) => {
+                // Convert contract hex text into bytecode
+                bytecode = hex::decode(contract_hex)
+                    .with_context(|| format!("Invalid contract hex text: {contract_hex}"))?;
+            }
+        }
+    } else {
+        // Parse contract hex file
+        let contract_hex_text = std::fs::read_to_string(contract)
+            .with_context(|| format!("Failed to read contract hex file: {}", contract))?;
+        bytecode = hex::decode(
+            contract_hex_text
+                .strip_prefix("0x")
+                .unwrap_or(&contract_hex_text),
+        )
+        .with_context(|| format!("Invalid contract hex file: {}", contract))?;
+    }
+    Ok(bytecode)
+}
+
+fn compile(compile_args: &CompileArgs) -> Result<()> {
+    if compile_args.output_dir.is_none() {
+        if let Some(stage) = compile_args.emit.iter().find(|stage| stage.is_binary()) {
+            anyhow::bail!("The {stage} stage is binary, use --output-dir to write it to a file");
+        }
+    }
+    let bytecode = read_contract(&compile_args.contract, compile_args.hex_file)?;
+    let code = Bytecode::new(bytecode.into());
+    let stages = compile_stages(
+        &code,
+        compile_args.spec_id,
+        compile_args.opt_level,
+        &compile_args.emit,
+    )?;
+    let name = Path::new(&compile_args.contract)
+        .file_stem()
+        .filter(|_| !compile_args.contract.starts_with("0x"))
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "contract".to_string());
+    for stage in stages {
+        match &compile_args.output_dir {
+            Some(dir) => {
+                std::fs::create_dir_all(dir).with_context(|| {
+                    format!("Failed to create the output directory: {}", dir.display())
+                })?;
+                let path = dir.join(format!(
+                    "{name}.{}.{}",
+                    stage.stage.name(),
+                    stage.stage.extension()
+                ));
+                std::fs::write(&path, &stage.content).with_context(|| {
+                    format!("Failed to write the stage file: {}", path.display())
+                })?;
+                info!("Emitted the {} stage to {}", stage.stage, path.display());
+            }
+            None => {
+                println!("// ----- {} -----", stage.stage);
+                println!("{}", String::from_utf8_lossy(&stage.content));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn disasm(disasm_args: &DisasmArgs) -> Result<()> {
+    let bytecode = read_contract(&disasm_args.contract, disasm_args.hex_file)?;
+    let Bytecode::EVM(code) = Bytecode::new(bytecode.into()) else {
+        anyhow::bail!("Only the EVM bytecode can be disassembled");
+    };
+    let disassembly = Disassembly::new(&code);
+    print!("{disassembly}");
+    if let Some(path) = &disasm_args.dot {
+        std::fs::write(path, disassembly.to_dot())
+            .with_context(|| format!("Failed to write the DOT file: {}", path.display()))?;
+    }
+    Ok(())
+}
+
+fn asm(asm_args: &AsmArgs) -> Result<()> {
+    let source = std::fs::read_to_string(&asm_args.source).with_context(|| {
+        format!(
+            "Failed to read the assembly file: {}",
+            asm_args.source.display()
+        )
+    })?;
+    let bytecode = assemble(&source)?;
+    match &asm_args.output {
+        Some(path) => std::fs::write(path, &bytecode)
+            .with_context(|| format!("Failed to write the bytecode file: {}", path.display()))?,
+        None => println!("{bytecode}"),
+    }
+    Ok(())
+}
+
+fn t8n(t8n_args: &T8nArgs) -> Result<()> {
+    let spec_id = t8n::parse_fork(&t8n_args.state_fork)?;
+    let input = T8nInput::read(
+        &t8n_args.input_alloc,
+        &t8n_args.input_env,
+        &t8n_args.input_txs,
+    )?;
+    let reward = u64::try_from(t8n_args.state_reward).ok().map(U256::from);
+    let output = t8n::transition(input, spec_id, t8n_args.state_chainid, reward)?;
+    output.write(
+        &t8n_args.output_basedir,
+        &t8n_args.output_result,
+        &t8n_args.output_alloc,
+        t8n_args.output_body.as_deref(),
+    )
+}
+
+fn write_gas_profile(run_args: &RunArgs, profile: &GasProfile) -> Result<()> {
+    if run_args.gas_profile {
+        println!("{}", profile.summary());
+    }
+    if let Some(path) = &run_args.folded_stacks {
+        std::fs::write(path, profile.folded_stacks())
+            .with_context(|| format!("Failed to write the folded stacks file: {}", path))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,163 @@
+use alloy_sol_types::{Panic, Revert, SolCall, SolError, SolValue, sol};
+use dora_primitives::{B256, Bytes, U256};
+use dora_runtime::{
+    ExecutionResult,
+    result::{Output, SuccessReason},
+};
+
+use crate::abi::Abi;
+
+sol! {
+    struct Order {
+        uint8 kind;
+        address maker;
+    }
+
+    function transfer(address to, uint256 amount) external returns (bool);
+    function submit(string name, bytes data, int64[] deltas, bool[2] flags) external;
+    function place(Order[] orders, bytes32 salt) external;
+}
+
+const ALICE: &str = "0x00000000000000000000000000000000000000aa";
+const BOB: &str = "0x00000000000000000000000000000000000000bb";
+
+fn abi(signatures: &[&str]) -> Abi {
+    let mut abi = Abi::default();
+    for signature in signatures {
+        abi.add_signature(signature).unwrap();
+    }
+    abi
+}
+
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(ToString::to_string).collect()
+}
+
+fn success(output: Vec<u8>) -> ExecutionResult {
+    ExecutionResult::Success {
+        reason: SuccessReason::Return,
+        gas_used: 0,
+        gas_refunded: 0,
+        output: Output::Call(output.into()),
+        logs: Vec::new(),
+    }
+}
+
+#[test]
+fn encode_static_arguments() {
+    let abi = abi(&["transfer(address,uint256)"]);
+    let data = abi
+        .encode_call("transfer", &args(&[ALICE, "1000"]))
+        .unwrap();
+    let expected = transferCall {
+        to: ALICE.parse().unwrap(),
+        amount: U256::from(1000),
+    };
+    assert_eq!(data, expected.abi_encode());
+}
+
+#[test]
+fn encode_dynamic_arguments() {
+    let abi = abi(&["submit(string,bytes,int64[],bool[2])"]);
+    let data = abi
+        .encode_call(
+            "submit",
+            &args(&["dora", "0xc0ffee", "[-1, 2, 3]", "[true, false]"]),
+        )
+        .unwrap();
+    let expected = submitCall {
+        name: "dora".to_string(),
+        data: Bytes::from_static(&[0xc0, 0xff, 0xee]),
+        deltas: vec![-1, 2, 3],
+        flags: [true, false],
+    };
+    assert_eq!(data, expected.abi_encode());
+}
+
+#[test]
+fn encode_tuple_array_arguments() {
+    let abi = abi(&["place((uint8,address)[],bytes32)"]);
+    let orders = format!("[(1, {ALICE}), (2, {BOB})]");
+    let salt = format!("0x{}", "11".repeat(32));
+    let data = abi.encode_call("place", &args(&[&orders, &salt])).unwrap();
+    let expected = placeCall {
+        orders: vec![
+            Order {
+                kind: 1,
+                maker: ALICE.parse().unwrap(),
+            },
+            Order {
+                kind: 2,
+                maker: BOB.parse().unwrap(),
+            },
+        ],
+        salt: B256::repeat_byte(0x11),
+    };
+    assert_eq!(data, expected.abi_encode());
+}
+
+#[test]
+fn select_overloaded_function_by_signature() {
+    let abi = abi(&["transfer(address,uint256)", "transfer(address)"]);
+    let data = abi
+        .encode_call("transfer(address,uint256)", &args(&[ALICE, "1"]))
+        .unwrap();
+    assert_eq!(data[..4], transferCall::SELECTOR);
+    let err = abi.encode_call("transfer", &args(&[ALICE])).unwrap_err();
+    assert!(err.to_string().contains("overloaded"), "{err:#}");
+}
+
+#[test]
+fn describe_return_and_revert_data() {
+    let abi = abi(&["function balanceOf(address owner) returns (uint256 balance, string memo)"]);
+    let input = abi.encode_call("balanceOf", &args(&[ALICE])).unwrap();
+    let output = (U256::from(42), "dora".to_string()).abi_encode_params();
+    assert_eq!(
+        abi.describe(&input, &success(output)),
+        vec![r#"Returns: (balance: 42, memo: "dora")"#]
+    );
+
+    let revert = |output: Vec<u8>| ExecutionResult::Revert {
+        gas_used: 0,
+        output: output.into(),
+    };
+    let error = Revert {
+        reason: "nope".to_string(),
+    };
+    assert_eq!(
+        abi.describe(&input, &revert(error.abi_encode())),
+        vec![r#"Reverted: Error("nope")"#]
+    );
+    let panic = Panic {
+        code: U256::from(0x11),
+    };
+    assert!(
+        abi.describe(&input, &revert(panic.abi_encode()))[0].starts_with("Reverted: Panic(0x11)")
+    );
+    assert_eq!(
+        abi.describe(&input, &revert(vec![0xde, 0xad])),
+        vec!["Reverted: 0xdead"]
+    );
+}
+
+#[test]
+fn reject_malformed_arguments() {
+    let abi = abi(&["transfer(address,uint256)"]);
+    let err = |values: &[&str]| {
+        format!(
+            "{:#}",
+            abi.encode_call("transfer", &args(values)).unwrap_err()
+        )
+    };
+    assert!(err(&[ALICE]).contains("Expected 2 arguments, got 1"));
+    assert!(err(&[ALICE, "abc"]).contains("Invalid argument of type uint256: abc"));
+    assert!(err(&["0x1234", "1"]).contains("Invalid argument of type address: 0x1234"));
+    assert!(
+        abi.encode_call("approve", &[])
+            .unwrap_err()
+            .to_string()
+            .contains("not found")
+    );
+    assert!(Abi::default().add_signature("transfer(address").is_err());
+    assert!(Abi::default().encode_constructor(&[]).is_err());
+}
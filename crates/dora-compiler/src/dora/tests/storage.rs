@@ -39,7 +39,12 @@ fn test_storage_memory_cache_code() {
     let context = Context::new();
     let mut module = Module::parse(&context.mlir_context, STORAGE_MEMORY_MAP_CODE).unwrap();
     assert!(module.as_operation().verify());
-    run(&context.mlir_context, &mut module).unwrap();
+    run(
+        &context.mlir_context,
+        &mut module,
+        OptimizationLevel::default(),
+    )
+    .unwrap();
     let _engine = ExecutionEngine::new(&module, OptimizationLevel::default() as usize, &[], false);
     assert!(module.as_operation().verify());
 }
@@ -0,0 +1,804 @@
+//! A small textual EVM assembler, mainly used to write the test contracts.
+//!
+//! The source is line based, everything after `;` or `//` is a comment.
+//!
+//! ```text
+//! ; Instructions are the opcode mnemonics, `PUSH` picks the smallest `PUSH<N>`.
+//!     PUSH 0x2a
+//!     PUSH2 1000
+//! ; `name:` defines a label which is a `JUMPDEST` in the legacy code.
+//! loop:
+//!     PUSH1 1
+//!     SWAP1
+//!     SUB
+//!     DUP1
+//!     PUSH @loop      ; The label offset, `PUSH` picks `PUSH2` for labels.
+//!     JUMPI
+//! ; Macros are expanded textually, `$name` is replaced with the argument and `$$` is replaced
+//! ; with a unique id of every expansion to declare the local labels.
+//! %macro store_ret value
+//!     PUSH $value
+//!     %return_word
+//! %end
+//!     %store_ret 7
+//! ```
+//!
+//! The EOF container is assembled when the source contains the `.code` directive, see below:
+//!
+//! ```text
+//! .code inputs=0 outputs=0x80 max_stack=2   ; The attributes are optional.
+//!     CALLF 1
+//!     DATALOADN 0
+//!     RJUMPI @done
+//!     STOP
+//! done:                                     ; The label is only a position in EOF code.
+//!     RJUMPV @done, @end
+//! end:
+//!     STOP
+//! .code inputs=0 outputs=1
+//!     PUSH0
+//!     RETF
+//! .container 0xef0001...                    ; The sub container bytes.
+//! .data 0x00000000000000000000000000000000000000000000000000000000000000ff
+//! ```
+//!
+//! The max stack size of the EOF code section is calculated from the instructions when the
+//! `max_stack` attribute is omitted.
+
+use std::collections::VecDeque;
+use std::fmt;
+
+use dora_primitives::{Bytes, EofBody, TypesSection};
+use num_bigint::BigUint;
+use rustc_hash::FxHashMap;
+use thiserror::Error;
+
+use super::program::{Opcode, Operation, Program, stack_io};
+
+/// The non-returning marker of the EOF type section outputs.
+const EOF_NON_RETURNING_FUNCTION: u8 = 0x80;
+/// The max nested macro expansion depth.
+const MAX_MACRO_DEPTH: usize = 16;
+/// The built-in macros which are always available.
+const PRELUDE: &str = "
+%macro return_word
+    PUSH1 0
+    MSTORE
+    PUSH1 32
+    PUSH1 0
+    RETURN
+%end
+%macro revert_empty
+    PUSH1 0
+    PUSH1 0
+    REVERT
+%end
+";
+
+/// An assembler error with the source line number starting from 1.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub struct AsmError {
+    /// The source line number, 0 means the error is not related to a specific line.
+    pub line: usize,
+    /// The error message.
+    pub message: String,
+}
+
+impl AsmError {
+    fn new(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.line == 0 {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "line {}: {}", self.line, self.message)
+        }
+    }
+}
+
+type AsmResult<T> = Result<T, AsmError>;
+
+/// Assembles the source into the legacy bytecode, or the EOF container bytes when the source
+/// contains the `.code` directive.
+///
+/// # Example
+///
+/// ```ignore
+/// let code = assemble("PUSH 1\nPUSH 2\nADD\n%return_word")?;
+/// ```
+pub fn assemble(source: &str) -> AsmResult<Bytes> {
+    let assembly = Assembly::parse(source)?;
+    if !assembly.is_eof {
+        let operations = assembly.legacy_operations()?;
+        return Ok(Program::operations_to_opcode(&operations).into());
+    }
+    let mut code_section = Vec::with_capacity(assembly.sections.len());
+    let mut types_section = Vec::with_capacity(assembly.sections.len());
+    for (index, section) in assembly.sections.iter().enumerate() {
+        let operations = assembly.section_operations(section)?;
+        let max_stack_size = match section.max_stack_size {
+            Some(max_stack_size) => max_stack_size,
+            None => assembly.max_stack_size(index, section, &operations)?,
+        };
+        code_section.push(Program::operations_to_opcode(&operations).into());
+        types_section.push(TypesSection {
+            inputs: section.inputs,
+            outputs: section.outputs,
+            max_stack_size,
+        });
+    }
+    let body = EofBody {
+        types_section,
+        code_section,
+        container_section: assembly.containers,
+        data_section: assembly.data,
+        is_data_filled: true,
+    };
+    Ok(body.into_eof().raw)
+}
+
+/// Assembles the legacy source into the operations, which can be used to construct the program
+/// with [`Program::from_operations`].
+pub fn assemble_operations(source: &str) -> AsmResult<Vec<Operation>> {
+    let assembly = Assembly::parse(source)?;
+    if assembly.is_eof {
+        return Err(AsmError::new(
+            0,
+            "the EOF source can not be assembled into the legacy operations",
+        ));
+    }
+    assembly.legacy_operations()
+}
+
+/// The jump target of the label reference or the literal value.
+#[derive(Debug, Clone)]
+enum Target {
+    Label(String),
+    Value(BigUint),
+    Offset(i16),
+}
+
+#[derive(Debug, Clone)]
+enum Immediate {
+    None,
+    Push(u8, Target),
+    U8(u8),
+    U16(u16),
+    Relative(Target),
+    Table(Vec<Target>),
+}
+
+#[derive(Debug, Clone)]
+enum Item {
+    Label(String),
+    Instruction {
+        opcode: Opcode,
+        immediate: Immediate,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct Section {
+    inputs: u8,
+    outputs: u8,
+    max_stack_size: Option<u16>,
+    items: Vec<(usize, Item)>,
+}
+
+impl Section {
+    fn new(index: usize) -> Self {
+        Self {
+            inputs: 0,
+            outputs: if index == 0 {
+                EOF_NON_RETURNING_FUNCTION
+            } else {
+                0
+            },
+            max_stack_size: None,
+            items: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Assembly {
+    is_eof: bool,
+    sections: Vec<Section>,
+    containers: Vec<Bytes>,
+    data: Bytes,
+}
+
+#[derive(Debug, Clone)]
+struct Macro {
+    params: Vec<String>,
+    body: Vec<(usize, String)>,
+}
+
+impl Assembly {
+    fn parse(source: &str) -> AsmResult<Self> {
+        let mut macros = FxHashMap::default();
+        collect_macros(PRELUDE, &mut macros)?;
+        let lines = collect_macros(source, &mut macros)?;
+        let mut expanded = Vec::new();
+        let mut expansion = 0;
+        for (line, text) in lines {
+            expand(line, &text, &macros, 0, &mut expansion, &mut expanded)?;
+        }
+
+        let is_eof = expanded
+            .iter()
+            .any(|(_, text)| directive(text) == Some(".code"));
+        let mut assembly = Assembly {
+            is_eof,
+            sections: if is_eof {
+                vec![]
+            } else {
+                vec![Section::new(0)]
+            },
+            containers: vec![],
+            data: Bytes::new(),
+        };
+        for (line, text) in expanded {
+            assembly.parse_line(line, &text)?;
+        }
+        Ok(assembly)
+    }
+
+    fn parse_line(&mut self, line: usize, text: &str) -> AsmResult<()> {
+        let tokens: Vec<&str> = text
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|token| !token.is_empty())
+            .collect();
+        let Some((&head, args)) = tokens.split_first() else {
+            return Ok(());
+        };
+        if head.starts_with('.') {
+            return self.parse_directive(line, head, args);
+        }
+        let Some(section) = self.sections.last_mut() else {
+            return Err(AsmError::new(
+                line,
+                "the instruction must follow a `.code` directive",
+            ));
+        };
+        if let Some(label) = head.strip_suffix(':') {
+            if !is_identifier(label) {
+                return Err(AsmError::new(line, format!("invalid label name `{label}`")));
+            }
+            section.items.push((line, Item::Label(label.to_string())));
+            return match args.first() {
+                Some(arg) => Err(AsmError::new(
+                    line,
+                    format!("unexpected `{arg}` after the label"),
+                )),
+                None => Ok(()),
+            };
+        }
+        let item = parse_instruction(line, head, args)?;
+        if let Item::Instruction { opcode, .. } = &item {
+            if !self.is_eof && is_eof_only(*opcode) {
+                return Err(AsmError::new(
+                    line,
+                    format!("the EOF opcode {opcode:?} is not allowed in the legacy code"),
+                ));
+            }
+        }
+        section.items.push((line, item));
+        Ok(())
+    }
+
+    fn parse_directive(&mut self, line: usize, head: &str, args: &[&str]) -> AsmResult<()> {
+        match head {
+            ".code" => {
+                let mut section = Section::new(self.sections.len());
+                for arg in args {
+                    let Some((key, value)) = arg.split_once('=') else {
+                        return Err(AsmError::new(
+                            line,
+                            format!("expected `key=value` attribute, found `{arg}`"),
+                        ));
+                    };
+                    match key {
+                        "inputs" => section.inputs = parse_int(line, value)?,
+                        "outputs" => section.outputs = parse_int(line, value)?,
+                        "max_stack" => section.max_stack_size = Some(parse_int(line, value)?),
+                        _ => {
+                            return Err(AsmError::new(
+                                line,
+                                format!("unknown code section attribute `{key}`"),
+                            ));
+                        }
+                    }
+                }
+                self.sections.push(section);
+            }
+            ".container" | ".data" if !self.is_eof => {
+                return Err(AsmError::new(
+                    line,
+                    format!("the `{head}` directive requires the `.code` sections"),
+                ));
+            }
+            ".container" => {
+                let bytes = args
+                    .iter()
+                    .map(|arg| parse_hex(line, arg))
+                    .collect::<AsmResult<Vec<_>>>()?;
+                self.containers.push(bytes.concat().into());
+            }
+            ".data" => {
+                let mut data = self.data.to_vec();
+                for arg in args {
+                    data.extend(parse_hex(line, arg)?);
+                }
+                self.data = data.into();
+            }
+            _ => {
+                return Err(AsmError::new(line, format!("unknown directive `{head}`")));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the size in bytes of the item.
+    fn item_size(&self, item: &Item) -> usize {
+        match item {
+            Item::Label(_) => usize::from(!self.is_eof),
+            Item::Instruction { immediate, .. } => match immediate {
+                Immediate::None => 1,
+                Immediate::Push(n, _) => 1 + *n as usize,
+                Immediate::U8(_) => 2,
+                Immediate::U16(_) | Immediate::Relative(_) => 3,
+                Immediate::Table(targets) => 2 + targets.len() * 2,
+            },
+        }
+    }
+
+    fn legacy_operations(&self) -> AsmResult<Vec<Operation>> {
+        self.section_operations(&self.sections[0])
+    }
+
+    /// Resolves the labels of the section and returns the operations.
+    fn section_operations(&self, section: &Section) -> AsmResult<Vec<Operation>> {
+        let mut labels = FxHashMap::default();
+        let mut pc = 0;
+        for (line, item) in &section.items {
+            if let Item::Label(label) = item {
+                if labels.insert(label.as_str(), pc).is_some() {
+                    return Err(AsmError::new(*line, format!("duplicate label `{label}`")));
+                }
+            }
+            pc += self.item_size(item);
+        }
+        let resolve = |line: usize, label: &str| {
+            labels
+                .get(label)
+                .copied()
+                .ok_or_else(|| AsmError::new(line, format!("undefined label `{label}`")))
+        };
+        let relative = |line: usize, target: &Target, end: usize| -> AsmResult<u16> {
+            match target {
+                Target::Offset(offset) => Ok(*offset as u16),
+                Target::Label(label) => {
+                    let offset = resolve(line, label)? as isize - end as isize;
+                    i16::try_from(offset)
+                        .map(|offset| offset as u16)
+                        .map_err(|_| {
+                            AsmError::new(line, format!("the label `{label}` is out of range"))
+                        })
+                }
+                Target::Value(_) => unreachable!("relative jumps only take offsets or labels"),
+            }
+        };
+
+        let mut operations = Vec::with_capacity(section.items.len());
+        let mut pc = 0;
+        for (line, item) in &section.items {
+            let line = *line;
+            let end = pc + self.item_size(item);
+            let operation = match item {
+                Item::Label(_) if self.is_eof => None,
+                Item::Label(_) => Some(Operation::Jumpdest { pc }),
+                Item::Instruction { opcode, immediate } => Some(match immediate {
+                    Immediate::None => {
+                        let mut at = pc;
+                        Operation::from(*opcode, &[], &mut at, self.is_eof)
+                            .map_err(|err| AsmError::new(line, err.to_string()))?
+                    }
+                    Immediate::Push(n, target) => {
+                        let value = match target {
+                            Target::Label(label) => BigUint::from(resolve(line, label)?),
+                            Target::Value(value) => value.clone(),
+                            Target::Offset(_) => unreachable!("push only takes values or labels"),
+                        };
+                        if value.bits() > *n as u64 * 8 {
+                            return Err(AsmError::new(
+                                line,
+                                format!("the value {value:#x} does not fit in PUSH{n}"),
+                            ));
+                        }
+                        Operation::Push((*n, value))
+                    }
+                    Immediate::U8(x) => match opcode {
+                        Opcode::DUPN => Operation::DupN(*x),
+                        Opcode::SWAPN => Operation::SwapN(*x),
+                        Opcode::EXCHANGE => Operation::Exchange(*x),
+                        Opcode::EOFCREATE => Operation::EofCreate(*x),
+                        Opcode::RETURNCONTRACT => Operation::ReturnContract(*x),
+                        _ => unreachable!("{opcode:?} does not take an u8 immediate"),
+                    },
+                    Immediate::U16(x) => match opcode {
+                        Opcode::DATALOADN => Operation::DataLoadN(*x),
+                        Opcode::CALLF => Operation::CallF(*x),
+                        Opcode::JUMPF => Operation::JumpF(*x),
+                        _ => unreachable!("{opcode:?} does not take an u16 immediate"),
+                    },
+                    Immediate::Relative(target) => {
+                        let offset = relative(line, target, end)?;
+                        match opcode {
+                            Opcode::RJUMP => Operation::RJump(offset),
+                            _ => Operation::RJumpI(offset),
+                        }
+                    }
+                    Immediate::Table(targets) => {
+                        let offsets = targets
+                            .iter()
+                            .map(|target| relative(line, target, end))
+                            .collect::<AsmResult<Vec<_>>>()?;
+                        Operation::RJumpV(((offsets.len() - 1) as u8, offsets))
+                    }
+                }),
+            };
+            operations.extend(operation);
+            pc = end;
+        }
+        Ok(operations)
+    }
+
+    /// Calculates the max stack height of the EOF code section by following the control flow.
+    fn max_stack_size(
+        &self,
+        index: usize,
+        section: &Section,
+        operations: &[Operation],
+    ) -> AsmResult<u16> {
+        let mut pcs = Vec::with_capacity(operations.len());
+        let mut pc = 0;
+        for operation in operations {
+            pcs.push(pc);
+            pc += operation.to().len();
+        }
+        let index_of = |pc: usize| pcs.binary_search(&pc).ok();
+        let mut heights: Vec<Option<usize>> = vec![None; operations.len()];
+        let mut queue = VecDeque::from([(0, section.inputs as usize)]);
+        let mut max = section.inputs as usize;
+        while let Some((i, height)) = queue.pop_front() {
+            let Some(operation) = operations.get(i) else {
+                continue;
+            };
+            if heights[i].is_some_and(|seen| seen >= height) {
+                continue;
+            }
+            heights[i] = Some(height);
+            let (inputs, outputs) = match operation {
+                Operation::DupN(_) => (0, 1),
+                Operation::CallF(callee) => match self.sections.get(*callee as usize) {
+                    Some(callee) if callee.outputs != EOF_NON_RETURNING_FUNCTION => {
+                        (callee.inputs as usize, callee.outputs as usize)
+                    }
+                    _ => (0, 0),
+                },
+                operation => {
+                    let (inputs, outputs) = stack_io(operation);
+                    (inputs as usize, outputs as usize)
+                }
+            };
+            let next = (height + outputs).checked_sub(inputs).ok_or_else(|| {
+                AsmError::new(0, format!("stack underflow in the code section {index}"))
+            })?;
+            max = max.max(next);
+            if max > 1024 {
+                return Err(AsmError::new(
+                    0,
+                    format!("stack overflow in the code section {index}"),
+                ));
+            }
+            let end = pcs[i] + operation.to().len();
+            let jump = |offset: &u16| index_of((end as isize + *offset as i16 as isize) as usize);
+            let mut successors = vec![];
+            match operation {
+                Operation::RJump(offset) => successors.extend(jump(offset)),
+                Operation::RJumpI(offset) => {
+                    successors.extend(jump(offset));
+                    successors.push(i + 1);
+                }
+                Operation::RJumpV((_, offsets)) => {
+                    successors.extend(offsets.iter().filter_map(jump));
+                    successors.push(i + 1);
+                }
+                Operation::Stop
+                | Operation::Return
+                | Operation::Revert
+                | Operation::Invalid
+                | Operation::RetF
+                | Operation::JumpF(_)
+                | Operation::ReturnContract(_) => {}
+                _ => successors.push(i + 1),
+            }
+            queue.extend(successors.into_iter().map(|i| (i, next)));
+        }
+        Ok(max as u16)
+    }
+}
+
+fn is_eof_only(opcode: Opcode) -> bool {
+    matches!(
+        opcode,
+        Opcode::DATALOAD
+            | Opcode::DATALOADN
+            | Opcode::DATASIZE
+            | Opcode::DATACOPY
+            | Opcode::RJUMP
+            | Opcode::RJUMPI
+            | Opcode::RJUMPV
+            | Opcode::CALLF
+            | Opcode::RETF
+            | Opcode::JUMPF
+            | Opcode::DUPN
+            | Opcode::SWAPN
+            | Opcode::EXCHANGE
+            | Opcode::EOFCREATE
+            | Opcode::RETURNCONTRACT
+            | Opcode::RETURNDATALOAD
+            | Opcode::EXTCALL
+            | Opcode::EXTDELEGATECALL
+            | Opcode::EXTSTATICCALL
+    )
+}
+
+fn parse_instruction(line: usize, mnemonic: &str, args: &[&str]) -> AsmResult<Item> {
+    let upper = mnemonic.to_ascii_uppercase();
+    let expect_args = |count: usize| {
+        if args.len() == count {
+            Ok(())
+        } else {
+            Err(AsmError::new(
+                line,
+                format!("{upper} expects {count} argument(s), found {}", args.len()),
+            ))
+        }
+    };
+    // `PUSH` without the size picks the smallest `PUSH<N>`, and `PUSH2` for the labels.
+    if upper == "PUSH" {
+        expect_args(1)?;
+        let target = parse_target(line, args[0])?;
+        let n = match &target {
+            Target::Label(_) => 2,
+            Target::Value(value) => (value.bits().div_ceil(8)).max(1) as u8,
+            Target::Offset(_) => unreachable!("push target is not an offset"),
+        };
+        return Ok(Item::Instruction {
+            opcode: push_opcode(n),
+            immediate: Immediate::Push(n, target),
+        });
+    }
+    let opcode = lookup_opcode(&upper)
+        .ok_or_else(|| AsmError::new(line, format!("unknown mnemonic `{mnemonic}`")))?;
+    let immediate = match opcode {
+        opcode if Opcode::PUSH1 as u8 <= opcode as u8 && opcode as u8 <= Opcode::PUSH32 as u8 => {
+            expect_args(1)?;
+            let n = opcode as u8 - Opcode::PUSH0 as u8;
+            Immediate::Push(n, parse_target(line, args[0])?)
+        }
+        Opcode::DUPN
+        | Opcode::SWAPN
+        | Opcode::EXCHANGE
+        | Opcode::EOFCREATE
+        | Opcode::RETURNCONTRACT => {
+            expect_args(1)?;
+            Immediate::U8(parse_int(line, args[0])?)
+        }
+        Opcode::DATALOADN | Opcode::CALLF | Opcode::JUMPF => {
+            expect_args(1)?;
+            Immediate::U16(parse_int(line, args[0])?)
+        }
+        Opcode::RJUMP | Opcode::RJUMPI => {
+            expect_args(1)?;
+            Immediate::Relative(parse_relative(line, args[0])?)
+        }
+        Opcode::RJUMPV => {
+            if args.is_empty() || args.len() > 256 {
+                return Err(AsmError::new(line, "RJUMPV expects 1 to 256 jump targets"));
+            }
+            Immediate::Table(
+                args.iter()
+                    .map(|arg| parse_relative(line, arg))
+                    .collect::<AsmResult<_>>()?,
+            )
+        }
+        _ => {
+            expect_args(0)?;
+            Immediate::None
+        }
+    };
+    Ok(Item::Instruction { opcode, immediate })
+}
+
+fn lookup_opcode(mnemonic: &str) -> Option<Opcode> {
+    (0..=u8::MAX)
+        .filter_map(|byte| Opcode::try_from(byte).ok())
+        .find(|opcode| format!("{opcode:?}") == mnemonic)
+}
+
+fn push_opcode(n: u8) -> Opcode {
+    Opcode::try_from(Opcode::PUSH0 as u8 + n).expect("PUSH1 to PUSH32 are valid opcodes")
+}
+
+fn parse_target(line: usize, arg: &str) -> AsmResult<Target> {
+    match arg.strip_prefix('@') {
+        Some(label) if is_identifier(label) => Ok(Target::Label(label.to_string())),
+        Some(label) => Err(AsmError::new(line, format!("invalid label name `{label}`"))),
+        None => parse_number(arg)
+            .map(Target::Value)
+            .ok_or_else(|| AsmError::new(line, format!("invalid number `{arg}`"))),
+    }
+}
+
+fn parse_relative(line: usize, arg: &str) -> AsmResult<Target> {
+    if arg.starts_with('@') {
+        return parse_target(line, arg);
+    }
+    arg.parse::<i16>()
+        .map(Target::Offset)
+        .map_err(|_| AsmError::new(line, format!("invalid relative offset `{arg}`")))
+}
+
+fn parse_number(arg: &str) -> Option<BigUint> {
+    let value = match arg.strip_prefix("0x") {
+        Some(hex) => BigUint::parse_bytes(hex.as_bytes(), 16)?,
+        None => BigUint::parse_bytes(arg.as_bytes(), 10)?,
+    };
+    (value.bits() <= 256).then_some(value)
+}
+
+fn parse_int<T: TryFrom<u64>>(line: usize, arg: &str) -> AsmResult<T> {
+    parse_number(arg)
+        .and_then(|value| u64::try_from(&value).ok())
+        .and_then(|value| T::try_from(value).ok())
+        .ok_or_else(|| AsmError::new(line, format!("invalid or out of range number `{arg}`")))
+}
+
+fn parse_hex(line: usize, arg: &str) -> AsmResult<Vec<u8>> {
+    hex::decode(arg.strip_prefix("0x").unwrap_or(arg))
+        .map_err(|err| AsmError::new(line, format!("invalid hex bytes `{arg}`: {err}")))
+}
+
+fn is_identifier(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+}
+
+fn directive(text: &str) -> Option<&str> {
+    text.split_whitespace()
+        .next()
+        .filter(|head| head.starts_with('.'))
+}
+
+fn strip_comment(text: &str) -> &str {
+    let end = [text.find(';'), text.find("//")]
+        .into_iter()
+        .flatten()
+        .min()
+        .unwrap_or(text.len());
+    text[..end].trim()
+}
+
+/// Collects the macro definitions into `macros` and returns the remaining non-empty lines.
+fn collect_macros(
+    source: &str,
+    macros: &mut FxHashMap<String, Macro>,
+) -> AsmResult<Vec<(usize, String)>> {
+    let mut lines = Vec::new();
+    let mut current: Option<(usize, String, Macro)> = None;
+    for (i, text) in source.lines().enumerate() {
+        let line = i + 1;
+        let text = strip_comment(text);
+        if text.is_empty() {
+            continue;
+        }
+        let mut tokens = text.split_whitespace();
+        match tokens.next() {
+            Some("%macro") => {
+                if current.is_some() {
+                    return Err(AsmError::new(line, "nested macro definition"));
+                }
+                let name = tokens
+                    .next()
+                    .filter(|name| is_identifier(name))
+                    .ok_or_else(|| AsmError::new(line, "expected the macro name"))?;
+                let params = tokens
+                    .map(|param| param.strip_prefix('$').unwrap_or(param).to_string())
+                    .collect();
+                current = Some((
+                    line,
+                    name.to_string(),
+                    Macro {
+                        params,
+                        body: vec![],
+                    },
+                ));
+            }
+            Some("%end") => {
+                let (_, name, definition) = current
+                    .take()
+                    .ok_or_else(|| AsmError::new(line, "`%end` without `%macro`"))?;
+                macros.insert(name, definition);
+            }
+            _ => match &mut current {
+                Some((_, _, definition)) => definition.body.push((line, text.to_string())),
+                None => lines.push((line, text.to_string())),
+            },
+        }
+    }
+    if let Some((line, name, _)) = current {
+        return Err(AsmError::new(line, format!("unterminated macro `{name}`")));
+    }
+    Ok(lines)
+}
+
+/// Expands the macro invocations of the line into `output`, the expanded lines keep the line
+/// number of the outermost invocation.
+fn expand(
+    line: usize,
+    text: &str,
+    macros: &FxHashMap<String, Macro>,
+    depth: usize,
+    expansion: &mut usize,
+    output: &mut Vec<(usize, String)>,
+) -> AsmResult<()> {
+    let Some(invocation) = text.strip_prefix('%') else {
+        output.push((line, text.to_string()));
+        return Ok(());
+    };
+    if depth >= MAX_MACRO_DEPTH {
+        return Err(AsmError::new(line, "the macro expansion is too deep"));
+    }
+    let mut tokens = invocation.split_whitespace();
+    let name = tokens.next().unwrap_or_default();
+    let args: Vec<&str> = tokens.collect();
+    let definition = macros
+        .get(name)
+        .ok_or_else(|| AsmError::new(line, format!("undefined macro `{name}`")))?;
+    if args.len() != definition.params.len() {
+        return Err(AsmError::new(
+            line,
+            format!(
+                "the macro `{name}` expects {} argument(s), found {}",
+                definition.params.len(),
+                args.len()
+            ),
+        ));
+    }
+    *expansion += 1;
+    let id = expansion.to_string();
+    // Substitute the longer names first, so that `$ab` is not replaced by the `$a` argument.
+    let mut bindings: Vec<(&String, &str)> = definition.params.iter().zip(args).collect();
+    bindings.sort_by_key(|(param, _)| std::cmp::Reverse(param.len()));
+    for (_, body) in &definition.body {
+        let mut body = body.replace("$$", &id);
+        for (param, arg) in &bindings {
+            body = body.replace(&format!("${param}"), arg);
+        }
+        expand(line, &body, macros, depth + 1, expansion, output)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,444 @@
+//! EVM bytecode disassembler with the control-flow annotations.
+//!
+//! The bytecode is parsed into [`Operation`]s by [`Program`], and each code section is split into
+//! the basic blocks. The static jump targets i.e., `PUSH` followed by `JUMP[I]` for the legacy
+//! bytecode and the relative jumps for the EOF bytecode are resolved into the block successors.
+
+use std::collections::BTreeSet;
+use std::fmt::{self, Write};
+use std::sync::Arc;
+
+use dora_primitives::{EVMBytecode, Eof, TypesSection};
+
+use super::program::{Opcode, Operation, Program};
+
+/// The non-returning marker of the EOF type section outputs.
+const EOF_NON_RETURNING_FUNCTION: u8 = 0x80;
+
+/// A disassembled instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    /// The program counter relative to the start of the code section.
+    pub pc: usize,
+    /// The size in bytes of the instruction including the immediates.
+    pub size: usize,
+    /// The raw opcode byte.
+    pub opcode: u8,
+    /// The parsed operation, the unknown opcodes are parsed as [`Operation::Invalid`].
+    pub operation: Operation,
+}
+
+impl Instruction {
+    /// Returns the mnemonic of the instruction, e.g. `PUSH1`.
+    pub fn mnemonic(&self) -> String {
+        match Opcode::try_from(self.opcode) {
+            Ok(opcode) => format!("{opcode:?}"),
+            Err(_) => format!("UNKNOWN(0x{:02x})", self.opcode),
+        }
+    }
+
+    /// Returns the immediates of the instruction, e.g. `0x80` of `PUSH1 0x80`.
+    pub fn immediates(&self) -> Option<String> {
+        match &self.operation {
+            Operation::Push((n, value)) => {
+                Some(format!("0x{:0width$x}", value, width = *n as usize * 2))
+            }
+            Operation::DataLoadN(offset) => Some(format!("0x{offset:04x}")),
+            Operation::RJump(offset) | Operation::RJumpI(offset) => {
+                Some(format!("{:+}", *offset as i16))
+            }
+            Operation::RJumpV((_, offsets)) => Some(
+                offsets
+                    .iter()
+                    .map(|offset| format!("{:+}", *offset as i16))
+                    .collect::<Vec<_>>()
+                    .join(","),
+            ),
+            Operation::CallF(section) | Operation::JumpF(section) => Some(section.to_string()),
+            Operation::DupN(n)
+            | Operation::SwapN(n)
+            | Operation::Exchange(n)
+            | Operation::EofCreate(n)
+            | Operation::ReturnContract(n) => Some(n.to_string()),
+            _ => None,
+        }
+    }
+
+    /// Returns whether the instruction terminates the basic block.
+    pub fn is_terminator(&self) -> bool {
+        matches!(
+            self.operation,
+            Operation::Stop
+                | Operation::Return
+                | Operation::Revert
+                | Operation::Invalid
+                | Operation::Selfdestruct
+                | Operation::Jump
+                | Operation::JumpI
+                | Operation::RJump(_)
+                | Operation::RJumpI(_)
+                | Operation::RJumpV(_)
+                | Operation::RetF
+                | Operation::JumpF(_)
+                | Operation::ReturnContract(_)
+        )
+    }
+
+    /// Returns whether the execution may continue with the next instruction.
+    fn falls_through(&self) -> bool {
+        !self.is_terminator()
+            || matches!(
+                self.operation,
+                Operation::JumpI | Operation::RJumpI(_) | Operation::RJumpV(_)
+            )
+    }
+
+    /// Returns the relative jump targets of the EOF instruction.
+    fn relative_targets(&self) -> Vec<usize> {
+        let end = (self.pc + self.size) as isize;
+        let offsets = match &self.operation {
+            Operation::RJump(offset) | Operation::RJumpI(offset) => vec![*offset],
+            Operation::RJumpV((_, offsets)) => offsets.clone(),
+            _ => return vec![],
+        };
+        offsets
+            .into_iter()
+            .map(|offset| (end + offset as i16 as isize).max(0) as usize)
+            .collect()
+    }
+}
+
+/// A basic block, which is a straight-line instruction sequence with a single entry and exit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BasicBlock {
+    /// The program counter of the first instruction.
+    pub start: usize,
+    /// The index range of the instructions in the code section.
+    pub instructions: std::ops::Range<usize>,
+    /// Whether the block starts with a `JUMPDEST`.
+    pub is_jumpdest: bool,
+    /// The program counters of the successor blocks in the same code section.
+    pub successors: Vec<usize>,
+    /// Whether the block ends with a jump whose target can not be resolved statically.
+    pub dynamic_jump: bool,
+    /// The static jump target which is not a valid jump destination.
+    pub invalid_jump: Option<usize>,
+    /// The code section called or jumped to by the last instruction, i.e. `CALLF` or `JUMPF`.
+    pub section_call: Option<usize>,
+}
+
+/// A disassembled code section, the legacy bytecode has a single code section.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeSection {
+    /// The code section index.
+    pub index: usize,
+    /// The EOF type of the code section.
+    pub types: Option<TypesSection>,
+    /// The instructions.
+    pub instructions: Vec<Instruction>,
+    /// The basic blocks ordered by the program counter.
+    pub blocks: Vec<BasicBlock>,
+}
+
+impl CodeSection {
+    fn new(index: usize, code: &[u8], eof: Option<&Arc<Eof>>) -> Self {
+        let program = Program::from_opcodes(code, eof.cloned());
+        let operations = program.operations();
+        let instructions: Vec<Instruction> = operations
+            .iter()
+            .enumerate()
+            .map(|(i, operation)| {
+                let pc = program.pc(i);
+                let end = if i + 1 < operations.len() {
+                    program.pc(i + 1)
+                } else {
+                    code.len()
+                };
+                Instruction {
+                    pc,
+                    size: end - pc,
+                    opcode: code[pc],
+                    operation: operation.clone(),
+                }
+            })
+            .collect();
+        let types = eof.and_then(|eof| eof.body.types_section.get(index).copied());
+        let blocks = Self::split_blocks(&instructions, eof.is_some());
+        Self {
+            index,
+            types,
+            instructions,
+            blocks,
+        }
+    }
+
+    fn split_blocks(instructions: &[Instruction], is_eof: bool) -> Vec<BasicBlock> {
+        let jumpdests: BTreeSet<usize> = instructions
+            .iter()
+            .filter(|inst| matches!(inst.operation, Operation::Jumpdest { .. }))
+            .map(|inst| inst.pc)
+            .collect();
+        let mut leaders = BTreeSet::from([0]);
+        leaders.extend(jumpdests.iter().copied());
+        for inst in instructions {
+            // The `CALLF` returns to the next instruction, it also ends the block to make the
+            // call edge explicit.
+            if inst.is_terminator() || matches!(inst.operation, Operation::CallF(_)) {
+                leaders.insert(inst.pc + inst.size);
+            }
+            leaders.extend(inst.relative_targets());
+        }
+
+        let mut blocks = Vec::new();
+        let mut first = 0;
+        for (i, inst) in instructions.iter().enumerate() {
+            let last = instructions
+                .get(i + 1)
+                .is_none_or(|next| leaders.contains(&next.pc));
+            if !last {
+                continue;
+            }
+            let start = instructions[first].pc;
+            let mut block = BasicBlock {
+                start,
+                instructions: first..i + 1,
+                is_jumpdest: jumpdests.contains(&start),
+                successors: Vec::new(),
+                dynamic_jump: false,
+                invalid_jump: None,
+                section_call: None,
+            };
+            match &inst.operation {
+                Operation::Jump | Operation::JumpI if !is_eof => {
+                    let target = if i > first {
+                        Self::static_target(&instructions[i - 1])
+                    } else {
+                        None
+                    };
+                    match target {
+                        Some(target) if jumpdests.contains(&target) => {
+                            block.successors.push(target)
+                        }
+                        Some(target) => block.invalid_jump = Some(target),
+                        None => block.dynamic_jump = true,
+                    }
+                }
+                Operation::CallF(section) | Operation::JumpF(section) => {
+                    block.section_call = Some(*section as usize);
+                }
+                _ => block.successors.extend(inst.relative_targets()),
+            }
+            if inst.falls_through() {
+                if let Some(next) = instructions.get(i + 1) {
+                    block.successors.push(next.pc);
+                }
+            }
+            block.successors.dedup();
+            blocks.push(block);
+            first = i + 1;
+        }
+        blocks
+    }
+
+    /// Returns the pushed value as the jump target.
+    fn static_target(inst: &Instruction) -> Option<usize> {
+        match &inst.operation {
+            Operation::Push0 => Some(0),
+            Operation::Push((_, value)) => usize::try_from(value).ok(),
+            _ => None,
+        }
+    }
+}
+
+/// The disassembly of the EVM bytecode.
+///
+/// # Example
+///
+/// ```ignore
+/// let disasm = Disassembly::new(&bytecode);
+/// println!("{disasm}");
+/// std::fs::write("cfg.dot", disasm.to_dot())?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct Disassembly {
+    /// The disassembled code sections.
+    pub sections: Vec<CodeSection>,
+    /// The EOF container, `None` for the legacy bytecode.
+    pub eof: Option<Arc<Eof>>,
+}
+
+impl Disassembly {
+    /// Disassembles the legacy or EOF bytecode.
+    pub fn new(bytecode: &EVMBytecode) -> Self {
+        match bytecode.eof() {
+            Some(eof) => Self::from_eof(eof),
+            None => Self::from_legacy(bytecode.original_byte_slice()),
+        }
+    }
+
+    /// Disassembles the legacy bytecode.
+    pub fn from_legacy(code: &[u8]) -> Self {
+        Self {
+            sections: vec![CodeSection::new(0, code, None)],
+            eof: None,
+        }
+    }
+
+    /// Disassembles each code section of the EOF container.
+    pub fn from_eof(eof: &Arc<Eof>) -> Self {
+        let sections = eof
+            .body
+            .code_section
+            .iter()
+            .enumerate()
+            .map(|(index, code)| CodeSection::new(index, code, Some(eof)))
+            .collect();
+        Self {
+            sections,
+            eof: Some(eof.clone()),
+        }
+    }
+
+    /// Exports the control-flow graph in the Graphviz DOT format, each code section is a cluster,
+    /// and the `CALLF` and `JUMPF` edges to the other sections are dashed.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::new();
+        let _ = writeln!(dot, "digraph cfg {{");
+        let _ = writeln!(dot, "    node [shape=box, fontname=\"monospace\"];");
+        for section in &self.sections {
+            let index = section.index;
+            let _ = writeln!(dot, "    subgraph cluster_{index} {{");
+            let _ = writeln!(dot, "        label=\"code section {index}\";");
+            for block in &section.blocks {
+                let mut label = String::new();
+                for inst in &section.instructions[block.instructions.clone()] {
+                    let _ = write!(label, "{:04x}: {}", inst.pc, inst.mnemonic());
+                    if let Some(immediates) = inst.immediates() {
+                        let _ = write!(label, " {immediates}");
+                    }
+                    label.push_str("\\l");
+                }
+                let _ = writeln!(
+                    dot,
+                    "        {} [label=\"{label}\"];",
+                    node_id(index, block.start)
+                );
+            }
+            let _ = writeln!(dot, "    }}");
+            for block in &section.blocks {
+                let from = node_id(index, block.start);
+                for successor in &block.successors {
+                    let _ = writeln!(dot, "    {from} -> {};", node_id(index, *successor));
+                }
+                if block.dynamic_jump {
+                    let _ = writeln!(dot, "    {from} -> dynamic_{index} [style=dotted];");
+                }
+                if let Some(target) = block.section_call {
+                    let _ = writeln!(dot, "    {from} -> {} [style=dashed];", node_id(target, 0));
+                }
+            }
+            if section.blocks.iter().any(|block| block.dynamic_jump) {
+                let _ = writeln!(
+                    dot,
+                    "    dynamic_{index} [label=\"dynamic jump\", shape=ellipse];"
+                );
+            }
+        }
+        let _ = writeln!(dot, "}}");
+        dot
+    }
+}
+
+#[inline]
+fn node_id(section: usize, pc: usize) -> String {
+    format!("s{section}_{pc:04x}")
+}
+
+impl fmt::Display for Disassembly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(eof) = &self.eof {
+            let body = &eof.body;
+            writeln!(
+                f,
+                "; EOF container: {} code sections, {} container sections, {} data bytes",
+                body.code_section.len(),
+                body.container_section.len(),
+                body.data_section.len()
+            )?;
+            writeln!(f, "; types section")?;
+            for (index, types) in body.types_section.iter().enumerate() {
+                writeln!(f, ";   {index}: {}", format_types(types))?;
+            }
+        }
+        for section in &self.sections {
+            if self.eof.is_some() {
+                write!(f, "\n; code section {}", section.index)?;
+                if let Some(types) = &section.types {
+                    write!(f, " ({})", format_types(types))?;
+                }
+                writeln!(f)?;
+            }
+            for block in &section.blocks {
+                write!(f, "\nblock_{:04x}:", block.start)?;
+                if block.is_jumpdest {
+                    write!(f, " ; jumpdest")?;
+                }
+                writeln!(f)?;
+                for inst in &section.instructions[block.instructions.clone()] {
+                    write!(f, "  {:04x}  {}", inst.pc, inst.mnemonic())?;
+                    if let Some(immediates) = inst.immediates() {
+                        write!(f, " {immediates}")?;
+                    }
+                    writeln!(f)?;
+                }
+                let mut edges: Vec<String> = block
+                    .successors
+                    .iter()
+                    .map(|pc| format!("block_{pc:04x}"))
+                    .collect();
+                if block.dynamic_jump {
+                    edges.push("<dynamic>".to_string());
+                }
+                if let Some(target) = block.invalid_jump {
+                    edges.push(format!("<invalid jump destination 0x{target:04x}>"));
+                }
+                if let Some(target) = block.section_call {
+                    edges.push(format!("<code section {target}>"));
+                }
+                if !edges.is_empty() {
+                    writeln!(f, "  ; -> {}", edges.join(", "))?;
+                }
+            }
+        }
+        if let Some(eof) = &self.eof {
+            let body = &eof.body;
+            for (index, container) in body.container_section.iter().enumerate() {
+                writeln!(
+                    f,
+                    "\n; container section {index} ({} bytes)\n0x{}",
+                    container.len(),
+                    hex::encode(container)
+                )?;
+            }
+            writeln!(
+                f,
+                "\n; data section ({} bytes)\n0x{}",
+                body.data_section.len(),
+                hex::encode(&body.data_section)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn format_types(types: &TypesSection) -> String {
+    let outputs = if types.outputs == EOF_NON_RETURNING_FUNCTION {
+        "non-returning".to_string()
+    } else {
+        types.outputs.to_string()
+    };
+    format!(
+        "inputs {}, outputs {outputs}, max stack height {}",
+        types.inputs, types.max_stack_size
+    )
+}
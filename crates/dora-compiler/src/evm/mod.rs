@@ -1,4 +1,4 @@
-use dora_primitives::SpecId;
+use dora_primitives::{OptimizationLevel, SpecId};
 use dora_runtime::ExitStatusCode;
 use dora_runtime::constants::env::DORA_TRACING;
 use dora_runtime::{
@@ -35,8 +35,10 @@ use crate::evm::program::Operation;
 use crate::intrinsics::Intrinsics;
 use crate::module::Module as MLIRModule;
 use crate::value::ToContextValue;
+pub mod asm;
 pub mod backend;
 pub(crate) mod conversion;
+pub mod disasm;
 pub(crate) mod instructions;
 pub mod pass;
 pub mod program;
@@ -242,6 +244,17 @@ impl<'c> EVMCompiler<'c> {
         } else {
             ctx.operation_blocks[index]
         };
+        // Note that the shared op functions do not know the program counter of the operation,
+        // thus the tracing call is emitted at their call sites instead.
+        if opts.tracing && !ctx.operation_blocks.is_empty() {
+            Self::tracing_block(
+                ctx,
+                start_block,
+                op,
+                ctx.program.pc(index),
+                op_info.base_gas(),
+            )?;
+        }
 
         // Note: make opcode not found as the runtime halt error,
         // because normal opcodes still consumes GAS during runtime.
@@ -261,7 +274,7 @@ impl<'c> EVMCompiler<'c> {
 
         // Static gas metering needs to be done before stack checking.
         if opts.gas_metering {
-            op_start_block = Self::gas_metering_block(ctx, region, op_start_block, op, &op_info)?;
+            op_start_block = Self::gas_metering_block(ctx, region, op_start_block, &op_info)?;
         }
 
         // Stack overflow/underflow check.
@@ -495,51 +508,63 @@ impl<'c> EVMCompiler<'c> {
         Ok(end_block)
     }
 
+    /// Emits the runtime tracing call which reports the opcode, the program counter, the remaining gas,
+    /// the static gas cost and the stack before the operation is executed.
+    fn tracing_block(
+        ctx: &CtxType<'c>,
+        block: BlockRef<'_, 'c>,
+        op: &Operation,
+        pc: usize,
+        gas_cost: u64,
+    ) -> Result<()> {
+        let builder = OpBuilder::new_with_block(ctx.context, block);
+        let uint8 = builder.i8_ty();
+        let uint64 = builder.i64_ty();
+        let gas_counter = builder.make(builder.load(ctx.values.gas_counter_ptr, uint64))?;
+        let opcode = builder.make(builder.iconst(uint8, op.opcode() as i64))?;
+        let pc = builder.make(builder.iconst_64(pc as i64))?;
+        let gas_cost = builder.make(builder.iconst_64(gas_cost as i64))?;
+        builder.create(func::call(
+            builder.context(),
+            FlatSymbolRefAttribute::new(builder.context(), runtime_symbols::TRACING),
+            &[
+                ctx.values.syscall_ctx,
+                opcode,
+                pc,
+                gas_counter,
+                gas_cost,
+                ctx.values.stack_ptr,
+                ctx.values.stack_size_ptr,
+            ],
+            &[],
+            builder.get_insert_location(),
+        ));
+        Ok(())
+    }
+
     fn gas_metering_block<'r>(
         ctx: &mut CtxType<'c>,
         region: &'r Region<'c>,
         gas_check_block: BlockRef<'r, 'c>,
-        op: &Operation,
         op_info: &OpcodeInfo,
     ) -> Result<BlockRef<'r, 'c>> {
         let base_gas = op_info.base_gas();
         let end_block = region.append_block(Block::new(&[]));
         let update_gas_remaining_block = region.append_block(Block::new(&[]));
         let builder = OpBuilder::new_with_block(ctx.context, gas_check_block);
-        let uint8 = builder.i8_ty();
         let uint64 = builder.i64_ty();
         let location = builder.get_insert_location();
         // Get address of gas counter global
         let gas_counter = builder.make(builder.load(ctx.values.gas_counter_ptr, uint64))?;
         let gas_value = builder.make(builder.iconst_64(base_gas as i64))?;
-        if std::env::var(DORA_TRACING).is_ok() {
-            let opcode = builder
-                .create(builder.iconst(uint8, op.opcode() as i64))
-                .result(0)?
-                .into();
-            builder.create(func::call(
-                builder.context(),
-                FlatSymbolRefAttribute::new(builder.context(), runtime_symbols::TRACING),
-                &[
-                    ctx.values.syscall_ctx,
-                    opcode,
-                    gas_counter,
-                    ctx.values.stack_ptr,
-                    ctx.values.stack_size_ptr,
-                ],
-                &[],
-                builder.get_insert_location(),
-            ));
-        } else {
-            // FIXME : Insert an empty FFI interface to prevent inline optimization of gas registers
-            builder.create(func::call(
-                builder.context(),
-                FlatSymbolRefAttribute::new(builder.context(), runtime_symbols::NOP),
-                &[],
-                &[],
-                builder.get_insert_location(),
-            ));
-        }
+        // FIXME : Insert an empty FFI interface to prevent inline optimization of gas registers
+        builder.create(func::call(
+            builder.context(),
+            FlatSymbolRefAttribute::new(builder.context(), runtime_symbols::NOP),
+            &[],
+            &[],
+            builder.get_insert_location(),
+        ));
         let flag = builder.make(arith::cmpi(
             builder.context(),
             arith::CmpiPredicate::Uge,
@@ -632,6 +657,7 @@ impl<'c> EVMCompiler<'c> {
         } else {
             // Generate opcode functions for the program
             let op_funcs = EVMCompiler::generate_op_functions(self.ctx, program, &self.opts)?;
+            let op_infos = op_info_map(self.opts.spec_id);
             let mut result = last_block
                 .append_operation(arith::constant(
                     context,
@@ -686,6 +712,15 @@ impl<'c> EVMCompiler<'c> {
                     last_block = end_block;
                 } else {
                     let start_block = ctx.operation_blocks[i];
+                    if self.opts.tracing {
+                        Self::tracing_block(
+                            &ctx,
+                            start_block,
+                            op,
+                            ctx.program.pc(i),
+                            op_infos[op.opcode()].base_gas(),
+                        )?;
+                    }
                     let builder = OpBuilder::new_with_block(context, start_block);
                     let is_stop = last_block
                         .append_operation(arith::cmpi(
@@ -802,6 +837,12 @@ pub struct EVMCompileOptions {
     pub stack_bound_checks: bool,
     /// Use common op functions instead of inlining everything
     pub inline: bool,
+    /// Emit a runtime tracing call before each operation, which is used by the runtime inspector
+    /// to observe every execution step. Defaults to `true` when the `DORA_TRACING` environment
+    /// variable is set.
+    pub tracing: bool,
+    /// The optimization level of the MLIR pass pipeline and the LLVM code generation.
+    pub opt_level: OptimizationLevel,
 }
 
 impl Default for EVMCompileOptions {
@@ -811,6 +852,8 @@ impl Default for EVMCompileOptions {
             gas_metering: true,
             stack_bound_checks: true,
             inline: false,
+            tracing: std::env::var(DORA_TRACING).is_ok(),
+            opt_level: OptimizationLevel::default(),
         }
     }
 }
@@ -833,6 +876,29 @@ impl EVMCompileOptions {
         self.stack_bound_checks = stack_bound_checks;
         self
     }
+
+    /// Set whether to emit the runtime tracing call for each operation.
+    pub fn tracing(mut self, tracing: bool) -> Self {
+        self.tracing = tracing;
+        self
+    }
+
+    /// Set the optimization level.
+    pub fn opt_level(mut self, opt_level: OptimizationLevel) -> Self {
+        self.opt_level = opt_level;
+        self
+    }
+
+    /// Returns the stable fingerprint of the options which affect the generated code, it is used
+    /// as the part of the artifact cache key. Note that the spec ID is not included, which is an
+    /// individual part of the key.
+    pub fn fingerprint(&self) -> u64 {
+        (self.gas_metering as u64)
+            | ((self.stack_bound_checks as u64) << 1)
+            | ((self.inline as u64) << 2)
+            | ((self.tracing as u64) << 3)
+            | ((self.opt_level as u64) << 8)
+    }
 }
 
 /// The [`CtxValues`] struct encapsulates values specific to the EVM context, such as those used for
@@ -427,12 +427,13 @@ macro_rules! operations {
                         opcode_bytes
                     },
                     Operation::RJumpV((l, x)) => {
-                        if *l == 0 {
-                            panic!("Invalid RJumpV max index: 0");
+                        if x.len() != *l as usize + 1 {
+                            panic!("Invalid RJumpV max index: {}", l);
                         }
-                        let len = 1 + (*l as usize + 1) * 2;
+                        let len = 2 + (*l as usize + 1) * 2;
                         let mut opcode_bytes = vec![0; len];
                         opcode_bytes[0] = Opcode::RJUMPV as u8;
+                        opcode_bytes[1] = *l;
                         let bytes: Vec<u8> = x.iter().flat_map(|&value| value.to_be_bytes()).collect();
                         opcode_bytes[len - bytes.len()..].copy_from_slice(&bytes);
                         opcode_bytes
@@ -940,6 +941,8 @@ pub struct Program {
     eof: Option<Arc<Eof>>,
     /// Mapping from program counter to instruction
     pc_to_index_mapping: FxHashMap<usize, usize>,
+    /// Mapping from instruction to program counter
+    index_to_pc_mapping: Vec<usize>,
 }
 
 impl From<Bytecode> for Program {
@@ -960,7 +963,8 @@ impl Program {
     /// # Returns
     /// A `Program` instance constructed from the parsed operations.
     pub fn from_opcodes(opcodes: &[u8], eof: Option<Arc<Eof>>) -> Self {
-        let (operations, pc_to_index_mapping, _) = Self::parse_operations(opcodes, eof.is_some());
+        let (operations, pc_to_index_mapping, index_to_pc_mapping, _) =
+            Self::parse_operations(opcodes, eof.is_some());
         let code_size = Self::calculate_code_size(&operations);
 
         Self {
@@ -968,6 +972,7 @@ impl Program {
             code_size,
             eof,
             pc_to_index_mapping,
+            index_to_pc_mapping,
         }
     }
 
@@ -1071,6 +1076,12 @@ impl Program {
         section_ptr as usize - first as usize
     }
 
+    /// Returns the program counter of the operation at the given index.
+    #[inline]
+    pub fn pc(&self, index: usize) -> usize {
+        self.index_to_pc_mapping[index]
+    }
+
     /// Returns the operation index of the given EOF section index.
     pub fn eof_section_index(&self, section: usize) -> usize {
         self.pc_to_index_mapping[&self.eof_section_pc(section)]
@@ -1082,11 +1093,13 @@ impl Program {
     ) -> (
         Vec<Operation>,
         FxHashMap<usize, usize>,
+        Vec<usize>,
         Vec<OpcodeParseError>,
     ) {
         let mut operations = vec![];
         let mut pc_to_index_mapping =
             FxHashMap::with_capacity_and_hasher(opcodes.len(), Default::default());
+        let mut index_to_pc_mapping = Vec::with_capacity(opcodes.len());
         let mut failed_opcodes = vec![];
         let mut pc = 0;
         let mut index = 0;
@@ -1094,6 +1107,7 @@ impl Program {
         pc_to_index_mapping.insert(pc, index);
 
         while pc < opcodes.len() {
+            index_to_pc_mapping.push(pc);
             match Self::parse_operation(opcodes, pc, is_eof) {
                 Ok((op, new_pc)) => {
                     operations.push(op);
@@ -1109,7 +1123,12 @@ impl Program {
             }
         }
 
-        (operations, pc_to_index_mapping, failed_opcodes)
+        (
+            operations,
+            pc_to_index_mapping,
+            index_to_pc_mapping,
+            failed_opcodes,
+        )
     }
 
     fn parse_operation(
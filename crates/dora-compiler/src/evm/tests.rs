@@ -82,3 +82,135 @@ fn program_from_eof_bytecode() {
     let program: Program = bytecode.into();
     insta::assert_snapshot!(format!("{:#?}", program.operations()));
 }
+
+#[test]
+fn disasm_legacy_blocks_and_static_jumps() {
+    use crate::evm::disasm::Disassembly;
+
+    let operations = vec![
+        Operation::Push((1_u8, BigUint::from(1_u8))),
+        Operation::Push((1_u8, BigUint::from(8_u8))),
+        Operation::JumpI,
+        Operation::Push0,
+        Operation::Jump,
+        Operation::Stop,
+        Operation::Jumpdest { pc: 8 },
+        Operation::Stop,
+    ];
+    let disasm = Disassembly::from_legacy(&Program::operations_to_opcode(&operations));
+    let section = &disasm.sections[0];
+    assert_eq!(section.instructions.len(), 8);
+    assert_eq!(section.instructions[1].mnemonic(), "PUSH1");
+    assert_eq!(
+        section.instructions[1].immediates().as_deref(),
+        Some("0x08")
+    );
+    let starts: Vec<usize> = section.blocks.iter().map(|block| block.start).collect();
+    assert_eq!(starts, vec![0, 5, 7, 8]);
+    // PUSH1 0x08 JUMPI resolves to the JUMPDEST and falls through.
+    assert_eq!(section.blocks[0].successors, vec![8, 5]);
+    // PUSH0 JUMP targets the PC 0 which is not a JUMPDEST.
+    assert_eq!(section.blocks[1].invalid_jump, Some(0));
+    assert!(section.blocks[3].is_jumpdest);
+    let text = disasm.to_string();
+    assert!(text.contains("block_0008: ; jumpdest"));
+    assert!(text.contains("; -> block_0008, block_0005"));
+    let dot = disasm.to_dot();
+    assert!(dot.starts_with("digraph cfg {"));
+    assert!(dot.contains("s0_0000 -> s0_0008;"));
+}
+
+#[test]
+fn disasm_eof_sections_and_relative_jumps() {
+    use crate::evm::disasm::Disassembly;
+
+    let operations = vec![
+        Operation::Push0,
+        Operation::RJumpI(1),
+        Operation::Stop,
+        Operation::CallF(0),
+        Operation::Stop,
+    ];
+    let program = Program::from_operations(operations, true);
+    let disasm = Disassembly::from_eof(&program.eof().unwrap());
+    let section = &disasm.sections[0];
+    let starts: Vec<usize> = section.blocks.iter().map(|block| block.start).collect();
+    assert_eq!(starts, vec![0, 4, 5, 8]);
+    assert_eq!(section.blocks[0].successors, vec![5, 4]);
+    assert_eq!(section.blocks[2].section_call, Some(0));
+    assert_eq!(section.instructions[1].immediates().as_deref(), Some("+1"));
+    let text = disasm.to_string();
+    assert!(text.contains("; EOF container: 1 code sections"));
+    assert!(text.contains("; code section 0"));
+    assert!(text.contains("; data section (0 bytes)"));
+}
+
+#[test]
+fn asm_legacy_labels_and_macros() {
+    use crate::evm::asm::{assemble, assemble_operations};
+
+    let source = "
+        %macro inc
+            PUSH1 1
+            ADD
+        %end
+            PUSH 0x2a       ; PUSH1
+            PUSH @end       // PUSH2
+            JUMP
+            %inc
+        end:
+            %return_word
+    ";
+    let code = assemble(source).unwrap();
+    assert_eq!(
+        code.as_ref(),
+        hex_literal::hex!("602a610009566001015b60005260206000f3")
+    );
+    let operations = assemble_operations(source).unwrap();
+    assert_eq!(operations[4], Operation::Jumpdest { pc: 9 });
+    assert_eq!(Program::operations_to_opcode(&operations), code.to_vec());
+}
+
+#[test]
+fn asm_eof_sections_and_relative_jumps() {
+    use crate::evm::asm::assemble;
+    use dora_primitives::Eof;
+    use std::sync::Arc;
+
+    let source = "
+        .code
+            PUSH0
+            RJUMPV @a, @b
+        a:
+            CALLF 1
+        b:
+            STOP
+        .code outputs=1
+            PUSH0
+            RETF
+        .data 0x01
+    ";
+    let eof = Eof::decode(assemble(source).unwrap()).unwrap();
+    let code = &eof.body.code_section[0];
+    assert_eq!(code.as_ref(), hex_literal::hex!("5fe20100000003e3000100"));
+    assert_eq!(eof.body.types_section[0].outputs, 0x80);
+    assert_eq!(eof.body.types_section[0].max_stack_size, 1);
+    assert_eq!(eof.body.types_section[1].outputs, 1);
+    assert_eq!(eof.body.types_section[1].max_stack_size, 1);
+    assert_eq!(eof.body.data_section.as_ref(), [0x01]);
+    let program = Program::from_opcodes(code, Some(Arc::new(eof.clone())));
+    assert_eq!(program.operations()[1], Operation::RJumpV((1, vec![0, 3])));
+}
+
+#[test]
+fn asm_errors() {
+    use crate::evm::asm::assemble;
+
+    let err = assemble("PUSH1 1\nPUSH @missing").unwrap_err();
+    assert_eq!(err.line, 2);
+    assert!(err.message.contains("undefined label `missing`"));
+    assert!(assemble("PUSH1 0x100").is_err());
+    assert!(assemble("RJUMP 0").is_err());
+    assert!(assemble("%undefined").is_err());
+    assert!(assemble(".code\n    JUMPDEST").is_err());
+}
@@ -7,6 +7,7 @@ pub mod dora;
 pub mod errors;
 pub mod evm;
 pub mod intrinsics;
+pub mod llvm;
 pub mod module;
 pub mod pass;
 pub mod state;
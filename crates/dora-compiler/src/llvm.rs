@@ -0,0 +1,55 @@
+//! Translation of the LLVM dialect module to the textual LLVM IR.
+
+use std::ffi::{CStr, c_char, c_void};
+
+use melior::ir::Module;
+use mlir_sys::MlirOperation;
+
+use crate::errors::{CompileError, Result};
+
+type LLVMContextRef = *mut c_void;
+type LLVMModuleRef = *mut c_void;
+
+unsafe extern "C" {
+    fn mlirTranslateModuleToLLVMIR(module: MlirOperation, context: LLVMContextRef)
+    -> LLVMModuleRef;
+    fn LLVMContextCreate() -> LLVMContextRef;
+    fn LLVMContextDispose(context: LLVMContextRef);
+    fn LLVMDisposeModule(module: LLVMModuleRef);
+    fn LLVMPrintModuleToString(module: LLVMModuleRef) -> *mut c_char;
+    fn LLVMDisposeMessage(message: *mut c_char);
+}
+
+/// Translates the module which only contains the LLVM dialect operations, i.e., the module
+/// lowered by [`crate::pass::run`], to the textual LLVM IR.
+///
+/// Note that the LLVM translations must be registered in the MLIR context, which is done by
+/// [`crate::context::initialize_mlir`].
+///
+/// # Example
+///
+/// ```ignore
+/// pass::run(&context.mlir_context, &mut module.mlir_module, OptimizationLevel::Default)?;
+/// println!("{}", translate_to_llvm_ir(&module.mlir_module)?);
+/// ```
+pub fn translate_to_llvm_ir(module: &Module) -> Result<String> {
+    // SAFETY: The LLVM module is owned by the LLVM context and both of them are disposed before
+    // returning, the printed string is copied before it is disposed.
+    unsafe {
+        let context = LLVMContextCreate();
+        let llvm_module = mlirTranslateModuleToLLVMIR(module.as_operation().to_raw(), context);
+        if llvm_module.is_null() {
+            LLVMContextDispose(context);
+            return Err(CompileError::LLVM(
+                "failed to translate the module to the LLVM IR".to_string(),
+            )
+            .into());
+        }
+        let message = LLVMPrintModuleToString(llvm_module);
+        let ir = CStr::from_ptr(message).to_string_lossy().into_owned();
+        LLVMDisposeMessage(message);
+        LLVMDisposeModule(llvm_module);
+        LLVMContextDispose(context);
+        Ok(ir)
+    }
+}
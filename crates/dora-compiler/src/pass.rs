@@ -1,3 +1,4 @@
+use dora_primitives::OptimizationLevel;
 use melior::{
     Context, Error,
     ir::Module,
@@ -14,6 +15,7 @@ use melior::{
 ///
 /// * `context` - A reference to the [`Context`] that holds the MLIR environment.
 /// * `module` - A mutable reference to the [`Module`] that will have the passes applied.
+/// * `opt_level` - The [`OptimizationLevel`] which selects the optimization passes applied before the lowering.
 ///
 /// # Returns
 ///
@@ -22,9 +24,15 @@ use melior::{
 ///
 /// # Passes Applied
 ///
-/// The following passes are applied in sequence:
+/// The optimization passes depend on the `opt_level`:
+///
+/// * [`OptimizationLevel::None`] - No optimization passes, which gives the lowest compile latency.
+/// * [`OptimizationLevel::Less`] and [`OptimizationLevel::Default`] - [`transform::create_canonicalizer`][pass::transform::create_canonicalizer] - Applies canonicalization to simplify and optimize the module.
+/// * [`OptimizationLevel::Aggressive`] - Additionally applies [`transform::create_cse`][pass::transform::create_cse] (common subexpression elimination)
+///   and [`transform::create_sccp`][pass::transform::create_sccp] (sparse conditional constant propagation), followed by another canonicalization.
+///
+/// Then the following lowering passes are applied in sequence:
 ///
-/// * [`transform::create_canonicalizer`][pass::transform::create_canonicalizer] - Applies canonicalization to simplify and optimize the module.
 /// * [`conversion::create_scf_to_control_flow`][pass::conversion::create_scf_to_control_flow] - Converts structured control flow (SCF) operations into a more generic control flow form.
 /// * [`conversion::create_arith_to_llvm`][pass::conversion::create_arith_to_llvm] - Lowers arithmetic operations to their LLVM counterparts.
 /// * [`conversion::create_math_to_llvm`][pass::conversion::create_math_to_llvm] - Converts mathematical operations into LLVM operations.
@@ -40,12 +48,27 @@ use melior::{
 /// ```ignore
 /// let context = Context::new();
 /// let mut module = Module::empty(&context);
-/// run(&context, &mut module)?;
+/// run(&context, &mut module, OptimizationLevel::Default)?;
 /// ```
-pub fn run(context: &Context, module: &mut Module) -> Result<(), Error> {
+pub fn run(
+    context: &Context,
+    module: &mut Module,
+    opt_level: OptimizationLevel,
+) -> Result<(), Error> {
     let pass_manager = PassManager::new(context);
     pass_manager.enable_verifier(true);
-    pass_manager.add_pass(pass::transform::create_canonicalizer());
+    match opt_level {
+        OptimizationLevel::None => {}
+        OptimizationLevel::Less | OptimizationLevel::Default => {
+            pass_manager.add_pass(pass::transform::create_canonicalizer());
+        }
+        OptimizationLevel::Aggressive => {
+            pass_manager.add_pass(pass::transform::create_canonicalizer());
+            pass_manager.add_pass(pass::transform::create_cse());
+            pass_manager.add_pass(pass::transform::create_sccp());
+            pass_manager.add_pass(pass::transform::create_canonicalizer());
+        }
+    }
     pass_manager.add_pass(pass::conversion::create_scf_to_control_flow());
     pass_manager.add_pass(pass::conversion::create_arith_to_llvm());
     pass_manager.add_pass(pass::conversion::create_math_to_llvm());
@@ -1,4 +1,5 @@
 use dora_ir::IRTypes;
+use dora_runtime::inspector::wasm_op_id;
 use dora_runtime::symbols;
 use dora_runtime::wasm::trap::TrapCode;
 use melior::ir::{
@@ -12,6 +13,7 @@ use melior::{
     },
     ir::Block,
 };
+use wasmer_compiler::wasmparser::Operator;
 
 use crate::conversion::rewriter::Rewriter;
 use crate::errors::CompileError;
@@ -20,6 +22,7 @@ use crate::value::ToContextValue;
 use crate::{backend::TypeMethods, context::Context, conversion::builder::OpBuilder, state::State};
 
 use super::intrinsics::WASMIntrinsics;
+use super::meter::{op_gas_cost, op_name};
 
 /// A builder structure for constructing WebAssembly (WASM) operations and managing
 /// the underlying compilation process using an `OpBuilder`. The `WASMBuilder`
@@ -221,6 +224,23 @@ pub(crate) fn trap_call(builder: &OpBuilder<'_, '_>, code: TrapCode) -> Result<(
     Ok(())
 }
 
+/// Emits the runtime tracing call which reports the operator, its offset in the module and its
+/// metering gas cost before the operator is executed.
+pub(crate) fn tracing_call(builder: &OpBuilder<'_, '_>, op: &Operator, offset: u32) -> Result<()> {
+    let ctx = builder.ctx;
+    let op_id = builder.make(builder.iconst_32(wasm_op_id(&op_name(op)) as _))?;
+    let offset = builder.make(builder.iconst_64(offset as _))?;
+    let gas_cost = builder.make(builder.iconst_64(op_gas_cost(op) as _))?;
+    builder.create(func::call(
+        ctx,
+        FlatSymbolRefAttribute::new(ctx, symbols::wasm::TRACING),
+        &[op_id, offset, gas_cost],
+        &[],
+        builder.get_insert_location(),
+    ));
+    Ok(())
+}
+
 pub(crate) fn gas_limit<'c, 'a>(builder: &OpBuilder<'c, 'a>) -> Result<Value<'c, 'a>> {
     let ctx = builder.ctx;
     let value = builder.make(func::call(
@@ -10,7 +10,7 @@ use crate::value::ToContextValue;
 use crate::wasm::backend::trap_float_if_not_representable_as_int;
 use crate::wasm::intrinsics::MemoryCache;
 
-use super::backend::{WASMBackend, is_zero, trap, trap_call};
+use super::backend::{WASMBackend, is_zero, tracing_call, trap, trap_call};
 use super::func::FuncTranslator;
 use super::intrinsics::CtxType;
 use super::intrinsics::FunctionCache;
@@ -720,6 +720,8 @@ pub struct FunctionCodeCtx<'c, 'a> {
     pub symbol_registry: &'a dyn SymbolRegistry,
     /// Whether to check static memory bound.
     pub static_memory_bound_check: bool,
+    /// Whether to report each executed operator to the runtime inspector.
+    pub tracing: bool,
 }
 
 impl FunctionCodeCtx<'_, '_> {
@@ -746,7 +748,7 @@ impl FunctionCodeGenerator {
     /// - `fcx`: A mutable reference to the function context containing state and translation data.
     /// - `backend`: A mutable reference to the WASM backend containing context and state information.
     /// - `region`: A reference to the MLIR region where the translated operations will be appended.
-    /// - `_source_loc`: A source location identifier (not used in the translation).
+    /// - `source_loc`: The operator offset in the module, which is reported when tracing.
     ///
     /// # Returns
     /// A result containing a tuple of `BlockRef<'c, 'a>`, which represents the start and end blocks
@@ -761,7 +763,7 @@ impl FunctionCodeGenerator {
         region: &'c Region<'c>,
         block: BlockRef<'c, 'a>,
         gas_counter_ptr: Option<Value<'c, 'a>>,
-        _source_loc: u32,
+        source_loc: u32,
     ) -> Result<BlockRef<'c, 'a>>
     where
         'a: 'c,
@@ -793,6 +795,9 @@ impl FunctionCodeGenerator {
                 }
             }
         } else {
+            if fcx.tracing {
+                tracing_call(&builder, &op, source_loc)?;
+            }
             // Insert the gas metering block
             if let Some(gas_counter_ptr) = gas_counter_ptr {
                 let location = builder.get_insert_location();
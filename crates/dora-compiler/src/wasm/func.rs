@@ -191,6 +191,7 @@ impl FuncTranslator {
                         wasm_module,
                         symbol_registry,
                         static_memory_bound_check: opts.static_memory_bound_check,
+                        tracing: opts.tracing,
                     };
                     let mut last_block = code_start_lock;
                     while backend.state.has_control_frames() {
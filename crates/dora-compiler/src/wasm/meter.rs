@@ -286,3 +286,12 @@ pub fn op_gas_cost(op: &Operator) -> u64 {
         _ => u64::MAX,
     }
 }
+
+/// Returns the operator name e.g., `LocalGet` without the immediates.
+pub fn op_name(op: &Operator) -> String {
+    let name = format!("{op:?}");
+    match name.find([' ', '{', '(']) {
+        Some(end) => name[..end].to_string(),
+        None => name,
+    }
+}
@@ -17,6 +17,7 @@ pub use conversion::ConversionPass;
 #[cfg(test)]
 mod tests;
 
+use dora_primitives::OptimizationLevel;
 use dora_runtime::wasm::WASMInstance;
 use dora_runtime::wasm::{env::WASMEnv, host};
 use func::FuncTranslator;
@@ -453,6 +454,7 @@ impl SymbolRegistry for ShortNames {
 /// let opts = WASMCompileOptions {
 ///     middlewares: vec![],
 ///     gas_metering: false,
+///     ..Default::default()
 /// };
 /// ```
 ///
@@ -467,6 +469,11 @@ pub struct WASMCompileOptions {
     pub gas_metering: bool,
     /// Whether to check static memory bound and offset gaurd.
     pub static_memory_bound_check: bool,
+    /// Whether to report each executed operator with its metering gas cost to the runtime
+    /// inspector, see `dora_runtime::inspector::WasmStep`.
+    pub tracing: bool,
+    /// The optimization level of the MLIR pass pipeline and the LLVM code generation.
+    pub opt_level: OptimizationLevel,
 }
 
 impl WASMCompileOptions {
@@ -481,4 +488,26 @@ impl WASMCompileOptions {
         self.static_memory_bound_check = static_memory_bound_check;
         self
     }
+
+    /// Set whether to report each executed operator to the runtime inspector.
+    pub fn tracing(mut self, tracing: bool) -> Self {
+        self.tracing = tracing;
+        self
+    }
+
+    /// Set the optimization level.
+    pub fn opt_level(mut self, opt_level: OptimizationLevel) -> Self {
+        self.opt_level = opt_level;
+        self
+    }
+
+    /// Returns the stable fingerprint of the options which affect the generated code, it is used
+    /// as the part of the artifact cache key. Note that the middlewares can not be fingerprinted,
+    /// thus the artifacts compiled with the custom middlewares should not be shared via the cache.
+    pub fn fingerprint(&self) -> u64 {
+        (self.gas_metering as u64)
+            | ((self.static_memory_bound_check as u64) << 1)
+            | ((self.tracing as u64) << 2)
+            | ((self.opt_level as u64) << 8)
+    }
 }
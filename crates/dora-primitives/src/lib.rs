@@ -1,5 +1,7 @@
 use std::sync::Arc;
 
+pub use revm;
+
 pub use alloy_primitives::{PrimitiveSignature, SignatureError, Signed};
 pub use revm::interpreter::{AccountLoad, Eip7702CodeLoad, OpCode, SelfDestructResult, StateLoad};
 pub use revm::precompile::{
@@ -0,0 +1,110 @@
+//! The access list generation of the transaction with the `eth_createAccessList` semantics.
+//!
+//! The transaction is executed with [`VM::transact`] without committing the state, and the
+//! accounts and storage slots loaded by the [`JournaledState`], i.e., the warm addresses and
+//! slots of the EIP-2929, form the access list. The sender, the recipient and the precompiles are
+//! warm already and are excluded. Since the access list changes the gas available to the
+//! transaction, the transaction is executed again with the access list applied until the access
+//! list is stable.
+//!
+//! [`JournaledState`]: crate::journaled_state::JournaledState
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use dora_primitives::{AccessList, AccessListItem, Address, B256, TxKind};
+
+use crate::{
+    db::{Database, DatabaseError},
+    journaled_state::State,
+    result::{ExecutionResult, VMError},
+    vm::VM,
+};
+
+/// The access list of the transaction with the gas used when the access list is applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessListResult {
+    /// The accounts and the storage slots accessed by the transaction.
+    pub access_list: AccessList,
+    /// The gas used by the transaction with the access list applied.
+    pub gas_used: u64,
+    /// The result of the transaction with the access list applied.
+    pub result: ExecutionResult,
+}
+
+impl<DB: Database> VM<'_, DB> {
+    /// Create the access list of the transaction in the environment, the access list of the
+    /// transaction is the initial access list and is restored afterwards. The state changes are
+    /// not committed.
+    ///
+    /// The beneficiary is loaded by every transaction to be rewarded, thus it is only listed
+    /// when its storage is accessed.
+    pub fn create_access_list(&mut self) -> Result<AccessListResult, VMError> {
+        let access_list = self.env.tx.access_list.clone();
+        let result = self.search_access_list();
+        self.env.tx.access_list = access_list;
+        result
+    }
+
+    fn search_access_list(&mut self) -> Result<AccessListResult, VMError> {
+        let caller = self.env.tx.caller;
+        let recipient = match self.env.tx.transact_to {
+            TxKind::Call(to) => to,
+            TxKind::Create => {
+                let nonce = match self.env.tx.nonce {
+                    Some(nonce) => nonce,
+                    None => self
+                        .db
+                        .basic(caller)
+                        .map_err(|_| VMError::Database(DatabaseError))?
+                        .map(|info| info.nonce)
+                        .unwrap_or_default(),
+                };
+                caller.create(nonce)
+            }
+        };
+        let excluded = [caller, recipient];
+        // The accounts and slots of the access list are always loaded, so the access list only
+        // grows until it is stable.
+        let mut access_list = AccessList(self.env.tx.access_list.clone());
+        loop {
+            self.env.tx.access_list = access_list.0.clone();
+            let output = self.transact()?;
+            let accessed = self.accessed_list(&output.state, &excluded);
+            if accessed == access_list {
+                return Ok(AccessListResult {
+                    access_list,
+                    gas_used: output.result.gas_used(),
+                    result: output.result,
+                });
+            }
+            access_list = accessed;
+        }
+    }
+
+    /// Returns the accounts and the storage slots loaded into the state sorted by the address
+    /// and the slot key.
+    fn accessed_list(&self, state: &State, excluded: &[Address]) -> AccessList {
+        let mut accessed: BTreeMap<Address, BTreeSet<B256>> = BTreeMap::new();
+        for (address, acc) in state {
+            if excluded.contains(address)
+                || self.precompiles.get(address).is_some()
+                || *address == self.env.block.coinbase && acc.storage.is_empty()
+            {
+                continue;
+            }
+            accessed
+                .entry(*address)
+                .or_default()
+                .extend(acc.storage.keys().map(|key| B256::from(*key)));
+        }
+        AccessList(
+            accessed
+                .into_iter()
+                .map(|(address, storage_keys)| AccessListItem {
+                    address,
+                    storage_keys: storage_keys.into_iter().collect(),
+                })
+                .collect(),
+        )
+    }
+}
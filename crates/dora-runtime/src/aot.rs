@@ -0,0 +1,87 @@
+//! Ahead-of-time (AOT) artifact support, which links the native object code emitted by the
+//! execution engine into a shared library that can be loaded without the compile pipeline.
+//!
+//! The compiled code calls the runtime functions e.g., `dora_fn_sload` by their symbol names,
+//! which are registered into the execution engine in the JIT mode. For the shared library, each
+//! runtime symbol is defined as a trampoline which jumps through a pointer slot, and the slots
+//! are filled with the runtime function pointers when the library is loaded.
+
+use anyhow::{Result, bail};
+use std::path::Path;
+#[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+use std::{fmt::Write, process::Command};
+
+/// The environment variable to override the C compiler used to link the shared library.
+pub const CC_ENV: &str = "CC";
+
+/// Whether the shared library artifact is supported on the target.
+pub const SUPPORTED: bool = cfg!(all(target_arch = "x86_64", target_os = "linux"));
+
+/// Returns the pointer slot symbol name of the runtime symbol.
+#[inline]
+pub fn slot_name(symbol: &str) -> String {
+    format!("__dora_slot_{symbol}")
+}
+
+/// Links the object file into a shared library, `symbols` are the runtime symbols referenced
+/// by the object code.
+///
+/// Note that the JIT code is compiled with the large code model and static relocations, thus
+/// only the x86_64 Linux target is supported at present, see [`SUPPORTED`].
+#[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+pub fn link_shared_library(object: &Path, output: &Path, symbols: &[&str]) -> Result<()> {
+    let trampolines = output.with_extension("s");
+    std::fs::write(&trampolines, trampoline_asm(symbols))?;
+    let cc = std::env::var(CC_ENV).unwrap_or_else(|_| "cc".to_string());
+    let status = Command::new(cc)
+        .arg("-shared")
+        // The object code uses the absolute addresses of the large code model.
+        .arg("-Wl,-z,notext")
+        // Always bind the symbols to the definitions in the library itself.
+        .arg("-Wl,-Bsymbolic")
+        .arg("-o")
+        .arg(output)
+        .arg(object)
+        .arg(&trampolines)
+        .status();
+    let _ = std::fs::remove_file(&trampolines);
+    match status {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => bail!("failed to link the shared library: {status}"),
+        Err(err) => bail!("failed to run the linker: {err}"),
+    }
+}
+
+/// Links the object file into a shared library, which is not supported on the target.
+#[cfg(not(all(target_arch = "x86_64", target_os = "linux")))]
+pub fn link_shared_library(_object: &Path, _output: &Path, _symbols: &[&str]) -> Result<()> {
+    bail!(
+        "the shared library artifact is only supported on the x86_64 Linux target, not {}-{}",
+        std::env::consts::ARCH,
+        std::env::consts::OS
+    )
+}
+
+/// Generates the assembly of the runtime symbol trampolines and the pointer slots.
+#[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+fn trampoline_asm(symbols: &[&str]) -> String {
+    let mut asm = String::new();
+    for symbol in symbols {
+        let slot = slot_name(symbol);
+        let _ = writeln!(asm, "    .text");
+        let _ = writeln!(asm, "    .globl {symbol}");
+        let _ = writeln!(asm, "    .hidden {symbol}");
+        let _ = writeln!(asm, "    .type {symbol},@function");
+        let _ = writeln!(asm, "{symbol}:");
+        let _ = writeln!(asm, "    jmpq *.L{slot}(%rip)");
+        let _ = writeln!(asm, "    .data");
+        let _ = writeln!(asm, "    .p2align 3");
+        let _ = writeln!(asm, "    .globl {slot}");
+        let _ = writeln!(asm, "    .type {slot},@object");
+        let _ = writeln!(asm, "{slot}:");
+        let _ = writeln!(asm, ".L{slot}:");
+        let _ = writeln!(asm, "    .quad 0");
+    }
+    let _ = writeln!(asm, "    .section .note.GNU-stack,\"\",@progbits");
+    asm
+}
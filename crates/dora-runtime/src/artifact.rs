@@ -1,6 +1,6 @@
 use crate::{
     call::CallResult,
-    constants::ENTRYPOINT,
+    constants::{DEPLOY_ENTRYPOINT, ENTRYPOINT},
     context::{Contract, EVMEntryFunc, RuntimeContext},
     executor::{ExecuteKind, Executor},
     host::DummyHost,
@@ -8,11 +8,57 @@ use crate::{
     wasm::context::{set_runtime_context, with_runtime_context},
 };
 use anyhow::{Result, anyhow};
-use dora_primitives::SpecId;
+use dora_primitives::{B256, SpecId, keccak256};
 use std::fmt::Debug;
 use std::panic::{AssertUnwindSafe, catch_unwind};
 use wasmer_vm::VMContext;
 
+/// The compiler version which is baked into the [`ArtifactKey`], thus the artifacts produced by
+/// different compiler versions are never mixed.
+pub const COMPILER_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The key of a compiled artifact.
+///
+/// The gas schedule and the opcode set are baked into the machine code at compile time, thus
+/// the artifact is not only identified by the code hash, but also the [`SpecId`], the compile
+/// options and the compiler version. When the chain crosses a hardfork boundary, the artifacts
+/// compiled for the previous spec are not hit and the code is compiled again for the new spec.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ArtifactKey {
+    /// The hash of the contract code.
+    pub code_hash: B256,
+    /// The spec ID which the code is compiled for.
+    pub spec_id: SpecId,
+    /// The fingerprint of the compile options e.g., `EVMCompileOptions::fingerprint`.
+    pub options: u64,
+    /// The compiler version.
+    pub version: &'static str,
+}
+
+impl ArtifactKey {
+    /// Creates an artifact key with the current compiler version.
+    #[inline]
+    pub fn new(code_hash: B256, spec_id: SpecId, options: u64) -> Self {
+        Self {
+            code_hash,
+            spec_id,
+            options,
+            version: COMPILER_VERSION,
+        }
+    }
+
+    /// Returns the stable hash of the key, which can be used as the persistent key
+    /// e.g., the file name of the on-disk cache.
+    pub fn hash(&self) -> B256 {
+        let mut data = Vec::with_capacity(32 + 1 + 8 + self.version.len());
+        data.extend_from_slice(self.code_hash.as_slice());
+        data.push(self.spec_id as u8);
+        data.extend_from_slice(&self.options.to_be_bytes());
+        data.extend_from_slice(self.version.as_bytes());
+        keccak256(data)
+    }
+}
+
 /// Artifact represents an abstraction of a compilation product for EVM/WASM bytecode.
 /// This versatile concept can be implemented in various forms, including intermediate
 /// compiled code, JIT memory format, or AOT binary executable/library/object format.
@@ -109,9 +155,18 @@ impl Artifact for SymbolArtifact {
                 })
             }
             ExecuteKind::WASM(_) => {
+                // The contract creation calls the `deploy` function, which is optional.
+                let entrypoint = if context.inner.is_create {
+                    if self.executor.lookup(DEPLOY_ENTRYPOINT).is_null() {
+                        return Ok(CallResult::new_with_gas_limit(context.gas_limit()));
+                    }
+                    DEPLOY_ENTRYPOINT
+                } else {
+                    ENTRYPOINT
+                };
                 // Note: default WASM entrypoint is `fn main() -> ()`, no args and return values.
                 let (_, result): ((), _) =
-                    self.execute_wasm_func_with_context_result(ENTRYPOINT, (), context)?;
+                    self.execute_wasm_func_with_context_result(entrypoint, (), context)?;
                 Ok(result)
             }
         }
@@ -119,6 +174,12 @@ impl Artifact for SymbolArtifact {
 }
 
 impl SymbolArtifact {
+    /// Returns the executor which holds the compiled code of the artifact.
+    #[inline]
+    pub fn executor(&self) -> &Executor {
+        &self.executor
+    }
+
     /// Executes a WASM function by name with the given arguments.
     ///
     /// # Arguments
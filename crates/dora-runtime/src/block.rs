@@ -0,0 +1,384 @@
+//! The block execution including the pre-block system calls, the transactions and the
+//! withdrawals, which produces the transaction receipts of the block.
+//!
+//! The block reward before the merge and the post-block system calls (e.g., EIP-7002 and
+//! EIP-7251 requests) are not applied by the executor.
+
+use alloy_rlp::{Encodable, Header, RlpEncodable};
+use dora_primitives::{
+    Address, B256, BLOCKHASH_STORAGE_ADDRESS, BlockEnv, Bytes, GAS_PER_BLOB, Log, SpecId, TxEnv,
+    U256, address, alloy_primitives::Bloom,
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    db::{Database, DatabaseError},
+    genesis::quantity,
+    journaled_state::State,
+    parallel::{self, WorkerHandler},
+    result::{ExecutionResult, ResultAndState, VMError},
+    trie::{StateTrie, ordered_trie_root},
+    vm::VM,
+};
+
+/// The caller address of the system calls.
+pub const SYSTEM_ADDRESS: Address = address!("fffffffffffffffffffffffffffffffffffffffe");
+/// The beacon roots contract address of the EIP-4788.
+pub const BEACON_ROOTS_ADDRESS: Address = address!("000F3df6D732807Ef1319fB7B8bB8522d0Beac02");
+/// The multiplier to convert the withdrawal amount in Gwei into Wei.
+pub const GWEI_TO_WEI: u64 = 1_000_000_000;
+
+pub const LEGACY_TX_TYPE: u8 = 0x00;
+pub const EIP2930_TX_TYPE: u8 = 0x01;
+pub const EIP1559_TX_TYPE: u8 = 0x02;
+pub const EIP4844_TX_TYPE: u8 = 0x03;
+pub const EIP7702_TX_TYPE: u8 = 0x04;
+
+/// The validator withdrawal of the EIP-4895.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, RlpEncodable)]
+#[serde(rename_all = "camelCase")]
+pub struct Withdrawal {
+    #[serde(with = "quantity")]
+    pub index: u64,
+    #[serde(with = "quantity")]
+    pub validator_index: u64,
+    pub address: Address,
+    /// The withdrawal amount in Gwei.
+    #[serde(with = "quantity")]
+    pub amount: u64,
+}
+
+/// The transaction of the block with its EIP-2718 type.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlockTransaction {
+    pub tx_type: u8,
+    pub tx: TxEnv,
+}
+
+/// The block to execute.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Block {
+    /// The block environment shared by all the transactions.
+    pub env: BlockEnv,
+    /// The ordered transactions of the block.
+    pub transactions: Vec<BlockTransaction>,
+    /// The withdrawals applied after the transactions since Shanghai.
+    pub withdrawals: Option<Vec<Withdrawal>>,
+    /// The parent beacon block root stored by the EIP-4788 system call since Cancun.
+    pub parent_beacon_block_root: Option<B256>,
+    /// The parent block hash stored by the EIP-2935 system call since Prague.
+    pub parent_hash: Option<B256>,
+}
+
+/// The receipt of the transaction.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Receipt {
+    pub tx_type: u8,
+    /// Whether the transaction is executed successfully.
+    pub status: bool,
+    /// The gas used by the block up to and including the transaction.
+    pub cumulative_gas_used: u64,
+    /// The gas used by the transaction.
+    pub gas_used: u64,
+    pub logs: Vec<Log>,
+    pub logs_bloom: Bloom,
+}
+
+impl Receipt {
+    /// Returns the EIP-2718 encoding of the receipt.
+    pub fn encoded(&self) -> Vec<u8> {
+        let mut fields = Vec::new();
+        self.status.encode(&mut fields);
+        self.cumulative_gas_used.encode(&mut fields);
+        self.logs_bloom.encode(&mut fields);
+        self.logs.encode(&mut fields);
+        let mut out = Vec::with_capacity(fields.len() + 10);
+        if self.tx_type != LEGACY_TX_TYPE {
+            out.push(self.tx_type);
+        }
+        Header {
+            list: true,
+            payload_length: fields.len(),
+        }
+        .encode(&mut out);
+        out.extend(fields);
+        out
+    }
+}
+
+/// The result of the block execution.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlockResult {
+    /// The receipts of the transactions in the block order.
+    pub receipts: Vec<Receipt>,
+    /// The execution results of the transactions in the block order.
+    pub results: Vec<ExecutionResult>,
+    /// The total gas used by the block.
+    pub gas_used: u64,
+    /// The total blob gas used by the block.
+    pub blob_gas_used: u64,
+    /// The root of the receipts trie.
+    pub receipts_root: B256,
+    /// The union of the logs bloom of all the receipts.
+    pub logs_bloom: Bloom,
+    /// The state root after the block when the executor maintains the state trie.
+    pub state_root: Option<B256>,
+}
+
+impl BlockResult {
+    /// Appends the receipt and the result of the executed transaction.
+    fn push_transaction(&mut self, transaction: &BlockTransaction, result: ExecutionResult) {
+        self.gas_used += result.gas_used();
+        self.blob_gas_used += transaction.tx.blob_hashes.len() as u64 * GAS_PER_BLOB;
+        let mut logs_bloom = Bloom::ZERO;
+        for log in result.logs() {
+            logs_bloom.accrue_log(log);
+        }
+        self.logs_bloom |= logs_bloom;
+        self.receipts.push(Receipt {
+            tx_type: transaction.tx_type,
+            status: result.is_success(),
+            cumulative_gas_used: self.gas_used,
+            gas_used: result.gas_used(),
+            logs: result.logs().to_vec(),
+            logs_bloom,
+        });
+        self.results.push(result);
+    }
+}
+
+/// The error of the block execution, the block is invalid and its state should be discarded.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum BlockError {
+    #[error("transaction {index} gas limit {gas_limit} exceeds the block gas left {gas_left}")]
+    GasLimitExceeded {
+        index: usize,
+        gas_limit: u64,
+        gas_left: u64,
+    },
+    #[error("invalid transaction {index}: {error}")]
+    Transaction { index: usize, error: VMError },
+    #[error("system call to {address} failed: {error}")]
+    SystemCall { address: Address, error: VMError },
+    #[error(transparent)]
+    Database(#[from] DatabaseError),
+}
+
+/// Executes the blocks on the VM and commits the state changes into the VM database.
+pub struct BlockExecutor<'a, DB: Database> {
+    vm: VM<'a, DB>,
+    state_trie: Option<StateTrie>,
+}
+
+impl<'a, DB: Database> BlockExecutor<'a, DB> {
+    /// Create a new block executor using the VM.
+    pub fn new(vm: VM<'a, DB>) -> Self {
+        Self {
+            vm,
+            state_trie: None,
+        }
+    }
+
+    /// Maintains the state trie of the committed state changes, which should be built from the
+    /// state of the VM database, so that the block result contains the state root.
+    pub fn with_state_trie(mut self, state_trie: StateTrie) -> Self {
+        self.state_trie = Some(state_trie);
+        self
+    }
+
+    /// Returns the state trie of the committed state.
+    #[inline]
+    pub fn state_trie(&self) -> Option<&StateTrie> {
+        self.state_trie.as_ref()
+    }
+
+    /// Returns the reference of the VM.
+    #[inline]
+    pub fn vm(&self) -> &VM<'a, DB> {
+        &self.vm
+    }
+
+    /// Returns the mutable reference of the VM.
+    #[inline]
+    pub fn vm_mut(&mut self) -> &mut VM<'a, DB> {
+        &mut self.vm
+    }
+
+    /// Returns the VM with the committed state.
+    #[inline]
+    pub fn into_vm(self) -> VM<'a, DB> {
+        self.vm
+    }
+
+    /// Executes the block, i.e., the pre-block system calls, all the transactions and the
+    /// withdrawals and returns the receipts of the transactions.
+    pub fn execute_block(&mut self, block: &Block) -> Result<BlockResult, BlockError> {
+        self.vm.env.block = block.env.clone();
+        self.apply_pre_block_system_calls(block)?;
+
+        let mut result = BlockResult::default();
+        for (index, transaction) in block.transactions.iter().enumerate() {
+            self.execute_transaction(index, transaction, &mut result)?;
+        }
+        self.finish_block(block, result)
+    }
+
+    /// Executes the transaction at the `index` of the block and commits its state changes, the
+    /// receipt and the result of the transaction are appended to the block result. The block
+    /// environment should be set and the pre-block system calls should be applied before.
+    ///
+    /// The block result is left unchanged when the transaction is invalid, so that the caller
+    /// is able to skip the invalid transaction, e.g., the rejected transactions of the t8n tool.
+    pub fn execute_transaction(
+        &mut self,
+        index: usize,
+        transaction: &BlockTransaction,
+        result: &mut BlockResult,
+    ) -> Result<(), BlockError> {
+        let block_gas_limit: u64 = self.vm.env.block.gas_limit.saturating_to();
+        let gas_left = block_gas_limit.saturating_sub(result.gas_used);
+        if transaction.tx.gas_limit > gas_left {
+            return Err(BlockError::GasLimitExceeded {
+                index,
+                gas_limit: transaction.tx.gas_limit,
+                gas_left,
+            });
+        }
+        self.vm.env.tx = transaction.tx.clone();
+        let ResultAndState {
+            result: outcome,
+            state,
+        } = self
+            .vm
+            .transact()
+            .map_err(|error| BlockError::Transaction { index, error })?;
+        self.commit(state);
+        result.push_transaction(transaction, outcome);
+        Ok(())
+    }
+
+    /// Executes the block as [`Self::execute_block`] does, but the transactions are executed in
+    /// parallel on the `concurrency` worker threads, see [`crate::parallel`]. Each worker uses the
+    /// VM handler created by the `handler`.
+    ///
+    /// The transactions are executed sequentially before the Spurious Dragon, where the empty
+    /// and the not existing accounts are different.
+    pub fn execute_block_parallel<H>(
+        &mut self,
+        block: &Block,
+        handler: H,
+        concurrency: usize,
+    ) -> Result<BlockResult, BlockError>
+    where
+        DB: Send + Sync,
+        H: WorkerHandler<DB>,
+    {
+        if concurrency < 2
+            || block.transactions.len() < 2
+            || !self.vm.spec_id().is_enabled_in(SpecId::SPURIOUS_DRAGON)
+        {
+            return self.execute_block(block);
+        }
+        self.vm.env.block = block.env.clone();
+        self.apply_pre_block_system_calls(block)?;
+
+        let outputs =
+            parallel::execute_transactions(&self.vm, &block.transactions, &handler, concurrency)?;
+        let mut result = BlockResult::default();
+        for (transaction, output) in block.transactions.iter().zip(outputs) {
+            self.commit(output.state);
+            result.push_transaction(transaction, output.result);
+        }
+        self.finish_block(block, result)
+    }
+
+    /// Applies the EIP-4788 beacon root and the EIP-2935 parent block hash system calls, which
+    /// are skipped when the system contract is not deployed.
+    pub fn apply_pre_block_system_calls(&mut self, block: &Block) -> Result<(), BlockError> {
+        let spec_id = self.vm.spec_id();
+        if let Some(root) = block
+            .parent_beacon_block_root
+            .filter(|_| spec_id.is_enabled_in(SpecId::CANCUN))
+        {
+            self.system_call(
+                BEACON_ROOTS_ADDRESS,
+                Bytes::copy_from_slice(root.as_slice()),
+            )?;
+        }
+        if let Some(hash) = block
+            .parent_hash
+            .filter(|_| spec_id.is_enabled_in(SpecId::PRAGUE))
+        {
+            self.system_call(
+                BLOCKHASH_STORAGE_ADDRESS,
+                Bytes::copy_from_slice(hash.as_slice()),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Increases the balance of the withdrawal addresses by the withdrawal amounts.
+    pub fn apply_withdrawals(&mut self, withdrawals: &[Withdrawal]) -> Result<(), BlockError> {
+        let ctx = &mut self.vm.context;
+        for withdrawal in withdrawals.iter().filter(|w| w.amount != 0) {
+            let amount = U256::from(withdrawal.amount) * U256::from(GWEI_TO_WEI);
+            let account = ctx
+                .journaled_state
+                .load_account(withdrawal.address, &mut ctx.db)
+                .map_err(|_| DatabaseError)?;
+            account.data.info.balance = account.data.info.balance.saturating_add(amount);
+            ctx.journaled_state.touch(&withdrawal.address);
+        }
+        let (state, _) = ctx.journaled_state.finalize();
+        ctx.journaled_state.clear();
+        self.commit(state);
+        Ok(())
+    }
+
+    fn system_call(&mut self, contract: Address, data: Bytes) -> Result<(), BlockError> {
+        let has_code = self
+            .vm
+            .db
+            .basic(contract)
+            .map_err(|_| DatabaseError)?
+            .is_some_and(|info| info.has_code());
+        if !has_code {
+            return Ok(());
+        }
+        // Keep the transaction environment of the block untouched by the system call.
+        let tx = self.vm.env.tx.clone();
+        let result = self
+            .vm
+            .transact_system_call(SYSTEM_ADDRESS, contract, data)
+            .map_err(|error| BlockError::SystemCall {
+                address: contract,
+                error,
+            });
+        self.vm.env.tx = tx;
+        self.commit(result?.state);
+        Ok(())
+    }
+
+    /// Applies the withdrawals and computes the roots of the block, which is called after all the
+    /// transactions are executed by [`Self::execute_transaction`].
+    pub fn finish_block(
+        &mut self,
+        block: &Block,
+        mut result: BlockResult,
+    ) -> Result<BlockResult, BlockError> {
+        if let Some(withdrawals) = &block.withdrawals {
+            self.apply_withdrawals(withdrawals)?;
+        }
+        result.receipts_root = ordered_trie_root(result.receipts.iter().map(Receipt::encoded));
+        result.state_root = self.state_trie.as_mut().map(StateTrie::root);
+        Ok(result)
+    }
+
+    fn commit(&mut self, state: State) {
+        if let Some(state_trie) = &mut self.state_trie {
+            state_trie.commit(&state);
+        }
+        self.vm.db.commit(state);
+    }
+}
@@ -0,0 +1,5 @@
+pub mod disk;
+pub mod lru;
+
+pub use disk::DiskCache;
+pub use lru::{ArtifactCache, CacheLimit, CacheStats};
@@ -0,0 +1,116 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anyhow::{Result, bail};
+
+use crate::aot;
+use crate::artifact::ArtifactKey;
+use crate::constants::env::DORA_CACHE_DIR;
+use crate::executor::{ExecuteKind, Executor};
+
+/// The file extension of the cached shared library artifacts.
+const ARTIFACT_EXTENSION: &str = "so";
+
+/// A persistent on-disk artifact cache, which stores the compiled EVM code as native shared
+/// libraries keyed by the [`ArtifactKey`] hash, thus the artifacts can be reused across the
+/// process restarts without running the compile pipeline again.
+///
+/// # Example
+///
+/// ```ignore
+/// let cache = DiskCache::new("/tmp/dora-cache")?;
+/// let executor = match cache.load(&key)? {
+///     Some(executor) => executor,
+///     None => {
+///         let executor = Executor::new_with_object_dump(module, opt_level, ExecuteKind::EVM);
+///         cache.store(&key, &executor)?;
+///         executor
+///     }
+/// };
+/// ```
+#[derive(Debug, Clone)]
+pub struct DiskCache {
+    dir: PathBuf,
+}
+
+impl DiskCache {
+    /// Creates a disk cache in the directory, the directory is created if it does not exist.
+    ///
+    /// Returns an error when the shared library artifact is not supported on the target, see
+    /// [`aot::SUPPORTED`].
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Result<Self> {
+        if !aot::SUPPORTED {
+            bail!("the on-disk artifact cache is only supported on the x86_64 Linux target");
+        }
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    /// Creates a disk cache in the directory given by the `DORA_CACHE_DIR` environment variable,
+    /// returns `None` if the variable is not set or the cache can not be created.
+    pub fn from_env() -> Option<Self> {
+        let dir = std::env::var(DORA_CACHE_DIR).ok()?;
+        Self::new(dir).ok()
+    }
+
+    /// Returns the cache directory.
+    #[inline]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns the artifact file path of the key.
+    #[inline]
+    pub fn path(&self, key: &ArtifactKey) -> PathBuf {
+        self.dir
+            .join(hex::encode(key.hash()))
+            .with_extension(ARTIFACT_EXTENSION)
+    }
+
+    /// Returns whether the artifact of the key is cached.
+    #[inline]
+    pub fn contains(&self, key: &ArtifactKey) -> bool {
+        self.path(key).is_file()
+    }
+
+    /// Loads the cached artifact of the key, returns `None` if it is not cached.
+    pub fn load(&self, key: &ArtifactKey) -> Result<Option<Executor>> {
+        let path = self.path(key);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        Executor::load(path, ExecuteKind::EVM).map(Some)
+    }
+
+    /// Stores the artifact of the key, the executor must be created by
+    /// [`Executor::new_with_object_dump`].
+    ///
+    /// The shared library is linked to a temporary file first and then renamed, thus the
+    /// concurrent readers never observe a partially written artifact.
+    pub fn store(&self, key: &ArtifactKey, executor: &Executor) -> Result<()> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let tmp = self.dir.join(format!(
+            "{}.{}.{}.tmp",
+            hex::encode(key.hash()),
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let result = executor
+            .emit_shared_library(&tmp)
+            .and_then(|_| Ok(std::fs::rename(&tmp, self.path(key))?));
+        if result.is_err() {
+            let _ = std::fs::remove_file(&tmp);
+        }
+        result
+    }
+
+    /// Removes the cached artifact of the key.
+    pub fn remove(&self, key: &ArtifactKey) -> Result<()> {
+        let path = self.path(key);
+        if path.is_file() {
+            std::fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+}
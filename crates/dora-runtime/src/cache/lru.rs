@@ -0,0 +1,242 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use rustc_hash::FxHashMap;
+
+use crate::artifact::{Artifact, ArtifactKey};
+
+/// The size limit of the [`ArtifactCache`], `None` means unlimited.
+///
+/// The limit counts the artifacts rather than their memory, because the execution engine does
+/// not report the memory size of the compiled code.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheLimit {
+    /// The maximum number of the cached artifacts.
+    pub max_entries: Option<usize>,
+}
+
+impl CacheLimit {
+    /// No limit, the artifacts are never evicted.
+    pub const UNLIMITED: Self = Self { max_entries: None };
+
+    /// Limits the number of the cached artifacts.
+    #[inline]
+    pub fn entries(max_entries: usize) -> Self {
+        Self {
+            max_entries: Some(max_entries),
+        }
+    }
+
+    #[inline]
+    fn exceeded(&self, entries: usize) -> bool {
+        self.max_entries.is_some_and(|max| entries > max)
+    }
+}
+
+/// The statistics of the [`ArtifactCache`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// The number of the lookups which find the artifact.
+    pub hits: u64,
+    /// The number of the lookups which do not find the artifact.
+    pub misses: u64,
+    /// The number of the artifacts evicted due to the size limits.
+    pub evictions: u64,
+    /// The number of the cached artifacts.
+    pub entries: usize,
+}
+
+impl CacheStats {
+    /// Returns the hit ratio of the lookups.
+    pub fn hit_ratio(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f64 / total as f64
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Entry<A> {
+    artifact: A,
+    /// The last access tick, which is the key of the LRU order.
+    tick: u64,
+}
+
+#[derive(Debug)]
+struct Inner<A> {
+    limit: CacheLimit,
+    entries: FxHashMap<ArtifactKey, Entry<A>>,
+    /// The artifact keys ordered from the least recently used to the most recently used.
+    order: BTreeMap<u64, ArtifactKey>,
+    tick: u64,
+    stats: CacheStats,
+}
+
+impl<A> Inner<A> {
+    #[inline]
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+
+    fn remove(&mut self, key: &ArtifactKey) -> Option<Entry<A>> {
+        let entry = self.entries.remove(key)?;
+        self.order.remove(&entry.tick);
+        self.stats.entries -= 1;
+        Some(entry)
+    }
+
+    fn evict(&mut self) {
+        while self.limit.exceeded(self.stats.entries) {
+            let Some((_, key)) = self.order.pop_first() else {
+                break;
+            };
+            if self.entries.remove(&key).is_some() {
+                self.stats.entries -= 1;
+                self.stats.evictions += 1;
+            }
+        }
+    }
+}
+
+/// A bounded, thread-safe artifact cache with the least-recently-used (LRU) eviction.
+///
+/// Each compiled artifact keeps the native code and the execution engine alive, thus the cache
+/// bounds the number of the artifacts, and evicts the least recently used ones when the limit is
+/// exceeded. The cache can be cloned and shared across threads and databases, all the clones
+/// share the same entries and statistics.
+///
+/// # Example
+///
+/// ```ignore
+/// let cache = ArtifactCache::<SymbolArtifact>::new(CacheLimit::entries(4096));
+/// let db = MemoryDB::new().with_artifact_cache(cache.clone());
+/// // ...
+/// let stats = cache.stats();
+/// println!("hits: {}, misses: {}, evictions: {}", stats.hits, stats.misses, stats.evictions);
+/// ```
+pub struct ArtifactCache<A> {
+    inner: Arc<Mutex<Inner<A>>>,
+}
+
+impl<A> Clone for ArtifactCache<A> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl<A: Artifact> Default for ArtifactCache<A> {
+    fn default() -> Self {
+        Self::new(CacheLimit::UNLIMITED)
+    }
+}
+
+impl<A> fmt::Debug for ArtifactCache<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let inner = self.inner.lock();
+        f.debug_struct("ArtifactCache")
+            .field("limit", &inner.limit)
+            .field("stats", &inner.stats)
+            .finish()
+    }
+}
+
+impl<A: Artifact> ArtifactCache<A> {
+    /// Creates an artifact cache with the size limit.
+    pub fn new(limit: CacheLimit) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Inner {
+                limit,
+                entries: FxHashMap::default(),
+                order: BTreeMap::new(),
+                tick: 0,
+                stats: CacheStats::default(),
+            })),
+        }
+    }
+
+    /// Returns the artifact of the key and marks it as the most recently used.
+    pub fn get(&self, key: &ArtifactKey) -> Option<A> {
+        let mut inner = self.inner.lock();
+        let tick = inner.next_tick();
+        let inner = &mut *inner;
+        match inner.entries.get_mut(key) {
+            Some(entry) => {
+                inner.order.remove(&entry.tick);
+                inner.order.insert(tick, *key);
+                entry.tick = tick;
+                inner.stats.hits += 1;
+                Some(entry.artifact.clone())
+            }
+            None => {
+                inner.stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Returns whether the artifact of the key is cached, the LRU order and the statistics
+    /// are not changed.
+    pub fn contains(&self, key: &ArtifactKey) -> bool {
+        self.inner.lock().entries.contains_key(key)
+    }
+
+    /// Inserts the artifact as the most recently used one, and evicts the least recently used
+    /// artifacts when the size limit is exceeded.
+    pub fn insert(&self, key: ArtifactKey, artifact: A) {
+        let mut inner = self.inner.lock();
+        inner.remove(&key);
+        let tick = inner.next_tick();
+        inner.order.insert(tick, key);
+        inner.entries.insert(key, Entry { artifact, tick });
+        inner.stats.entries += 1;
+        inner.evict();
+    }
+
+    /// Removes the artifact of the key.
+    pub fn remove(&self, key: &ArtifactKey) -> Option<A> {
+        self.inner.lock().remove(key).map(|entry| entry.artifact)
+    }
+
+    /// Removes all the artifacts, the statistics counters are kept.
+    pub fn clear(&self) {
+        let mut inner = self.inner.lock();
+        inner.entries.clear();
+        inner.order.clear();
+        inner.stats.entries = 0;
+    }
+
+    /// Returns the size limit.
+    pub fn limit(&self) -> CacheLimit {
+        self.inner.lock().limit
+    }
+
+    /// Updates the size limit and evicts the artifacts which exceed the new limit.
+    pub fn set_limit(&self, limit: CacheLimit) {
+        let mut inner = self.inner.lock();
+        inner.limit = limit;
+        inner.evict();
+    }
+
+    /// Returns the cache statistics.
+    pub fn stats(&self) -> CacheStats {
+        self.inner.lock().stats
+    }
+
+    /// Returns the number of the cached artifacts.
+    pub fn len(&self) -> usize {
+        self.inner.lock().entries.len()
+    }
+
+    /// Returns whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
@@ -0,0 +1,352 @@
+//! The layered read-through cache database over any [`Database`].
+//!
+//! The [`CacheDB`] lazily loads the accounts, the code, the storage slots and the block hashes
+//! from the inner database and caches them. The committed state changes are applied to the cache
+//! and accumulated into the [`StateDiff`] without touching the inner database, the diff can be
+//! flushed into the inner database later, e.g., after the block is executed, or discarded. Since
+//! the [`CacheDB`] is a [`Database`] as well, the cache databases can be layered, e.g., to fork
+//! the state or to simulate the transactions on top of the pending state.
+
+use dora_primitives::{Address, B256, Bytecode, U256};
+use parking_lot::RwLock;
+use rustc_hash::FxHashMap;
+
+use crate::{
+    account::{Account, AccountInfo},
+    artifact::ArtifactKey,
+    db::{Database, DatabaseCommit, StorageSlot},
+};
+
+/// The state changes committed into the [`CacheDB`] which are not flushed into the inner
+/// database.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StateDiff {
+    /// The changed accounts.
+    pub accounts: FxHashMap<Address, AccountDiff>,
+    /// The deployed contracts by the code hash.
+    pub contracts: FxHashMap<B256, Bytecode>,
+}
+
+/// The changes of the account.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccountDiff {
+    /// The account information, `None` if the account is self-destructed.
+    pub info: Option<AccountInfo>,
+    /// The changed storage slots, the original value is the value before the first change.
+    pub storage: FxHashMap<U256, StorageSlot>,
+    /// Whether the storage of the inner database is cleared, i.e., the account is created or
+    /// self-destructed.
+    pub storage_cleared: bool,
+}
+
+impl StateDiff {
+    /// Returns whether there is no change.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty() && self.contracts.is_empty()
+    }
+
+    /// Converts the diff into the state changes, which can be committed into any database with
+    /// the [`DatabaseCommit`].
+    pub fn into_changes(self) -> FxHashMap<Address, Account> {
+        self.accounts
+            .into_iter()
+            .map(|(address, diff)| {
+                let mut account = match diff.info {
+                    Some(mut info) => {
+                        if info.has_code() && info.code.is_none() {
+                            info.code = self.contracts.get(&info.code_hash).cloned();
+                        }
+                        let mut account = Account::from(info);
+                        if diff.storage_cleared {
+                            account.mark_created();
+                        }
+                        account.storage = diff.storage;
+                        account
+                    }
+                    None => {
+                        let mut account = Account::new_not_existing();
+                        account.mark_selfdestruct();
+                        account
+                    }
+                };
+                account.mark_touch();
+                (address, account)
+            })
+            .collect()
+    }
+}
+
+/// The values loaded from the inner database and the committed values.
+#[derive(Debug, Clone, Default)]
+struct Cache {
+    accounts: FxHashMap<Address, Option<AccountInfo>>,
+    storage: FxHashMap<Address, CachedStorage>,
+    contracts: FxHashMap<B256, Bytecode>,
+    block_hashes: FxHashMap<U256, B256>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct CachedStorage {
+    slots: FxHashMap<U256, U256>,
+    /// Whether the storage of the inner database is cleared, thus the slots that are not cached
+    /// are zero.
+    cleared: bool,
+}
+
+/// The database which caches the values of the inner database and absorbs the committed state
+/// changes without touching the inner database.
+///
+/// # Example
+///
+/// ```ignore
+/// let mut vm = VM::new(VMContext::new(CacheDB::new(db), env, spec_id, handler));
+/// vm.transact_commit()?;
+/// let diff = vm.db.diff().clone();
+/// vm.db.flush();
+/// ```
+#[derive(Debug)]
+pub struct CacheDB<DB> {
+    inner: DB,
+    cache: RwLock<Cache>,
+    diff: StateDiff,
+}
+
+impl<DB: Clone> Clone for CacheDB<DB> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            cache: RwLock::new(self.cache.read().clone()),
+            diff: self.diff.clone(),
+        }
+    }
+}
+
+impl<DB: Database> CacheDB<DB> {
+    /// Create a new cache database over the inner database.
+    pub fn new(inner: DB) -> Self {
+        Self {
+            inner,
+            cache: RwLock::default(),
+            diff: StateDiff::default(),
+        }
+    }
+
+    /// Returns the reference of the inner database.
+    #[inline]
+    pub fn inner(&self) -> &DB {
+        &self.inner
+    }
+
+    /// Returns the inner database, the state changes which are not flushed are discarded.
+    #[inline]
+    pub fn into_inner(self) -> DB {
+        self.inner
+    }
+
+    /// Returns the state changes accumulated since the last flush.
+    #[inline]
+    pub fn diff(&self) -> &StateDiff {
+        &self.diff
+    }
+
+    /// Takes the state changes accumulated since the last flush, the cached values are kept.
+    #[inline]
+    pub fn take_diff(&mut self) -> StateDiff {
+        std::mem::take(&mut self.diff)
+    }
+
+    /// Commits the accumulated state changes into the inner database.
+    pub fn flush(&mut self) {
+        let diff = self.take_diff();
+        if !diff.is_empty() {
+            self.inner.commit(diff.into_changes());
+        }
+    }
+
+    /// Removes the cached values, the state changes which are not flushed are kept.
+    pub fn clear_cache(&mut self) {
+        let cache = self.cache.get_mut();
+        *cache = Cache::default();
+        for (address, diff) in &self.diff.accounts {
+            cache.accounts.insert(*address, diff.info.clone());
+            cache.storage.insert(
+                *address,
+                CachedStorage {
+                    slots: diff
+                        .storage
+                        .iter()
+                        .map(|(key, slot)| (*key, slot.present_value))
+                        .collect(),
+                    cleared: diff.storage_cleared,
+                },
+            );
+        }
+        cache.contracts.clone_from(&self.diff.contracts);
+    }
+
+    fn insert_code(&mut self, info: &AccountInfo) {
+        if let Some(code) = info.code.as_ref().filter(|_| info.has_code()) {
+            self.cache
+                .get_mut()
+                .contracts
+                .insert(info.code_hash, code.clone());
+            self.diff.contracts.insert(info.code_hash, code.clone());
+        }
+    }
+
+    /// Applies the account changes to the cache and the diff.
+    fn write_account(
+        &mut self,
+        address: Address,
+        info: Option<AccountInfo>,
+        storage: FxHashMap<U256, StorageSlot>,
+        storage_cleared: bool,
+    ) {
+        let cache = self.cache.get_mut();
+        cache.accounts.insert(address, info.clone());
+        let cached = cache.storage.entry(address).or_default();
+        let diff = self.diff.accounts.entry(address).or_default();
+        diff.info = info;
+        if storage_cleared {
+            cached.slots.clear();
+            cached.cleared = true;
+            diff.storage.clear();
+            diff.storage_cleared = true;
+        }
+        for (key, slot) in storage {
+            cached.slots.insert(key, slot.present_value);
+            diff.storage
+                .entry(key)
+                .and_modify(|changed| changed.present_value = slot.present_value)
+                .or_insert(slot);
+        }
+    }
+}
+
+impl<DB: Database> Database for CacheDB<DB> {
+    type Error = DB::Error;
+    type Artifact = DB::Artifact;
+
+    fn basic(&self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        if let Some(info) = self.cache.read().accounts.get(&address) {
+            return Ok(info.clone());
+        }
+        let info = self.inner.basic(address)?;
+        self.cache.write().accounts.insert(address, info.clone());
+        Ok(info)
+    }
+
+    fn code_by_hash(&self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        if let Some(code) = self.cache.read().contracts.get(&code_hash) {
+            return Ok(code.clone());
+        }
+        let code = self.inner.code_by_hash(code_hash)?;
+        self.cache.write().contracts.insert(code_hash, code.clone());
+        Ok(code)
+    }
+
+    fn storage(&self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        if let Some(storage) = self.cache.read().storage.get(&address) {
+            match storage.slots.get(&index) {
+                Some(value) => return Ok(*value),
+                None if storage.cleared => return Ok(U256::ZERO),
+                None => {}
+            }
+        }
+        let value = self.inner.storage(address, index)?;
+        self.cache
+            .write()
+            .storage
+            .entry(address)
+            .or_default()
+            .slots
+            .insert(index, value);
+        Ok(value)
+    }
+
+    fn block_hash(&self, number: U256) -> Result<B256, Self::Error> {
+        if let Some(hash) = self.cache.read().block_hashes.get(&number) {
+            return Ok(*hash);
+        }
+        let hash = self.inner.block_hash(number)?;
+        self.cache.write().block_hashes.insert(number, hash);
+        Ok(hash)
+    }
+
+    fn insert_contract(&mut self, address: Address, bytecode: Bytecode, balance: U256) {
+        let info = AccountInfo {
+            balance,
+            nonce: 1,
+            code_hash: bytecode.hash_slow(),
+            code: Some(bytecode),
+        };
+        self.insert_code(&info);
+        self.write_account(address, Some(info), FxHashMap::default(), true);
+    }
+
+    fn set_account(
+        &mut self,
+        address: Address,
+        nonce: u64,
+        balance: U256,
+        storage: FxHashMap<U256, U256>,
+    ) {
+        let info = match self.basic(address) {
+            Ok(Some(info)) => info,
+            _ => AccountInfo::empty(),
+        };
+        let storage = storage
+            .into_iter()
+            .map(|(key, value)| (key, StorageSlot::new_changed(U256::ZERO, value)))
+            .collect();
+        self.write_account(
+            address,
+            Some(AccountInfo {
+                nonce,
+                balance,
+                ..info
+            }),
+            storage,
+            true,
+        );
+    }
+
+    fn get_artifact(&self, key: &ArtifactKey) -> Result<Option<Self::Artifact>, Self::Error> {
+        self.inner.get_artifact(key)
+    }
+
+    fn set_artifact(&mut self, key: ArtifactKey, artifact: Self::Artifact) {
+        self.inner.set_artifact(key, artifact);
+    }
+
+    /// Returns the state of the inner database with the state changes which are not flushed.
+    fn into_state(mut self) -> FxHashMap<Address, Account> {
+        self.flush();
+        self.inner.into_state()
+    }
+}
+
+impl<DB: Database> DatabaseCommit for CacheDB<DB> {
+    /// Commits the state changes into the cache and the diff, the untouched accounts are skipped
+    /// and the self-destructed accounts are removed.
+    fn commit(&mut self, changes: FxHashMap<Address, Account>) {
+        for (address, account) in changes {
+            if !account.is_touched() {
+                continue;
+            }
+            if account.is_selfdestructed() {
+                self.write_account(address, None, FxHashMap::default(), true);
+                continue;
+            }
+            self.insert_code(&account.info);
+            let storage_cleared = account.is_created();
+            let storage = account
+                .storage
+                .into_iter()
+                .filter(|(_, slot)| storage_cleared || slot.is_changed())
+                .collect();
+            self.write_account(address, Some(account.info), storage, storage_cleared);
+        }
+    }
+}
@@ -4,10 +4,14 @@ pub const MAX_STACK_SIZE: usize = 1024;
 pub const CALL_STACK_LIMIT: usize = 1024;
 /// MLIR call entry point name.
 pub const ENTRYPOINT: &str = "call";
+/// WASM deployment entry point name, which is called when the WASM module is the init code of a
+/// contract creation.
+pub const DEPLOY_ENTRYPOINT: &str = "deploy";
 
 pub mod env {
     pub const DORA_TRACING: &str = "DORA_TRACING";
     pub const DORA_DISABLE_CONSOLE: &str = "DORA_DISABLE_CONSOLE";
+    pub const DORA_CACHE_DIR: &str = "DORA_CACHE_DIR";
 }
 
 pub mod gas_cost {
@@ -4,22 +4,23 @@ use crate::account::Account;
 use crate::call::{CallKind, CallMessage, CallResult, CallType, ExtCallType};
 use crate::constants::env::DORA_TRACING;
 use crate::constants::gas_cost::MIN_CALLEE_GAS;
-use crate::constants::{CALL_STACK_LIMIT, MAX_STACK_SIZE, gas_cost};
+use crate::constants::{CALL_STACK_LIMIT, gas_cost};
 use crate::db::{Database, DatabaseError};
 use crate::executor::ExecutionEngine;
 use crate::handler::{Frame, Handler};
 use crate::host::{AccountLoad, Host, SStoreResult, SelfDestructResult, StateLoad};
+use crate::inspector::{Inspector, PrintInspector, Step};
 use crate::journaled_state::{JournalCheckpoint, JournalEntry, JournaledState};
 use crate::result::VMError;
 use crate::stack::Stack;
-use crate::wasm::host::gas_limit;
+use crate::wasm::host::{gas_limit, wasm_tracing};
 use crate::wasm::trap::wasm_raise_trap;
 use crate::{ExitStatusCode, gas, symbols};
 use dora_primitives::{
     Address, B256, BLOCK_HASH_HISTORY, BLOCKHASH_STORAGE_ADDRESS, Bytecode, Bytes, Bytes32, CfgEnv,
-    EOF_MAGIC_BYTES, EOF_MAGIC_HASH, Env, KECCAK_EMPTY, Log, LogData, OpCode, PER_AUTH_BASE_COST,
+    EOF_MAGIC_BYTES, EOF_MAGIC_HASH, Env, KECCAK_EMPTY, Log, LogData, PER_AUTH_BASE_COST,
     PER_EMPTY_ACCOUNT_COST, PrecompileErrors, PrecompileSpecId, Precompiles, SpecId, U256,
-    as_u64_saturated, as_usize_saturated, keccak256,
+    WASM_MAGIC_BYTES, as_u64_saturated, as_usize_saturated, keccak256,
 };
 
 /// Function type for the EVM main entrypoint of the generated code.
@@ -45,6 +46,8 @@ pub struct VMContext<'a, DB: Database> {
     pub journaled_state: JournaledState,
     /// Precompiles that are available for evm.
     pub precompiles: &'a Precompiles,
+    /// Optional inspector to observe the execution.
+    pub inspector: Option<Box<dyn Inspector + 'a>>,
 }
 
 impl<'a, DB: Database> VMContext<'a, DB> {
@@ -57,9 +60,27 @@ impl<'a, DB: Database> VMContext<'a, DB> {
             handler,
             journaled_state: JournaledState::new(spec_id, Default::default()),
             precompiles: Precompiles::new(PrecompileSpecId::from_spec_id(spec_id)),
+            inspector: if std::env::var(DORA_TRACING).is_ok() {
+                Some(Box::new(PrintInspector))
+            } else {
+                None
+            },
         }
     }
 
+    /// Attaches an inspector to observe the execution.
+    #[inline]
+    pub fn with_inspector<I: Inspector + 'a>(mut self, inspector: I) -> Self {
+        self.inspector = Some(Box::new(inspector));
+        self
+    }
+
+    /// Sets or removes the inspector attached to the context.
+    #[inline]
+    pub fn set_inspector(&mut self, inspector: Option<Box<dyn Inspector + 'a>>) {
+        self.inspector = inspector;
+    }
+
     /// Returns the configured EVM spec ID.
     #[inline]
     pub const fn spec_id(&self) -> SpecId {
@@ -273,15 +294,7 @@ impl<'a, DB: Database> VMContext<'a, DB> {
     /// Reward beneficiary with gas fee.
     pub fn reward_beneficiary(&mut self, gas_used: u64, gas_refunded: i64) -> Result<(), VMError> {
         let beneficiary = self.env.block.coinbase;
-        let effective_gas_price = self.env.effective_gas_price();
-
-        // transfer fee to coinbase/beneficiary.
-        // EIP-1559 discard basefee for coinbase transfer. Basefee amount of gas is discarded.
-        let coinbase_gas_price = if self.spec_id().is_enabled_in(SpecId::LONDON) {
-            effective_gas_price.saturating_sub(self.env.block.basefee)
-        } else {
-            effective_gas_price
-        };
+        let reward = self.beneficiary_reward(gas_used, gas_refunded);
 
         let coinbase_account = self
             .journaled_state
@@ -289,15 +302,26 @@ impl<'a, DB: Database> VMContext<'a, DB> {
             .map_err(|_| VMError::Database(DatabaseError))?;
 
         coinbase_account.data.mark_touch();
-        coinbase_account.data.info.balance = coinbase_account
-            .data
-            .info
-            .balance
-            .saturating_add(coinbase_gas_price * U256::from(gas_used - gas_refunded as u64));
+        coinbase_account.data.info.balance =
+            coinbase_account.data.info.balance.saturating_add(reward);
 
         Ok(())
     }
 
+    /// Returns the gas fee transferred to the beneficiary.
+    pub fn beneficiary_reward(&self, gas_used: u64, gas_refunded: i64) -> U256 {
+        let effective_gas_price = self.env.effective_gas_price();
+
+        // transfer fee to coinbase/beneficiary.
+        // EIP-1559 discard basefee for coinbase transfer. Basefee amount of gas is discarded.
+        let coinbase_gas_price = if self.spec_id().is_enabled_in(SpecId::LONDON) {
+            effective_gas_price.saturating_sub(self.env.block.basefee)
+        } else {
+            effective_gas_price
+        };
+        coinbase_gas_price * U256::from(gas_used - gas_refunded as u64)
+    }
+
     /// Handle output of the transaction
     pub fn last_frame_return(&mut self, result: &mut CallResult) {
         let remaining = result.gas_remaining;
@@ -487,6 +511,32 @@ impl<'a, DB: Database> VMContext<'a, DB> {
 
     /// Handle frame sub call.
     pub fn call(&mut self, msg: CallMessage) -> Result<CallResult, VMError> {
+        if self.inspector.is_none() || matches!(msg.kind, CallKind::ReturnContract) {
+            return self.call_inner(msg);
+        }
+        let is_create = matches!(
+            msg.kind,
+            CallKind::Create | CallKind::Create2 | CallKind::EofCreate
+        );
+        if let Some(inspector) = self.inspector.as_mut() {
+            if is_create {
+                inspector.create(&msg);
+            } else {
+                inspector.call(&msg);
+            }
+        }
+        let result = self.call_inner(msg.clone())?;
+        if let Some(inspector) = self.inspector.as_mut() {
+            if is_create {
+                inspector.create_end(&msg, &result);
+            } else {
+                inspector.call_end(&msg, &result);
+            }
+        }
+        Ok(result)
+    }
+
+    fn call_inner(&mut self, msg: CallMessage) -> Result<CallResult, VMError> {
         // Check depth
         if self.journaled_state.depth() > CALL_STACK_LIMIT {
             return Ok(CallResult::new_with_gas_limit_and_status(
@@ -558,6 +608,7 @@ impl<'a, DB: Database> VMContext<'a, DB> {
                         is_static: msg.is_static,
                         is_eof_init: msg.is_eof_init,
                         validate_eof: msg.validate_eof,
+                        is_create: false,
                         depth: self.journaled_state.depth(),
                     })?;
                     self.call_return(&call_result.status, checkpoint);
@@ -627,6 +678,7 @@ impl<'a, DB: Database> VMContext<'a, DB> {
                         is_static: msg.is_static,
                         is_eof_init: msg.is_eof_init,
                         validate_eof: msg.validate_eof,
+                        is_create: false,
                         depth: self.journaled_state.depth(),
                     })?;
                     self.call_return(&call_result.status, checkpoint);
@@ -705,9 +757,10 @@ impl<'a, DB: Database> VMContext<'a, DB> {
                     is_static: msg.is_static,
                     is_eof_init: msg.is_eof_init,
                     validate_eof: msg.validate_eof,
+                    is_create: false,
                     depth: self.journaled_state.depth(),
                 })?;
-                self.create_return(&mut call_result, created_address, checkpoint);
+                self.create_return(&mut call_result, created_address, checkpoint, false);
                 Ok(call_result)
             }
             CallKind::ReturnContract => {
@@ -793,9 +846,16 @@ impl<'a, DB: Database> VMContext<'a, DB> {
                     is_static: msg.is_static,
                     is_eof_init: msg.is_eof_init,
                     validate_eof: msg.validate_eof,
+                    is_create: true,
                     depth: self.journaled_state.depth(),
                 })?;
-                self.create_return(&mut call_result, created_address, checkpoint);
+                // The WASM module is deployed as the runtime code itself, the `deploy` function
+                // only initializes the contract state.
+                let is_wasm = msg.input.starts_with(&WASM_MAGIC_BYTES);
+                if call_result.status.is_ok() && is_wasm {
+                    call_result.output = msg.input;
+                }
+                self.create_return(&mut call_result, created_address, checkpoint, is_wasm);
                 Ok(call_result)
             }
         }
@@ -815,12 +875,15 @@ impl<'a, DB: Database> VMContext<'a, DB> {
         }
     }
 
-    /// Handles create return.
+    /// Handles create return, when `is_wasm` is `true`, the output is the deployed WASM module,
+    /// which is neither bounded by the EIP-170 code size limit nor charged the code deposit gas,
+    /// since its size is paid by the init code of the transaction already.
     pub fn create_return(
         &mut self,
         result: &mut CallResult,
         address: Address,
         journal_checkpoint: JournalCheckpoint,
+        is_wasm: bool,
     ) {
         result.create_address = Some(address);
         // if return is not ok revert and return.
@@ -841,14 +904,19 @@ impl<'a, DB: Database> VMContext<'a, DB> {
 
         // EIP-170: Contract code size limit
         // By default limit is 0x6000 (~25kb)
-        if spec_id.is_enabled_in(SpecId::SPURIOUS_DRAGON)
+        if !is_wasm
+            && spec_id.is_enabled_in(SpecId::SPURIOUS_DRAGON)
             && result.output.len() > self.cfg().max_code_size()
         {
             self.journaled_state.checkpoint_revert(journal_checkpoint);
             result.status = ExitStatusCode::CreateContractSizeLimit;
             return;
         }
-        let gas_for_code = result.output.len() as u64 * gas_cost::CODEDEPOSIT;
+        let gas_for_code = if is_wasm {
+            0
+        } else {
+            result.output.len() as u64 * gas_cost::CODEDEPOSIT
+        };
         if !result.record_cost(gas_for_code) {
             // Record code deposit gas cost and check if we are out of gas.
             // EIP-2 point 3: If contract creation does not have enough gas to pay for the
@@ -938,6 +1006,15 @@ impl<DB: Database> Host for VMContext<'_, DB> {
         addr: Address,
         target: Address,
     ) -> Option<StateLoad<SelfDestructResult>> {
+        if let Some(inspector) = self.inspector.as_mut() {
+            let value = self
+                .journaled_state
+                .state
+                .get(&addr)
+                .map(|acc| acc.info.balance)
+                .unwrap_or_default();
+            inspector.selfdestruct(addr, target, value);
+        }
         self.journaled_state
             .selfdestruct(addr, target, &mut self.db)
             .ok()
@@ -959,6 +1036,9 @@ impl<DB: Database> Host for VMContext<'_, DB> {
 
     #[inline]
     fn log(&mut self, log: Log) {
+        if let Some(inspector) = self.inspector.as_mut() {
+            inspector.log(&log);
+        }
         self.journaled_state.log(log);
     }
 
@@ -966,6 +1046,14 @@ impl<DB: Database> Host for VMContext<'_, DB> {
     fn call(&mut self, msg: CallMessage) -> Result<CallResult, VMError> {
         self.call(msg)
     }
+
+    #[inline]
+    fn inspector(&mut self) -> Option<&mut dyn Inspector> {
+        match self.inspector.as_mut() {
+            Some(inspector) => Some(inspector.as_mut()),
+            None => None,
+        }
+    }
 }
 
 /// The internal execution context, which holds the memory, gas, and program state during contract execution.
@@ -1010,6 +1098,8 @@ pub struct InnerContext {
     pub is_static: bool,
     /// Whether the context is EOF init.
     pub is_eof_init: bool,
+    /// Whether the context runs the init code of a `CREATE` or `CREATE2` contract creation.
+    pub is_create: bool,
     /// VM spec id
     pub spec_id: SpecId,
 }
@@ -1027,6 +1117,7 @@ impl Default for InnerContext {
             depth: Default::default(),
             is_static: Default::default(),
             is_eof_init: Default::default(),
+            is_create: Default::default(),
             spec_id: Default::default(),
         }
     }
@@ -1298,45 +1389,29 @@ impl RuntimeContext<'_> {
     extern "C" fn tracing(
         &mut self,
         op: u8,
+        pc: u64,
         gas: u64,
-        stack_ptr: *mut Bytes32,
-        stack_size_ptr: *mut u64,
+        gas_cost: u64,
+        stack_ptr: *const Bytes32,
+        stack_size_ptr: *const u64,
     ) {
-        let stack_size = unsafe { *stack_size_ptr } as usize;
-        let stack =
-            unsafe { Vec::<Bytes32>::from_raw_parts(stack_ptr, stack_size, MAX_STACK_SIZE) };
-        let nano_seconds = if let Ok(duration_since_epoch) =
-            std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)
-        {
-            duration_since_epoch.as_nanos()
-        } else {
-            0
+        let Some(inspector) = self.host.inspector() else {
+            return;
         };
-        let op_name = OpCode::new(op).map(|i| i.as_str()).unwrap();
-        println!(
-            "time: {}ns, op: {}, opHex: {:x}, opName: {}, gas: 0x{:x}, memSize: {}, stack: {:?}, stackSize: {}, depth: {}",
-            nano_seconds,
-            op,
-            op,
-            op_name,
-            gas,
-            self.memory().len(),
-            stack
-                .iter()
-                .map(|v| hex::encode(v.to_be_bytes())
-                    .trim_start_matches('0')
-                    .to_string())
-                .map(|v| if v.is_empty() {
-                    "0x0".to_string()
-                } else {
-                    format!("0x{v}")
-                })
-                .collect::<Vec<_>>(),
-            stack_size,
-            self.inner.depth,
-        );
-        // DO NOT free the stack pointer.
-        stack.leak();
+        let stack_size = unsafe { *stack_size_ptr } as usize;
+        let stack = unsafe { std::slice::from_raw_parts(stack_ptr, stack_size) };
+        inspector.step(&Step {
+            pc,
+            opcode: op,
+            gas_remaining: gas,
+            gas_cost,
+            stack,
+            memory: &self.inner.memory,
+            return_data: &self.inner.returndata,
+            refund: self.inner.gas_refunded,
+            depth: self.inner.depth,
+            address: self.contract.target_address,
+        });
     }
 
     extern "C" fn write_result(
@@ -1478,16 +1553,10 @@ impl RuntimeContext<'_> {
             is_eof_init: false,
             validate_eof: true,
         };
-        if std::env::var(DORA_TRACING).is_ok() {
-            println!("info: sub call msg {:?}", call_msg);
-        }
         let call_result = self
             .host
             .call(call_msg)
             .unwrap_or_else(|_| CallResult::new_with_gas_limit(gas_limit));
-        if std::env::var(DORA_TRACING).is_ok() {
-            println!("info: sub call ret {:?}", call_result);
-        }
         self.inner.returndata = call_result.output.to_vec();
         let ret_offset = ret_offset as usize;
         let ret_size = ret_size as usize;
@@ -1609,16 +1678,10 @@ impl RuntimeContext<'_> {
             is_eof_init: true,
             validate_eof: true,
         };
-        if std::env::var(DORA_TRACING).is_ok() {
-            println!("info: sub call msg {:?}", call_msg);
-        }
         let call_result = self
             .host
             .call(call_msg)
             .unwrap_or_else(|_| CallResult::new_with_gas_limit(gas_limit));
-        if std::env::var(DORA_TRACING).is_ok() {
-            println!("info: sub call ret {:?}", call_result);
-        }
         self.inner.returndata = call_result.output.to_vec();
         // Check the error message.
         if call_result.status.is_ok() {
@@ -2460,162 +2523,167 @@ impl RuntimeContext<'_> {
     }
 }
 
-type SymbolSignature = (&'static str, *const fn() -> ());
+/// The runtime symbol name and its function pointer.
+pub type SymbolSignature = (&'static str, *const fn() -> ());
 
 impl RuntimeContext<'_> {
+    /// Returns all the syscalls and their function pointers which are required by the compiled
+    /// EVM code.
+    pub fn evm_symbols() -> Vec<SymbolSignature> {
+        // Global variables and syscalls with corresponding function signatures
+        vec![
+            // Debug functions
+            (symbols::NOP, RuntimeContext::nop as *const _),
+            (symbols::TRACING, RuntimeContext::tracing as *const _),
+            // Syscalls
+            (
+                symbols::WRITE_RESULT,
+                RuntimeContext::write_result as *const _,
+            ),
+            (
+                symbols::CTX_IS_STATIC,
+                RuntimeContext::ctx_is_static as *const _,
+            ),
+            (symbols::EXP, RuntimeContext::exp as *const _),
+            (
+                symbols::KECCAK256_HASHER,
+                RuntimeContext::keccak256_hasher as *const _,
+            ),
+            (
+                symbols::EXTEND_MEMORY,
+                RuntimeContext::extend_memory as *const _,
+            ),
+            (symbols::MEMORY_PTR, RuntimeContext::memory_ptr as *const _),
+            (
+                symbols::MEMORY_SIZE,
+                RuntimeContext::memory_size as *const _,
+            ),
+            (symbols::SLOAD, RuntimeContext::sload as *const _),
+            (symbols::SSTORE, RuntimeContext::sstore as *const _),
+            (symbols::APPEND_LOG, RuntimeContext::append_log as *const _),
+            (
+                symbols::APPEND_LOG_ONE_TOPIC,
+                RuntimeContext::append_log_with_one_topic as *const _,
+            ),
+            (
+                symbols::APPEND_LOG_TWO_TOPICS,
+                RuntimeContext::append_log_with_two_topics as *const _,
+            ),
+            (
+                symbols::APPEND_LOG_THREE_TOPICS,
+                RuntimeContext::append_log_with_three_topics as *const _,
+            ),
+            (
+                symbols::APPEND_LOG_FOUR_TOPICS,
+                RuntimeContext::append_log_with_four_topics as *const _,
+            ),
+            (symbols::CALLDATA, RuntimeContext::calldata as *const _),
+            (
+                symbols::CALLDATA_SIZE,
+                RuntimeContext::calldata_size as *const _,
+            ),
+            (
+                symbols::CALLDATA_COPY,
+                RuntimeContext::calldata_copy as *const _,
+            ),
+            (
+                symbols::DATA_SECTION,
+                RuntimeContext::data_section as *const _,
+            ),
+            (
+                symbols::DATA_SECTION_SIZE,
+                RuntimeContext::data_section_size as *const _,
+            ),
+            (
+                symbols::DATA_SECTION_COPY,
+                RuntimeContext::data_section_copy as *const _,
+            ),
+            (symbols::CODE_COPY, RuntimeContext::code_copy as *const _),
+            (symbols::ORIGIN, RuntimeContext::origin as *const _),
+            (symbols::ADDRESS, RuntimeContext::address as *const _),
+            (symbols::CALLVALUE, RuntimeContext::callvalue as *const _),
+            (
+                symbols::STORE_IN_BLOBBASEFEE_PTR,
+                RuntimeContext::store_in_blobbasefee_ptr as *const _,
+            ),
+            (
+                symbols::EXT_CODE_SIZE,
+                RuntimeContext::extcodesize as *const _,
+            ),
+            (symbols::COINBASE, RuntimeContext::coinbase as *const _),
+            (
+                symbols::STORE_IN_TIMESTAMP_PTR,
+                RuntimeContext::store_in_timestamp_ptr as *const _,
+            ),
+            (
+                symbols::STORE_IN_BASEFEE_PTR,
+                RuntimeContext::store_in_basefee_ptr as *const _,
+            ),
+            (symbols::CALLER, RuntimeContext::caller as *const _),
+            (
+                symbols::STORE_IN_GASLIMIT_PTR,
+                RuntimeContext::store_in_gaslimit_ptr as *const _,
+            ),
+            (
+                symbols::STORE_IN_GASPRICE_PTR,
+                RuntimeContext::store_in_gasprice_ptr as *const _,
+            ),
+            (
+                symbols::BLOCK_NUMBER,
+                RuntimeContext::block_number as *const _,
+            ),
+            (symbols::PREVRANDAO, RuntimeContext::prevrandao as *const _),
+            (symbols::BLOB_HASH, RuntimeContext::blob_hash as *const _),
+            (symbols::CHAINID, RuntimeContext::chainid as *const _),
+            (
+                symbols::STORE_IN_BALANCE,
+                RuntimeContext::store_in_balance as *const _,
+            ),
+            (
+                symbols::STORE_IN_SELFBALANCE_PTR,
+                RuntimeContext::store_in_selfbalance_ptr as *const _,
+            ),
+            (
+                symbols::EXT_CODE_COPY,
+                RuntimeContext::extcodecopy as *const _,
+            ),
+            (symbols::BLOCK_HASH, RuntimeContext::block_hash as *const _),
+            (
+                symbols::EXT_CODE_HASH,
+                RuntimeContext::extcodehash as *const _,
+            ),
+            (symbols::EOFCREATE, RuntimeContext::eofcreate as *const _),
+            (
+                symbols::RETURNCONTRACT,
+                RuntimeContext::returncontract as *const _,
+            ),
+            (symbols::CREATE, RuntimeContext::create as *const _),
+            (symbols::CREATE2, RuntimeContext::create2 as *const _),
+            (symbols::CALL, RuntimeContext::call as *const _),
+            (symbols::EXTCALL, RuntimeContext::extcall as *const _),
+            (symbols::RETURNDATA, RuntimeContext::returndata as *const _),
+            (
+                symbols::RETURNDATA_SIZE,
+                RuntimeContext::returndata_size as *const _,
+            ),
+            (
+                symbols::RETURNDATA_COPY,
+                RuntimeContext::returndata_copy as *const _,
+            ),
+            (
+                symbols::SELFDESTRUCT,
+                RuntimeContext::selfdestruct as *const _,
+            ),
+            (symbols::TLOAD, RuntimeContext::tload as *const _),
+            (symbols::TSTORE, RuntimeContext::tstore as *const _),
+        ]
+    }
+
     /// Registers all the syscalls as symbols in the execution engine.
     pub fn register_evm_symbols(engine: &ExecutionEngine) {
         unsafe {
-            // Global variables and syscalls with corresponding function signatures
-            let symbols_and_signatures: &[SymbolSignature] = &[
-                // Debug functions
-                (symbols::NOP, RuntimeContext::nop as *const _),
-                (symbols::TRACING, RuntimeContext::tracing as *const _),
-                // Syscalls
-                (
-                    symbols::WRITE_RESULT,
-                    RuntimeContext::write_result as *const _,
-                ),
-                (
-                    symbols::CTX_IS_STATIC,
-                    RuntimeContext::ctx_is_static as *const _,
-                ),
-                (symbols::EXP, RuntimeContext::exp as *const _),
-                (
-                    symbols::KECCAK256_HASHER,
-                    RuntimeContext::keccak256_hasher as *const _,
-                ),
-                (
-                    symbols::EXTEND_MEMORY,
-                    RuntimeContext::extend_memory as *const _,
-                ),
-                (symbols::MEMORY_PTR, RuntimeContext::memory_ptr as *const _),
-                (
-                    symbols::MEMORY_SIZE,
-                    RuntimeContext::memory_size as *const _,
-                ),
-                (symbols::SLOAD, RuntimeContext::sload as *const _),
-                (symbols::SSTORE, RuntimeContext::sstore as *const _),
-                (symbols::APPEND_LOG, RuntimeContext::append_log as *const _),
-                (
-                    symbols::APPEND_LOG_ONE_TOPIC,
-                    RuntimeContext::append_log_with_one_topic as *const _,
-                ),
-                (
-                    symbols::APPEND_LOG_TWO_TOPICS,
-                    RuntimeContext::append_log_with_two_topics as *const _,
-                ),
-                (
-                    symbols::APPEND_LOG_THREE_TOPICS,
-                    RuntimeContext::append_log_with_three_topics as *const _,
-                ),
-                (
-                    symbols::APPEND_LOG_FOUR_TOPICS,
-                    RuntimeContext::append_log_with_four_topics as *const _,
-                ),
-                (symbols::CALLDATA, RuntimeContext::calldata as *const _),
-                (
-                    symbols::CALLDATA_SIZE,
-                    RuntimeContext::calldata_size as *const _,
-                ),
-                (
-                    symbols::CALLDATA_COPY,
-                    RuntimeContext::calldata_copy as *const _,
-                ),
-                (
-                    symbols::DATA_SECTION,
-                    RuntimeContext::data_section as *const _,
-                ),
-                (
-                    symbols::DATA_SECTION_SIZE,
-                    RuntimeContext::data_section_size as *const _,
-                ),
-                (
-                    symbols::DATA_SECTION_COPY,
-                    RuntimeContext::data_section_copy as *const _,
-                ),
-                (symbols::CODE_COPY, RuntimeContext::code_copy as *const _),
-                (symbols::ORIGIN, RuntimeContext::origin as *const _),
-                (symbols::ADDRESS, RuntimeContext::address as *const _),
-                (symbols::CALLVALUE, RuntimeContext::callvalue as *const _),
-                (
-                    symbols::STORE_IN_BLOBBASEFEE_PTR,
-                    RuntimeContext::store_in_blobbasefee_ptr as *const _,
-                ),
-                (
-                    symbols::EXT_CODE_SIZE,
-                    RuntimeContext::extcodesize as *const _,
-                ),
-                (symbols::COINBASE, RuntimeContext::coinbase as *const _),
-                (
-                    symbols::STORE_IN_TIMESTAMP_PTR,
-                    RuntimeContext::store_in_timestamp_ptr as *const _,
-                ),
-                (
-                    symbols::STORE_IN_BASEFEE_PTR,
-                    RuntimeContext::store_in_basefee_ptr as *const _,
-                ),
-                (symbols::CALLER, RuntimeContext::caller as *const _),
-                (
-                    symbols::STORE_IN_GASLIMIT_PTR,
-                    RuntimeContext::store_in_gaslimit_ptr as *const _,
-                ),
-                (
-                    symbols::STORE_IN_GASPRICE_PTR,
-                    RuntimeContext::store_in_gasprice_ptr as *const _,
-                ),
-                (
-                    symbols::BLOCK_NUMBER,
-                    RuntimeContext::block_number as *const _,
-                ),
-                (symbols::PREVRANDAO, RuntimeContext::prevrandao as *const _),
-                (symbols::BLOB_HASH, RuntimeContext::blob_hash as *const _),
-                (symbols::CHAINID, RuntimeContext::chainid as *const _),
-                (
-                    symbols::STORE_IN_BALANCE,
-                    RuntimeContext::store_in_balance as *const _,
-                ),
-                (
-                    symbols::STORE_IN_SELFBALANCE_PTR,
-                    RuntimeContext::store_in_selfbalance_ptr as *const _,
-                ),
-                (
-                    symbols::EXT_CODE_COPY,
-                    RuntimeContext::extcodecopy as *const _,
-                ),
-                (symbols::BLOCK_HASH, RuntimeContext::block_hash as *const _),
-                (
-                    symbols::EXT_CODE_HASH,
-                    RuntimeContext::extcodehash as *const _,
-                ),
-                (symbols::EOFCREATE, RuntimeContext::eofcreate as *const _),
-                (
-                    symbols::RETURNCONTRACT,
-                    RuntimeContext::returncontract as *const _,
-                ),
-                (symbols::CREATE, RuntimeContext::create as *const _),
-                (symbols::CREATE2, RuntimeContext::create2 as *const _),
-                (symbols::CALL, RuntimeContext::call as *const _),
-                (symbols::EXTCALL, RuntimeContext::extcall as *const _),
-                (symbols::RETURNDATA, RuntimeContext::returndata as *const _),
-                (
-                    symbols::RETURNDATA_SIZE,
-                    RuntimeContext::returndata_size as *const _,
-                ),
-                (
-                    symbols::RETURNDATA_COPY,
-                    RuntimeContext::returndata_copy as *const _,
-                ),
-                (
-                    symbols::SELFDESTRUCT,
-                    RuntimeContext::selfdestruct as *const _,
-                ),
-                (symbols::TLOAD, RuntimeContext::tload as *const _),
-                (symbols::TSTORE, RuntimeContext::tstore as *const _),
-            ];
-
-            for (symbol, signature) in symbols_and_signatures {
-                engine.register_symbol(symbol, *signature as *mut ());
+            for (symbol, signature) in Self::evm_symbols() {
+                engine.register_symbol(symbol, signature as *mut ());
             }
         }
     }
@@ -2742,6 +2810,7 @@ impl RuntimeContext<'_> {
                 ),
                 (symbols::wasm::RAISE_TRAP, wasm_raise_trap as *const _),
                 (symbols::wasm::GAS_LIMIT, gas_limit as *const _),
+                (symbols::wasm::TRACING, wasm_tracing as *const _),
             ];
 
             for (symbol, signature) in symbols_and_signatures {
@@ -1,8 +1,10 @@
 use crate::{
     account::{Account, AccountInfo, AccountStatus},
-    artifact::{Artifact, SymbolArtifact},
+    artifact::{Artifact, ArtifactKey, SymbolArtifact},
+    cache::ArtifactCache,
+    genesis::{GenesisAccount, GenesisAlloc, Prestate},
 };
-use dora_primitives::{Address, B256, Bytecode, KECCAK_EMPTY, U256, keccak256};
+use dora_primitives::{Address, B256, Bytecode, Bytes, KECCAK_EMPTY, U256, keccak256};
 use rustc_hash::FxHashMap;
 use std::{convert::Infallible, fmt::Debug};
 use thiserror::Error;
@@ -92,18 +94,19 @@ pub trait Database: Clone + Debug + DatabaseCommit {
         storage: FxHashMap<U256, U256>,
     );
 
-    /// Retrieves the contract bytecode artifact for a given code hash.
+    /// Retrieves the contract bytecode artifact for a given artifact key.
     ///
     /// # Parameters:
-    /// - `code_hash`: The hash of the contract code to be retrieved.
+    /// - `key`: The artifact key, which covers the code hash, the spec ID, the compile options
+    ///   and the compiler version.
     ///
     /// # Returns:
     /// - `Result<Option<Self::Artifact>, Self::Error>`: A `Result` containing either
-    ///   the `Self::Artifact` associated with the hash or an error if the query fails.
-    fn get_artifact(&self, code_hash: B256) -> Result<Option<Self::Artifact>, Self::Error>;
+    ///   the `Self::Artifact` associated with the key or an error if the query fails.
+    fn get_artifact(&self, key: &ArtifactKey) -> Result<Option<Self::Artifact>, Self::Error>;
 
-    /// Sets or updates an account in the database with the specified address and the contract artifact.
-    fn set_artifact(&mut self, code_hash: B256, artifact: Self::Artifact);
+    /// Sets or updates the contract artifact in the database with the specified artifact key.
+    fn set_artifact(&mut self, key: ArtifactKey, artifact: Self::Artifact);
 
     /// Converts the current state of the database into a collection of `Account` objects.
     fn into_state(self) -> FxHashMap<Address, Account>;
@@ -251,6 +254,11 @@ impl From<U256> for StorageSlot {
 /// - `contracts`: Stores contract bytecode mapped by code hash.
 /// - `block_hashes`: Stores block numbers and their corresponding hashes.
 ///
+/// Note that the compiled artifacts are stored in an [`ArtifactCache`] which is shared by the
+/// cloned databases, i.e., the artifact compiled by one clone is reused by the others, while the
+/// accounts, the contracts and the block hashes are copied. Use
+/// [`MemoryDB::with_artifact_cache`] with a new cache to separate the artifacts of a clone.
+///
 /// # Example Usage:
 /// ```no_run
 /// use dora_primitives::{B256, U256};
@@ -262,7 +270,8 @@ impl From<U256> for StorageSlot {
 pub struct MemoryDB {
     accounts: FxHashMap<Address, DbAccount>,
     contracts: FxHashMap<B256, Bytecode>,
-    artifacts: FxHashMap<B256, SymbolArtifact>,
+    /// The compiled artifacts, note that the cache is shared by the cloned databases.
+    artifacts: ArtifactCache<SymbolArtifact>,
     block_hashes: FxHashMap<U256, B256>,
 }
 
@@ -284,6 +293,27 @@ impl MemoryDB {
         Self::default()
     }
 
+    /// Uses the artifact cache to store the compiled artifacts, the cache can be bounded and
+    /// shared with other databases and threads. By default, the artifacts are stored in an
+    /// unbounded cache.
+    ///
+    /// # Example:
+    /// ```ignore
+    /// let cache = ArtifactCache::new(CacheLimit::entries(1024));
+    /// let db = MemoryDB::new().with_artifact_cache(cache.clone());
+    /// ```
+    #[inline]
+    pub fn with_artifact_cache(mut self, cache: ArtifactCache<SymbolArtifact>) -> Self {
+        self.artifacts = cache;
+        self
+    }
+
+    /// Returns the artifact cache of the database.
+    #[inline]
+    pub fn artifact_cache(&self) -> &ArtifactCache<SymbolArtifact> {
+        &self.artifacts
+    }
+
     /// Inserts a block hash for a given block number.
     ///
     /// This function associates a block number (`U256`) with a block hash (`B256`).
@@ -451,6 +481,72 @@ impl MemoryDB {
             .unwrap_or_default()
     }
 
+    /// Inserts the accounts and the block hashes of the pre-state, the existing accounts at the
+    /// same addresses are replaced.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let prestate = Prestate::from_json(&std::fs::read_to_string("prestate.json")?)?;
+    /// db.insert_prestate(prestate);
+    /// ```
+    pub fn insert_prestate(&mut self, prestate: Prestate) {
+        for (address, account) in prestate.alloc {
+            let code = Bytecode::new(account.code);
+            let bytecode_hash = code.hash_slow();
+            if !code.is_empty() {
+                self.contracts.insert(bytecode_hash, code);
+            }
+            self.accounts.insert(
+                address,
+                DbAccount {
+                    nonce: account.nonce,
+                    balance: account.balance,
+                    storage: account.storage.into_iter().collect(),
+                    bytecode_hash,
+                    ..DbAccount::empty()
+                },
+            );
+        }
+        self.block_hashes.extend(prestate.block_hashes);
+    }
+
+    /// Dumps the accounts into the genesis allocation, the empty accounts e.g., the self-destructed
+    /// accounts and the zero storage slots are omitted.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// vm.transact_commit()?;
+    /// let alloc = vm.context.db.dump_state();
+    /// println!("{}", serde_json::to_string_pretty(&alloc)?);
+    /// ```
+    pub fn dump_state(&self) -> GenesisAlloc {
+        self.accounts
+            .iter()
+            .filter_map(|(address, account)| {
+                let code = self
+                    .contracts
+                    .get(&account.bytecode_hash)
+                    .map(|code| Bytes::copy_from_slice(code.original_byte_slice()))
+                    .unwrap_or_default();
+                let storage = account
+                    .storage
+                    .iter()
+                    .filter(|(_, value)| !value.is_zero())
+                    .map(|(key, value)| (*key, *value))
+                    .collect();
+                let account = GenesisAccount {
+                    balance: account.balance,
+                    nonce: account.nonce,
+                    code,
+                    storage,
+                };
+                (account != GenesisAccount::default()).then_some((*address, account))
+            })
+            .collect()
+    }
+
     #[inline]
     pub fn store_contract(&mut self, account: &AccountInfo) {
         if let Some(code) = account.code.as_ref() {
@@ -508,12 +604,12 @@ impl Database for MemoryDB {
         account.storage = storage;
     }
 
-    fn get_artifact(&self, code_hash: B256) -> Result<Option<Self::Artifact>, Self::Error> {
-        Ok(self.artifacts.get(&code_hash).cloned())
+    fn get_artifact(&self, key: &ArtifactKey) -> Result<Option<Self::Artifact>, Self::Error> {
+        Ok(self.artifacts.get(key))
     }
 
-    fn set_artifact(&mut self, code_hash: B256, artifact: Self::Artifact) {
-        self.artifacts.insert(code_hash, artifact);
+    fn set_artifact(&mut self, key: ArtifactKey, artifact: Self::Artifact) {
+        self.artifacts.insert(key, artifact);
     }
 
     fn into_state(self) -> FxHashMap<Address, Account> {
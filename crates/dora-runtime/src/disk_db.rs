@@ -0,0 +1,400 @@
+//! The persistent on-disk database backed by the embedded [`redb`] key-value store.
+//!
+//! The accounts, the contract code by the code hash, the non-zero storage slots and the block
+//! hashes are stored in the separated tables of a single database file. Each commit is written
+//! in one write transaction, thus either all the state changes of the commit are persisted or
+//! none of them, and the database can be reopened to resume from the last commit, e.g., a devnet
+//! or a long test campaign.
+//!
+//! The compiled artifacts are cached in memory as [`MemoryDB`](crate::db::MemoryDB) does, and
+//! are optionally loaded from and written through to the [`DiskCache`], which stores the
+//! artifacts as the native shared libraries, see `CompileOptions::disk_cache` of the `dora`
+//! crate.
+
+use std::{path::Path, sync::Arc};
+
+use dora_primitives::{Address, B256, Bytecode, Bytes, KECCAK_EMPTY, U256};
+use redb::{ReadableTable, TableDefinition};
+use rustc_hash::FxHashMap;
+use thiserror::Error;
+
+use crate::{
+    account::{Account, AccountInfo},
+    artifact::{Artifact, ArtifactKey, SymbolArtifact},
+    cache::{ArtifactCache, DiskCache},
+    db::{Database, DatabaseCommit, StorageSlot},
+    trie::is_state_account,
+};
+
+/// The accounts from the address to the encoded account, see [`encode_account`].
+const ACCOUNTS: TableDefinition<&[u8], &[u8]> = TableDefinition::new("accounts");
+/// The contract code from the code hash to the original bytes.
+const CONTRACTS: TableDefinition<&[u8], &[u8]> = TableDefinition::new("contracts");
+/// The non-zero storage slots from the address and the slot key to the value.
+const STORAGE: TableDefinition<&[u8], &[u8]> = TableDefinition::new("storage");
+/// The block hashes from the block number to the hash.
+const BLOCK_HASHES: TableDefinition<&[u8], &[u8]> = TableDefinition::new("block_hashes");
+
+/// The length of the encoded account, i.e., the nonce, the balance and the code hash.
+const ACCOUNT_LEN: usize = 8 + 32 + 32;
+/// The length of the storage key, i.e., the address and the slot key.
+const STORAGE_KEY_LEN: usize = 20 + 32;
+
+/// An error that occurs during the disk database access.
+#[derive(Error, Debug)]
+pub enum DiskDBError {
+    #[error(transparent)]
+    Database(#[from] redb::DatabaseError),
+    #[error(transparent)]
+    Transaction(#[from] redb::TransactionError),
+    #[error(transparent)]
+    Table(#[from] redb::TableError),
+    #[error(transparent)]
+    Storage(#[from] redb::StorageError),
+    #[error(transparent)]
+    Commit(#[from] redb::CommitError),
+    #[error("invalid {0} entry in the disk database")]
+    InvalidEntry(&'static str),
+}
+
+/// A persistent database which stores the state in a single file on the local disk, the cloned
+/// databases share the same store.
+///
+/// The trait methods which can not return an error, e.g., [`DatabaseCommit::commit`], panic when
+/// the store fails, use the `try_*` methods to handle the error instead.
+///
+/// # Example
+///
+/// ```ignore
+/// let db = DiskDB::open("/tmp/dora-devnet.redb")?;
+/// let mut vm = VM::new(VMContext::new(db, env, spec_id, compile_handler()));
+/// vm.transact_commit()?;
+/// // The committed state is loaded after the restart.
+/// let db = DiskDB::open("/tmp/dora-devnet.redb")?;
+/// ```
+#[derive(Clone)]
+pub struct DiskDB {
+    store: Arc<redb::Database>,
+    /// The compiled artifacts, note that the cache is shared by the cloned databases.
+    artifacts: ArtifactCache<SymbolArtifact>,
+    disk_cache: Option<DiskCache>,
+}
+
+unsafe impl Send for DiskDB {}
+unsafe impl Sync for DiskDB {}
+
+impl std::fmt::Debug for DiskDB {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DiskDB")
+            .field("artifacts", &self.artifacts)
+            .field("disk_cache", &self.disk_cache)
+            .finish_non_exhaustive()
+    }
+}
+
+impl DiskDB {
+    /// Opens the database file, the file is created if it does not exist.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, DiskDBError> {
+        let store = redb::Database::create(path)?;
+        // Create the tables, thus the read transactions can always open them.
+        let txn = store.begin_write()?;
+        for table in [ACCOUNTS, CONTRACTS, STORAGE, BLOCK_HASHES] {
+            txn.open_table(table)?;
+        }
+        txn.commit()?;
+        Ok(Self {
+            store: Arc::new(store),
+            artifacts: ArtifactCache::default(),
+            disk_cache: None,
+        })
+    }
+
+    /// Uses the artifact cache to store the compiled artifacts, see
+    /// [`MemoryDB::with_artifact_cache`](crate::db::MemoryDB::with_artifact_cache).
+    #[inline]
+    pub fn with_artifact_cache(mut self, cache: ArtifactCache<SymbolArtifact>) -> Self {
+        self.artifacts = cache;
+        self
+    }
+
+    /// Loads the compiled artifacts which are not in the memory from the disk cache, and writes
+    /// the artifacts set into the database through to the disk cache.
+    ///
+    /// Only the artifacts compiled with the object dump can be written, e.g., the EVM artifacts
+    /// compiled with `CompileOptions::disk_cache` of the `dora` crate, the others are kept in
+    /// the memory only.
+    #[inline]
+    pub fn with_disk_cache(mut self, disk_cache: DiskCache) -> Self {
+        self.disk_cache = Some(disk_cache);
+        self
+    }
+
+    /// Inserts a block hash for a given block number.
+    pub fn insert_block_hash(&self, number: U256, hash: B256) -> Result<(), DiskDBError> {
+        let txn = self.store.begin_write()?;
+        txn.open_table(BLOCK_HASHES)?
+            .insert(number.to_be_bytes::<32>().as_slice(), hash.as_slice())?;
+        txn.commit()?;
+        Ok(())
+    }
+
+    /// Inserts the contract with the balance at the address, the storage of the account is
+    /// cleared.
+    pub fn try_insert_contract(
+        &self,
+        address: Address,
+        bytecode: Bytecode,
+        balance: U256,
+    ) -> Result<(), DiskDBError> {
+        let info = AccountInfo {
+            balance,
+            nonce: 1,
+            code_hash: bytecode.hash_slow(),
+            code: Some(bytecode),
+        };
+        let mut account = Account::from(info);
+        account.mark_created();
+        account.mark_touch();
+        self.try_commit([(address, account)].into_iter().collect())
+    }
+
+    /// Sets the nonce, the balance and the storage of the account, the code is kept.
+    pub fn try_set_account(
+        &self,
+        address: Address,
+        nonce: u64,
+        balance: U256,
+        storage: FxHashMap<U256, U256>,
+    ) -> Result<(), DiskDBError> {
+        let info = self.try_basic(address)?.unwrap_or_else(AccountInfo::empty);
+        let mut account = Account::from(AccountInfo {
+            nonce,
+            balance,
+            ..info
+        });
+        account.storage = storage
+            .into_iter()
+            .map(|(key, value)| (key, StorageSlot::new_changed(U256::ZERO, value)))
+            .collect();
+        account.mark_created();
+        account.mark_touch();
+        self.try_commit([(address, account)].into_iter().collect())
+    }
+
+    /// Commits the state changes in one write transaction, the untouched accounts are skipped
+    /// and the self-destructed accounts and the touched empty accounts that did not exist, see
+    /// [`is_state_account`], are removed.
+    pub fn try_commit(&self, changes: FxHashMap<Address, Account>) -> Result<(), DiskDBError> {
+        let txn = self.store.begin_write()?;
+        {
+            let mut accounts = txn.open_table(ACCOUNTS)?;
+            let mut contracts = txn.open_table(CONTRACTS)?;
+            let mut storage = txn.open_table(STORAGE)?;
+            for (address, account) in changes {
+                if !account.is_touched() {
+                    continue;
+                }
+                let storage_cleared = account.is_created() || account.is_selfdestructed();
+                if storage_cleared {
+                    let start = storage_key(address, U256::ZERO);
+                    let end = storage_key(address, U256::MAX);
+                    storage.retain_in(start.as_slice()..=end.as_slice(), |_, _| false)?;
+                }
+                if account.is_selfdestructed() || !is_state_account(&account) {
+                    accounts.remove(address.as_slice())?;
+                    continue;
+                }
+                if let Some(code) = account
+                    .info
+                    .code
+                    .as_ref()
+                    .filter(|_| account.info.has_code())
+                {
+                    contracts.insert(
+                        account.info.code_hash.as_slice(),
+                        code.original_byte_slice(),
+                    )?;
+                }
+                accounts.insert(address.as_slice(), encode_account(&account.info).as_slice())?;
+                for (key, slot) in account.storage {
+                    if !storage_cleared && !slot.is_changed() {
+                        continue;
+                    }
+                    let key = storage_key(address, key);
+                    if slot.present_value.is_zero() {
+                        storage.remove(key.as_slice())?;
+                    } else {
+                        storage.insert(
+                            key.as_slice(),
+                            slot.present_value.to_be_bytes::<32>().as_slice(),
+                        )?;
+                    }
+                }
+            }
+        }
+        txn.commit()?;
+        Ok(())
+    }
+
+    fn try_basic(&self, address: Address) -> Result<Option<AccountInfo>, DiskDBError> {
+        let txn = self.store.begin_read()?;
+        let accounts = txn.open_table(ACCOUNTS)?;
+        let info = accounts.get(address.as_slice())?;
+        info.map(|info| decode_account(info.value())).transpose()
+    }
+
+    fn try_into_state(&self) -> Result<FxHashMap<Address, Account>, DiskDBError> {
+        let txn = self.store.begin_read()?;
+        let mut state = FxHashMap::default();
+        for entry in txn.open_table(ACCOUNTS)?.iter()? {
+            let (address, info) = entry?;
+            let address = Address::try_from(address.value())
+                .map_err(|_| DiskDBError::InvalidEntry("account"))?;
+            state.insert(address, Account::from(decode_account(info.value())?));
+        }
+        for entry in txn.open_table(STORAGE)?.iter()? {
+            let (key, value) = entry?;
+            let key = key.value();
+            if key.len() != STORAGE_KEY_LEN {
+                return Err(DiskDBError::InvalidEntry("storage"));
+            }
+            let (address, index) = key.split_at(20);
+            if let Some(account) = state.get_mut(&Address::from_slice(address)) {
+                account.storage.insert(
+                    U256::from_be_slice(index),
+                    StorageSlot::from(U256::from_be_slice(value.value())),
+                );
+            }
+        }
+        Ok(state)
+    }
+}
+
+impl Database for DiskDB {
+    type Error = DiskDBError;
+    type Artifact = SymbolArtifact;
+
+    fn basic(&self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        self.try_basic(address)
+    }
+
+    fn code_by_hash(&self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        let txn = self.store.begin_read()?;
+        let contracts = txn.open_table(CONTRACTS)?;
+        let code = contracts.get(code_hash.as_slice())?;
+        Ok(code
+            .map(|code| Bytecode::new(Bytes::copy_from_slice(code.value())))
+            .unwrap_or_default())
+    }
+
+    fn storage(&self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        let txn = self.store.begin_read()?;
+        let storage = txn.open_table(STORAGE)?;
+        let value = storage.get(storage_key(address, index).as_slice())?;
+        Ok(value
+            .map(|value| U256::from_be_slice(value.value()))
+            .unwrap_or_default())
+    }
+
+    fn block_hash(&self, number: U256) -> Result<B256, Self::Error> {
+        let txn = self.store.begin_read()?;
+        let block_hashes = txn.open_table(BLOCK_HASHES)?;
+        let hash = block_hashes.get(number.to_be_bytes::<32>().as_slice())?;
+        Ok(hash
+            .map(|hash| B256::from_slice(hash.value()))
+            .unwrap_or_default())
+    }
+
+    fn insert_contract(&mut self, address: Address, bytecode: Bytecode, balance: U256) {
+        self.try_insert_contract(address, bytecode, balance)
+            .expect("failed to insert the contract into the disk database");
+    }
+
+    fn set_account(
+        &mut self,
+        address: Address,
+        nonce: u64,
+        balance: U256,
+        storage: FxHashMap<U256, U256>,
+    ) {
+        self.try_set_account(address, nonce, balance, storage)
+            .expect("failed to set the account of the disk database");
+    }
+
+    fn get_artifact(&self, key: &ArtifactKey) -> Result<Option<Self::Artifact>, Self::Error> {
+        if let Some(artifact) = self.artifacts.get(key) {
+            return Ok(Some(artifact));
+        }
+        // The artifacts which fail to load are compiled again.
+        let artifact = self
+            .disk_cache
+            .as_ref()
+            .and_then(|disk_cache| disk_cache.load(key).ok().flatten())
+            .map(SymbolArtifact::new);
+        if let Some(artifact) = &artifact {
+            self.artifacts.insert(*key, artifact.clone());
+        }
+        Ok(artifact)
+    }
+
+    fn set_artifact(&mut self, key: ArtifactKey, artifact: Self::Artifact) {
+        if let Some(disk_cache) = self
+            .disk_cache
+            .as_ref()
+            .filter(|disk_cache| !disk_cache.contains(&key))
+        {
+            // The disk cache is best-effort, the artifact is still usable when it fails to be
+            // stored, e.g., it is not compiled with the object dump.
+            if let Err(err) = disk_cache.store(&key, artifact.executor()) {
+                tracing::warn!(
+                    "Failed to store the artifact into the disk cache {}: {err}",
+                    disk_cache.dir().display()
+                );
+            }
+        }
+        self.artifacts.insert(key, artifact);
+    }
+
+    fn into_state(self) -> FxHashMap<Address, Account> {
+        self.try_into_state()
+            .expect("failed to read the state of the disk database")
+    }
+}
+
+impl DatabaseCommit for DiskDB {
+    /// Commits the state changes atomically, see [`DiskDB::try_commit`].
+    fn commit(&mut self, changes: FxHashMap<Address, Account>) {
+        self.try_commit(changes)
+            .expect("failed to commit the state into the disk database");
+    }
+}
+
+/// Encodes the account as the big-endian nonce, balance and code hash.
+fn encode_account(info: &AccountInfo) -> [u8; ACCOUNT_LEN] {
+    let mut bytes = [0u8; ACCOUNT_LEN];
+    bytes[..8].copy_from_slice(&info.nonce.to_be_bytes());
+    bytes[8..40].copy_from_slice(&info.balance.to_be_bytes::<32>());
+    bytes[40..].copy_from_slice(info.code_hash.as_slice());
+    bytes
+}
+
+/// Decodes the account, the code is loaded by the code hash when needed.
+fn decode_account(bytes: &[u8]) -> Result<AccountInfo, DiskDBError> {
+    if bytes.len() != ACCOUNT_LEN {
+        return Err(DiskDBError::InvalidEntry("account"));
+    }
+    let code_hash = B256::from_slice(&bytes[40..]);
+    Ok(AccountInfo {
+        nonce: u64::from_be_bytes(bytes[..8].try_into().expect("the nonce has 8 bytes")),
+        balance: U256::from_be_slice(&bytes[8..40]),
+        code_hash,
+        code: (code_hash == KECCAK_EMPTY).then(Bytecode::default),
+    })
+}
+
+fn storage_key(address: Address, index: U256) -> [u8; STORAGE_KEY_LEN] {
+    let mut key = [0u8; STORAGE_KEY_LEN];
+    key[..20].copy_from_slice(address.as_slice());
+    key[20..].copy_from_slice(&index.to_be_bytes::<32>());
+    key
+}
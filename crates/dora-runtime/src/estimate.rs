@@ -0,0 +1,141 @@
+//! The gas estimation of the transaction with the `eth_estimateGas` semantics.
+//!
+//! The transaction is executed with [`VM::transact`] without committing the state while the gas
+//! limit is searched between the gas used by the transaction and the highest gas limit allowed by
+//! the block and the caller balance. Because of the 63/64 rule of the calls and the gas refunds,
+//! the minimal gas limit is usually larger than the gas used, thus the search starts with the gas
+//! used plus the refunded gas and the call stipend scaled by 64/63, which is often enough. The
+//! EIP-7623 floor gas is a lower bound of the gas limit since Prague.
+
+use dora_primitives::{Bytes, InvalidTransaction, SpecId};
+
+use crate::{
+    constants::gas_cost::CALL_STIPEND,
+    db::{Database, DatabaseError},
+    result::{ExecutionResult, VMError},
+    vm::VM,
+};
+
+/// The estimated gas limit of the transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GasEstimate {
+    /// The minimal gas limit at which the transaction succeeds, or the highest gas limit tried
+    /// when the transaction fails at every gas level.
+    pub gas_limit: u64,
+    /// The result of the transaction executed with the gas limit.
+    pub result: ExecutionResult,
+}
+
+impl GasEstimate {
+    /// Returns whether the transaction succeeds with the estimated gas limit.
+    #[inline]
+    pub fn is_success(&self) -> bool {
+        self.result.is_success()
+    }
+
+    /// Returns the revert data when the transaction reverts at every gas level.
+    #[inline]
+    pub fn revert_data(&self) -> Option<&Bytes> {
+        match &self.result {
+            ExecutionResult::Revert { output, .. } => Some(output),
+            _ => None,
+        }
+    }
+}
+
+impl<DB: Database> VM<'_, DB> {
+    /// Estimate the minimal gas limit of the transaction in the environment, the state changes
+    /// are not committed and the gas limit of the transaction is restored afterwards.
+    ///
+    /// The gas limit of the transaction is the upper bound of the search when it covers the
+    /// initial gas, otherwise the block gas limit is used. The validation errors of the
+    /// transaction other than the too low gas limit are returned as is.
+    pub fn estimate_gas(&mut self) -> Result<GasEstimate, VMError> {
+        let gas_limit = self.env.tx.gas_limit;
+        let estimate = self.search_gas_limit();
+        self.env.tx.gas_limit = gas_limit;
+        estimate
+    }
+
+    fn search_gas_limit(&mut self) -> Result<GasEstimate, VMError> {
+        let spec_id = self.spec_id();
+        let gas = Self::initial_tx_gas(&self.env, spec_id);
+        let min_gas_limit = if spec_id.is_enabled_in(SpecId::PRAGUE) {
+            gas.initial_gas.max(gas.floor_gas)
+        } else {
+            gas.initial_gas
+        };
+        let mut hi = if self.env.tx.gas_limit >= min_gas_limit {
+            self.env.tx.gas_limit
+        } else {
+            self.env.block.gas_limit.saturating_to()
+        };
+        // The caller can not pay for the gas above its balance allowance.
+        if !self.env.tx.gas_price.is_zero() {
+            let balance = self
+                .db
+                .basic(self.env.tx.caller)
+                .map_err(|_| VMError::Database(DatabaseError))?
+                .map(|info| info.balance)
+                .unwrap_or_default();
+            let allowance = balance.saturating_sub(self.env.tx.value) / self.env.tx.gas_price;
+            hi = hi.min(allowance.saturating_to()).max(min_gas_limit);
+        }
+
+        // The transaction fails at every gas level when it fails with the highest gas limit.
+        let mut result = self.transact_with_gas_limit(hi)?;
+        if !result.is_success() {
+            return Ok(GasEstimate {
+                gas_limit: hi,
+                result,
+            });
+        }
+        let mut lo = result.gas_used().max(min_gas_limit) - 1;
+
+        // Most transactions succeed with the gas used plus the refunded gas, the remaining
+        // 1/64 of the gas kept by the calls and the call stipend.
+        let optimistic =
+            (result.gas_used() + result.gas_refunded() + CALL_STIPEND).saturating_mul(64) / 63;
+        if lo < optimistic && optimistic < hi {
+            match self.try_gas_limit(optimistic)? {
+                Some(optimistic_result) => {
+                    hi = optimistic;
+                    result = optimistic_result;
+                }
+                None => lo = optimistic,
+            }
+        }
+        while lo + 1 < hi {
+            // Bias the search towards the lower bound, which is usually close to the estimate.
+            let mid = (lo + (hi - lo) / 2).min(lo.saturating_mul(2));
+            match self.try_gas_limit(mid)? {
+                Some(mid_result) => {
+                    hi = mid;
+                    result = mid_result;
+                }
+                None => lo = mid,
+            }
+        }
+        Ok(GasEstimate {
+            gas_limit: hi,
+            result,
+        })
+    }
+
+    /// Execute the transaction with the gas limit and returns the result if it succeeds.
+    fn try_gas_limit(&mut self, gas_limit: u64) -> Result<Option<ExecutionResult>, VMError> {
+        match self.transact_with_gas_limit(gas_limit) {
+            Ok(result) => Ok(Some(result).filter(ExecutionResult::is_success)),
+            Err(VMError::Transaction(
+                InvalidTransaction::CallGasCostMoreThanGasLimit
+                | InvalidTransaction::GasFloorMoreThanGasLimit,
+            )) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn transact_with_gas_limit(&mut self, gas_limit: u64) -> Result<ExecutionResult, VMError> {
+        self.env.tx.gas_limit = gas_limit;
+        self.transact().map(|output| output.result)
+    }
+}
@@ -1,17 +1,22 @@
 #![allow(clippy::arc_with_non_send_sync)]
 
+use crate::aot;
 use crate::constants::ENTRYPOINT;
 use crate::context::{EVMEntryFunc, RuntimeContext, WASMEntryFunc};
 use crate::wasm::WASMInstance;
+use anyhow::{Result, anyhow, bail};
 use dora_primitives::config::OptimizationLevel;
+use libloading::Library;
 use melior::StringRef;
 use melior::ir::Module;
 use mlir_sys::{
     MlirExecutionEngine, mlirExecutionEngineCreate, mlirExecutionEngineDestroy,
-    mlirExecutionEngineLookup, mlirExecutionEngineRegisterSymbol,
+    mlirExecutionEngineDumpToObjectFile, mlirExecutionEngineLookup,
+    mlirExecutionEngineRegisterSymbol,
 };
 use parking_lot::RwLock;
 use std::fmt::Debug;
+use std::path::Path;
 use std::sync::Arc;
 
 /// The stack size at runtime, used for recursive program execution to prevent stack overflow
@@ -25,6 +30,8 @@ pub const RUNTIME_STACK_SIZE: usize = 128 * 1024 * 1024;
 #[derive(Default, Debug, Clone)]
 pub struct Executor {
     engine: ExecutionEngine,
+    /// The native shared library which the executor is loaded from, see [`Executor::load`].
+    library: Option<Arc<Library>>,
     pub(crate) kind: ExecuteKind,
 }
 
@@ -65,12 +72,97 @@ impl Executor {
     /// let executor = Executor::new(&module, &runtime_ctx, OptimizationLevel::Aggressive);
     /// ```
     pub fn new(module: &Module, opt_level: OptimizationLevel, kind: ExecuteKind) -> Self {
-        let engine = ExecutionEngine::new(module, opt_level as usize, &[], false);
+        Self::new_with_engine(
+            ExecutionEngine::new(module, opt_level as usize, &[], false),
+            kind,
+        )
+    }
+
+    /// Creates a new `Executor` like [`Executor::new`], and keeps the compiled native object code
+    /// in the execution engine, thus it can be emitted with [`Executor::emit_object`] or
+    /// [`Executor::emit_shared_library`].
+    pub fn new_with_object_dump(
+        module: &Module,
+        opt_level: OptimizationLevel,
+        kind: ExecuteKind,
+    ) -> Self {
+        Self::new_with_engine(
+            ExecutionEngine::new(module, opt_level as usize, &[], true),
+            kind,
+        )
+    }
+
+    fn new_with_engine(engine: ExecutionEngine, kind: ExecuteKind) -> Self {
         match kind {
             ExecuteKind::EVM => RuntimeContext::register_evm_symbols(&engine),
             ExecuteKind::WASM(_) => RuntimeContext::register_wasm_symbols(&engine),
         }
-        Self { engine, kind }
+        Self {
+            engine,
+            library: None,
+            kind,
+        }
+    }
+
+    /// Loads an `Executor` from the native shared library emitted by [`Executor::emit_shared_library`],
+    /// the MLIR and LLVM compile pipeline is not required.
+    ///
+    /// Note that only the EVM kind is supported, because the WASM kind requires the WASM module
+    /// instance which is built by the WASM compiler.
+    ///
+    /// # Example Usage:
+    /// ```ignore
+    /// executor.emit_shared_library("contract.so")?;
+    /// let executor = Executor::load("contract.so", ExecuteKind::EVM)?;
+    /// ```
+    pub fn load<P: AsRef<Path>>(path: P, kind: ExecuteKind) -> Result<Self> {
+        if !matches!(kind, ExecuteKind::EVM) {
+            bail!("only the EVM executor can be loaded from the shared library");
+        }
+        // SAFETY: The library is emitted by `emit_shared_library` and has no initialization routines.
+        let library = unsafe { Library::new(path.as_ref())? };
+        for (symbol, ptr) in RuntimeContext::evm_symbols() {
+            // SAFETY: The slot is a pointer-sized writable global defined by the trampoline code.
+            unsafe {
+                let slot =
+                    library.get::<*mut *const fn() -> ()>(aot::slot_name(symbol).as_bytes())?;
+                **slot = ptr;
+            }
+        }
+        Ok(Self {
+            engine: ExecutionEngine::default(),
+            library: Some(Arc::new(library)),
+            kind,
+        })
+    }
+
+    /// Emits the compiled native object code to the file, the executor must be created by
+    /// [`Executor::new_with_object_dump`].
+    pub fn emit_object<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        if !self.engine.object_dump {
+            bail!("the executor is not created with the object dump enabled");
+        }
+        let path = path
+            .as_ref()
+            .to_str()
+            .ok_or_else(|| anyhow!("invalid object file path"))?;
+        self.engine.dump_to_object_file(path);
+        Ok(())
+    }
+
+    /// Emits the compiled code as a native shared library which can be loaded with [`Executor::load`],
+    /// the executor must be created by [`Executor::new_with_object_dump`].
+    pub fn emit_shared_library<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let object = path.with_extension("o");
+        self.emit_object(&object)?;
+        let symbols: Vec<&str> = RuntimeContext::evm_symbols()
+            .into_iter()
+            .map(|(symbol, _)| symbol)
+            .collect();
+        let result = aot::link_shared_library(&object, path, &symbols);
+        let _ = std::fs::remove_file(&object);
+        result
     }
     /// Retrieves the EVM main entry point function from the execution engine.
     ///
@@ -120,7 +212,16 @@ impl Executor {
     /// Searches a symbol in a module and returns a pointer to it.
     #[inline]
     pub fn lookup(&self, name: &str) -> *mut () {
-        self.engine.lookup(name)
+        match &self.library {
+            // SAFETY: The symbol is only used as an opaque pointer.
+            Some(library) => unsafe {
+                library
+                    .get::<*mut ()>(name.as_bytes())
+                    .map(|symbol| *symbol)
+                    .unwrap_or(std::ptr::null_mut())
+            },
+            None => self.engine.lookup(name),
+        }
     }
 }
 
@@ -134,6 +235,7 @@ pub fn ciface_name<S: AsRef<str>>(name: S) -> String {
 #[derive(Debug)]
 pub struct ExecutionEngine {
     raw: Arc<MlirExecutionEngine>,
+    object_dump: bool,
 }
 
 unsafe impl Send for ExecutionEngine {}
@@ -143,6 +245,7 @@ impl Clone for ExecutionEngine {
     fn clone(&self) -> Self {
         ExecutionEngine {
             raw: Arc::clone(&self.raw),
+            object_dump: self.object_dump,
         }
     }
 }
@@ -153,6 +256,7 @@ impl Default for ExecutionEngine {
             raw: Arc::new(MlirExecutionEngine {
                 ptr: std::ptr::null_mut(),
             }),
+            object_dump: false,
         }
     }
 }
@@ -179,9 +283,16 @@ impl ExecutionEngine {
                     enable_object_dump,
                 ))
             },
+            object_dump: enable_object_dump,
         }
     }
 
+    /// Dumps the compiled object code to the file.
+    #[inline]
+    pub fn dump_to_object_file(&self, path: &str) {
+        unsafe { mlirExecutionEngineDumpToObjectFile(*self.raw, StringRef::new(path).to_raw()) }
+    }
+
     /// Searches a symbol in a module and returns a pointer to it.
     #[inline]
     pub fn lookup(&self, name: &str) -> *mut () {
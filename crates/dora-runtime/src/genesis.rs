@@ -0,0 +1,130 @@
+//! The geth-style genesis allocation, i.e., the `alloc` field of the genesis file, which is used
+//! to load the pre-state into [`MemoryDB`] and dump the committed state out of it.
+
+use std::collections::BTreeMap;
+
+use dora_primitives::{Address, B256, Bytes, U256};
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
+
+use crate::db::MemoryDB;
+
+/// An account of the genesis allocation.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenesisAccount {
+    /// The account balance.
+    #[serde(default)]
+    pub balance: U256,
+    /// The account nonce, both the hex string and the JSON number are accepted.
+    #[serde(default, with = "quantity")]
+    pub nonce: u64,
+    /// The account code.
+    #[serde(default, skip_serializing_if = "Bytes::is_empty")]
+    pub code: Bytes,
+    /// The non-zero storage slots.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub storage: BTreeMap<U256, U256>,
+}
+
+/// The genesis allocation from the account address to the account.
+pub type GenesisAlloc = BTreeMap<Address, GenesisAccount>;
+
+/// The pre-state to run the transactions against.
+///
+/// The JSON file is either the plain genesis allocation, or an object with the `alloc` field and
+/// the optional `blockHashes` field mapping the block number to the block hash, see below:
+///
+/// ```json
+/// {
+///     "alloc": {
+///         "0x1000000000000000000000000000000000000000": {
+///             "balance": "0xde0b6b3a7640000",
+///             "nonce": "0x1",
+///             "code": "0x600160005500",
+///             "storage": { "0x00": "0x01" }
+///         }
+///     },
+///     "blockHashes": { "0x1": "0x..." }
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Prestate {
+    /// The accounts.
+    pub alloc: GenesisAlloc,
+    /// The block hashes by the block number.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub block_hashes: BTreeMap<U256, B256>,
+}
+
+impl<'de> Deserialize<'de> for Prestate {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase", deny_unknown_fields)]
+        struct Full {
+            alloc: GenesisAlloc,
+            #[serde(default)]
+            block_hashes: BTreeMap<U256, B256>,
+        }
+
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Full(Full),
+            Alloc(GenesisAlloc),
+        }
+
+        Ok(match Repr::deserialize(deserializer)? {
+            Repr::Full(full) => Prestate {
+                alloc: full.alloc,
+                block_hashes: full.block_hashes,
+            },
+            Repr::Alloc(alloc) => Prestate {
+                alloc,
+                block_hashes: BTreeMap::new(),
+            },
+        })
+    }
+}
+
+impl Prestate {
+    /// Parses the pre-state from the JSON text.
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+
+    /// Builds a new [`MemoryDB`] containing the pre-state.
+    pub fn into_db(self) -> MemoryDB {
+        let mut db = MemoryDB::new();
+        db.insert_prestate(self);
+        db
+    }
+}
+
+/// Serializes the `u64` as the hex quantity, and deserializes it from the hex or decimal string or
+/// the JSON number.
+pub(crate) mod quantity {
+    use super::*;
+
+    pub(crate) fn serialize<S: Serializer>(value: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("{value:#x}"))
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Quantity {
+            Number(u64),
+            String(String),
+        }
+
+        match Quantity::deserialize(deserializer)? {
+            Quantity::Number(value) => Ok(value),
+            Quantity::String(value) => match value.strip_prefix("0x") {
+                Some(hex) => u64::from_str_radix(hex, 16),
+                None => value.parse(),
+            }
+            .map_err(de::Error::custom),
+        }
+    }
+}
@@ -21,6 +21,8 @@ pub struct Frame {
     pub is_eof_init: bool,
     /// Whether validate EOF bytecode.
     pub validate_eof: bool,
+    /// Whether the frame runs the init code of a `CREATE` or `CREATE2` contract creation.
+    pub is_create: bool,
 }
 
 pub type CallFrameHandle<'a, DB> =
@@ -3,6 +3,7 @@ use rustc_hash::FxHashMap;
 use std::{collections::hash_map::Entry, fmt::Debug};
 
 use crate::call::{CallKind, CallMessage, CallResult};
+use crate::inspector::Inspector;
 use crate::result::VMError;
 
 pub use dora_primitives::{AccountLoad, Eip7702CodeLoad, SelfDestructResult, StateLoad};
@@ -64,6 +65,11 @@ pub trait Host {
 
     /// Host for the call-like insturctions e.g., `CALL`, `CREATE`, etc.
     fn call(&mut self, msg: CallMessage) -> Result<CallResult, VMError>;
+
+    /// Returns the inspector attached to the host, if any.
+    fn inspector(&mut self) -> Option<&mut dyn Inspector> {
+        None
+    }
 }
 
 /// Result of a `set_storage` action.
@@ -0,0 +1,309 @@
+use dora_primitives::{Address, Bytes32, Log, OpCode, U256};
+use parking_lot::RwLock;
+
+use crate::call::{CallMessage, CallResult};
+use crate::result::ExecutionResult;
+
+pub mod call_tracer;
+pub mod eip3155;
+pub mod gas_profiler;
+pub mod prestate;
+
+pub use call_tracer::{CallFrame, CallTracer};
+pub use eip3155::Eip3155Tracer;
+pub use gas_profiler::{GasProfile, GasProfiler, GasStat, ProfileFrame};
+pub use prestate::{PrestateFrame, PrestateTracer};
+
+/// The execution state observed right before an opcode is executed.
+///
+/// The step is reported by the compiled code through the `dora_fn_tracing` runtime
+/// function, thus it is only available when the code was compiled with tracing enabled
+/// (see `EVMCompileOptions::tracing`).
+#[derive(Debug, Clone, Copy)]
+pub struct Step<'a> {
+    /// The program counter of the opcode.
+    pub pc: u64,
+    /// The opcode that is going to be executed.
+    pub opcode: u8,
+    /// The gas remaining before the static gas of the opcode is charged.
+    pub gas_remaining: u64,
+    /// The static gas cost of the opcode, note that the dynamic gas cost e.g., the memory
+    /// expansion cost is not included.
+    pub gas_cost: u64,
+    /// The stack items, ordered from bottom to top.
+    pub stack: &'a [Bytes32],
+    /// The current call frame memory.
+    pub memory: &'a [u8],
+    /// The return data of the last sub call.
+    pub return_data: &'a [u8],
+    /// The gas refund counter of the current call frame.
+    pub refund: i64,
+    /// The depth of the current call frame.
+    pub depth: usize,
+    /// The address of the account whose storage is being executed on.
+    pub address: Address,
+}
+
+impl Step<'_> {
+    /// Returns the opcode name, e.g., `PUSH1`, or `UNKNOWN` for undefined opcodes.
+    #[inline]
+    pub fn op_name(&self) -> &'static str {
+        OpCode::new(self.opcode)
+            .map(|op| op.as_str())
+            .unwrap_or("UNKNOWN")
+    }
+}
+
+/// The WASM operator names interned by [`wasm_op_id`], the index is the operator id.
+static WASM_OP_NAMES: RwLock<Vec<&'static str>> = RwLock::new(Vec::new());
+
+/// Returns the id of the WASM operator name, which is embedded into the WASM code compiled with
+/// tracing and resolved by [`wasm_op_name`] when the operator is executed.
+///
+/// The ids are only valid in the current process, note that the tracing artifacts are never
+/// stored in the on-disk artifact cache.
+pub fn wasm_op_id(name: &str) -> u32 {
+    if let Some(id) = WASM_OP_NAMES.read().iter().position(|op| *op == name) {
+        return id as u32;
+    }
+    let mut names = WASM_OP_NAMES.write();
+    if let Some(id) = names.iter().position(|op| *op == name) {
+        return id as u32;
+    }
+    names.push(Box::leak(Box::<str>::from(name)));
+    (names.len() - 1) as u32
+}
+
+/// Returns the WASM operator name of the id returned by [`wasm_op_id`].
+pub fn wasm_op_name(id: u32) -> &'static str {
+    WASM_OP_NAMES
+        .read()
+        .get(id as usize)
+        .copied()
+        .unwrap_or("Unknown")
+}
+
+/// The WASM operator observed right before it is executed.
+///
+/// The step is reported by the compiled code through the `dora_fn_wasm_tracing` runtime
+/// function, thus it is only available when the code was compiled with tracing enabled
+/// (see `WASMCompileOptions::tracing`).
+#[derive(Debug, Clone, Copy)]
+pub struct WasmStep {
+    /// The operator name, e.g., `I32Add`.
+    pub op: &'static str,
+    /// The offset of the operator in the WASM module.
+    pub offset: u64,
+    /// The metering gas cost of the operator given by `dora_compiler::wasm::meter::op_gas_cost`,
+    /// note that the gas charged by the host functions is not included.
+    pub gas_cost: u64,
+    /// The depth of the current call frame.
+    pub depth: usize,
+    /// The address of the account whose storage is being executed on.
+    pub address: Address,
+}
+
+/// The [`Inspector`] trait allows to observe the contract execution of the VM.
+///
+/// All methods have default empty implementations, thus an inspector only needs to
+/// implement the callbacks it is interested in. An inspector can be attached to the
+/// [`VMContext`](crate::context::VMContext) using `VMContext::with_inspector`.
+///
+/// # Example
+///
+/// ```ignore
+/// #[derive(Default)]
+/// struct OpcodeCounter {
+///     count: usize,
+/// }
+///
+/// impl Inspector for OpcodeCounter {
+///     fn step(&mut self, _step: &Step<'_>) {
+///         self.count += 1;
+///     }
+/// }
+///
+/// let mut counter = OpcodeCounter::default();
+/// let ctx = VMContext::new(db, env, spec_id, handler).with_inspector(&mut counter);
+/// VM::new(ctx).transact()?;
+/// println!("executed {} opcodes", counter.count);
+/// ```
+pub trait Inspector {
+    /// Called before each opcode is executed.
+    fn step(&mut self, _step: &Step<'_>) {}
+
+    /// Called before each WASM operator is executed.
+    fn wasm_step(&mut self, _step: &WasmStep) {}
+
+    /// Called before a call-like frame (`CALL`, `DELEGATECALL`, `EXTCALL`, etc.) is executed,
+    /// including the transaction top call.
+    fn call(&mut self, _msg: &CallMessage) {}
+
+    /// Called after a call-like frame is finished.
+    fn call_end(&mut self, _msg: &CallMessage, _result: &CallResult) {}
+
+    /// Called before a create-like frame (`CREATE`, `CREATE2` and `EOFCREATE`) is executed,
+    /// including the transaction top create.
+    fn create(&mut self, _msg: &CallMessage) {}
+
+    /// Called after a create-like frame is finished, `result.create_address` contains
+    /// the created contract address.
+    fn create_end(&mut self, _msg: &CallMessage, _result: &CallResult) {}
+
+    /// Called when a log is emitted.
+    fn log(&mut self, _log: &Log) {}
+
+    /// Called when a contract is self-destructed, `value` is the balance transferred to `target`.
+    fn selfdestruct(&mut self, _contract: Address, _target: Address, _value: U256) {}
+
+    /// Called after the transaction is executed, the gas used of the result is the gas used
+    /// by the whole transaction including the intrinsic gas and the refund.
+    fn transaction_end(&mut self, _result: &ExecutionResult) {}
+}
+
+impl<I: Inspector + ?Sized> Inspector for &mut I {
+    #[inline]
+    fn step(&mut self, step: &Step<'_>) {
+        (**self).step(step)
+    }
+
+    #[inline]
+    fn wasm_step(&mut self, step: &WasmStep) {
+        (**self).wasm_step(step)
+    }
+
+    #[inline]
+    fn call(&mut self, msg: &CallMessage) {
+        (**self).call(msg)
+    }
+
+    #[inline]
+    fn call_end(&mut self, msg: &CallMessage, result: &CallResult) {
+        (**self).call_end(msg, result)
+    }
+
+    #[inline]
+    fn create(&mut self, msg: &CallMessage) {
+        (**self).create(msg)
+    }
+
+    #[inline]
+    fn create_end(&mut self, msg: &CallMessage, result: &CallResult) {
+        (**self).create_end(msg, result)
+    }
+
+    #[inline]
+    fn log(&mut self, log: &Log) {
+        (**self).log(log)
+    }
+
+    #[inline]
+    fn selfdestruct(&mut self, contract: Address, target: Address, value: U256) {
+        (**self).selfdestruct(contract, target, value)
+    }
+
+    #[inline]
+    fn transaction_end(&mut self, result: &ExecutionResult) {
+        (**self).transaction_end(result)
+    }
+}
+
+/// Composes two inspectors, the callbacks are forwarded to the first inspector and then to the
+/// second one, e.g., `(Eip3155Tracer::new(std::io::stdout()), &mut profiler)`.
+impl<A: Inspector, B: Inspector> Inspector for (A, B) {
+    #[inline]
+    fn step(&mut self, step: &Step<'_>) {
+        self.0.step(step);
+        self.1.step(step);
+    }
+
+    #[inline]
+    fn wasm_step(&mut self, step: &WasmStep) {
+        self.0.wasm_step(step);
+        self.1.wasm_step(step);
+    }
+
+    #[inline]
+    fn call(&mut self, msg: &CallMessage) {
+        self.0.call(msg);
+        self.1.call(msg);
+    }
+
+    #[inline]
+    fn call_end(&mut self, msg: &CallMessage, result: &CallResult) {
+        self.0.call_end(msg, result);
+        self.1.call_end(msg, result);
+    }
+
+    #[inline]
+    fn create(&mut self, msg: &CallMessage) {
+        self.0.create(msg);
+        self.1.create(msg);
+    }
+
+    #[inline]
+    fn create_end(&mut self, msg: &CallMessage, result: &CallResult) {
+        self.0.create_end(msg, result);
+        self.1.create_end(msg, result);
+    }
+
+    #[inline]
+    fn log(&mut self, log: &Log) {
+        self.0.log(log);
+        self.1.log(log);
+    }
+
+    #[inline]
+    fn selfdestruct(&mut self, contract: Address, target: Address, value: U256) {
+        self.0.selfdestruct(contract, target, value);
+        self.1.selfdestruct(contract, target, value);
+    }
+
+    #[inline]
+    fn transaction_end(&mut self, result: &ExecutionResult) {
+        self.0.transaction_end(result);
+        self.1.transaction_end(result);
+    }
+}
+
+/// An inspector that prints the execution steps and frames to the standard output.
+///
+/// It is attached by default when the `DORA_TRACING` environment variable is set.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PrintInspector;
+
+impl Inspector for PrintInspector {
+    fn step(&mut self, step: &Step<'_>) {
+        println!(
+            "pc: {}, op: {}, opHex: {:x}, opName: {}, gas: 0x{:x}, memSize: {}, stack: {:?}, stackSize: {}, depth: {}",
+            step.pc,
+            step.opcode,
+            step.opcode,
+            step.op_name(),
+            step.gas_remaining,
+            step.memory.len(),
+            step.stack
+                .iter()
+                .map(|v| format!("{:#x}", v.to_u256()))
+                .collect::<Vec<_>>(),
+            step.stack.len(),
+            step.depth,
+        );
+    }
+
+    fn call(&mut self, msg: &CallMessage) {
+        println!("info: call msg {:?}", msg);
+    }
+
+    fn call_end(&mut self, _msg: &CallMessage, result: &CallResult) {
+        println!("info: call ret {:?}", result);
+    }
+
+    fn create(&mut self, msg: &CallMessage) {
+        println!("info: create msg {:?}", msg);
+    }
+
+    fn create_end(&mut self, _msg: &CallMessage, result: &CallResult) {
+        println!("info: create ret {:?}", result);
+    }
+}
@@ -0,0 +1,253 @@
+//! Reference: [geth callTracer](https://geth.ethereum.org/docs/developers/evm-tracing/built-in-tracers#call-tracer)
+
+use dora_primitives::{Address, B256, Bytes, Log, U256, as_usize_saturated};
+use serde::{Serialize, Serializer};
+
+use crate::call::{CallKind, CallMessage, CallResult};
+use crate::inspector::Inspector;
+
+/// The selector of the `Error(string)` revert data.
+const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+
+/// A call frame of the call tree, which has the same layout as the `callTracer` output of geth.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallFrame {
+    /// The call type e.g., `CALL`, `DELEGATECALL`, `CREATE2`, `SELFDESTRUCT`, etc.
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// The caller address.
+    pub from: Address,
+    /// The callee address, for the create-like frames it is the created address.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<Address>,
+    /// The transferred value, it is not set for the delegate and static calls.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<U256>,
+    /// The gas limit of the frame.
+    #[serde(serialize_with = "serialize_hex_u64")]
+    pub gas: u64,
+    /// The gas used of the frame.
+    #[serde(serialize_with = "serialize_hex_u64")]
+    pub gas_used: u64,
+    /// The call data, or the init code for the create-like frames.
+    pub input: Bytes,
+    /// The return data, or the deployed code for the create-like frames.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<Bytes>,
+    /// The error message when the frame is failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// The decoded `Error(string)` revert reason.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revert_reason: Option<String>,
+    /// The logs emitted by the frame, only recorded with [`CallTracer::with_log`].
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub logs: Vec<CallLog>,
+    /// The sub call frames.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub calls: Vec<CallFrame>,
+}
+
+/// A log emitted in the call frame.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct CallLog {
+    /// The address of the contract which emits the log.
+    pub address: Address,
+    /// The log topics.
+    pub topics: Vec<B256>,
+    /// The log data.
+    pub data: Bytes,
+}
+
+impl CallFrame {
+    fn new(msg: &CallMessage) -> Self {
+        let is_delegate_or_static = matches!(
+            msg.kind,
+            CallKind::Delegatecall
+                | CallKind::Staticcall
+                | CallKind::ExtDelegatecall
+                | CallKind::ExtStaticcall
+        );
+        // The caller of the delegate call message is the caller of the parent frame.
+        let from = if matches!(msg.kind, CallKind::Delegatecall | CallKind::ExtDelegatecall) {
+            msg.recipient
+        } else {
+            msg.caller
+        };
+        Self {
+            kind: call_kind_name(&msg.kind).to_string(),
+            from,
+            to: (!is_create_kind(&msg.kind)).then_some(msg.code_address),
+            value: (!is_delegate_or_static).then_some(msg.value),
+            gas: msg.gas_limit,
+            input: msg.input.clone(),
+            ..Default::default()
+        }
+    }
+
+    fn finish(&mut self, result: &CallResult) {
+        self.gas_used = result.gas_used();
+        if let Some(address) = result.create_address {
+            self.to = Some(address);
+        }
+        if !result.output.is_empty() {
+            self.output = Some(result.output.clone());
+        }
+        if !result.status.is_ok() {
+            if result.status.is_revert() {
+                self.error = Some("execution reverted".to_string());
+                self.revert_reason = decode_revert_reason(&result.output);
+            } else {
+                self.error = Some(format!("{:?}", result.status));
+            }
+            // The logs of the failed frame and its sub frames are reverted.
+            self.clear_logs();
+        }
+    }
+
+    fn clear_logs(&mut self) {
+        self.logs.clear();
+        for call in &mut self.calls {
+            call.clear_logs();
+        }
+    }
+}
+
+/// An [`Inspector`] which collects the call tree of the transaction, including `CALL`, `CALLCODE`,
+/// `DELEGATECALL`, `STATICCALL`, `EXT*CALL`, `CREATE`, `CREATE2`, `EOFCREATE` and `SELFDESTRUCT`.
+///
+/// # Example
+///
+/// ```ignore
+/// let mut tracer = CallTracer::new();
+/// let ctx = VMContext::new(db, env, spec_id, handler).with_inspector(&mut tracer);
+/// VM::new(ctx).transact()?;
+/// println!("{}", serde_json::to_string_pretty(&tracer.into_call_frame())?);
+/// ```
+#[derive(Debug, Default)]
+pub struct CallTracer {
+    with_log: bool,
+    stack: Vec<CallFrame>,
+    root: Option<CallFrame>,
+}
+
+impl CallTracer {
+    /// Creates a new call tracer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the logs emitted in each call frame.
+    pub fn with_log(mut self) -> Self {
+        self.with_log = true;
+        self
+    }
+
+    /// Returns the root call frame of the transaction, if any.
+    pub fn call_frame(&self) -> Option<&CallFrame> {
+        self.root.as_ref()
+    }
+
+    /// Consumes the tracer and returns the root call frame of the transaction, if any.
+    pub fn into_call_frame(self) -> Option<CallFrame> {
+        self.root
+    }
+
+    fn frame_start(&mut self, msg: &CallMessage) {
+        self.stack.push(CallFrame::new(msg));
+    }
+
+    fn frame_end(&mut self, result: &CallResult) {
+        let Some(mut frame) = self.stack.pop() else {
+            return;
+        };
+        frame.finish(result);
+        match self.stack.last_mut() {
+            Some(parent) => parent.calls.push(frame),
+            None => self.root = Some(frame),
+        }
+    }
+}
+
+impl Inspector for CallTracer {
+    fn call(&mut self, msg: &CallMessage) {
+        self.frame_start(msg);
+    }
+
+    fn call_end(&mut self, _msg: &CallMessage, result: &CallResult) {
+        self.frame_end(result);
+    }
+
+    fn create(&mut self, msg: &CallMessage) {
+        self.frame_start(msg);
+    }
+
+    fn create_end(&mut self, _msg: &CallMessage, result: &CallResult) {
+        self.frame_end(result);
+    }
+
+    fn log(&mut self, log: &Log) {
+        if !self.with_log {
+            return;
+        }
+        if let Some(frame) = self.stack.last_mut() {
+            frame.logs.push(CallLog {
+                address: log.address,
+                topics: log.data.topics().to_vec(),
+                data: log.data.data.clone(),
+            });
+        }
+    }
+
+    fn selfdestruct(&mut self, contract: Address, target: Address, value: U256) {
+        if let Some(frame) = self.stack.last_mut() {
+            frame.calls.push(CallFrame {
+                kind: "SELFDESTRUCT".to_string(),
+                from: contract,
+                to: Some(target),
+                value: Some(value),
+                ..Default::default()
+            });
+        }
+    }
+}
+
+/// Decodes the `Error(string)` revert reason from the revert data.
+pub fn decode_revert_reason(output: &[u8]) -> Option<String> {
+    let data = output.strip_prefix(&ERROR_SELECTOR)?;
+    // ABI layout: offset (32 bytes), length (32 bytes), string data.
+    let offset = as_usize_saturated!(U256::try_from_be_slice(data.get(..32)?)?);
+    let len_end = offset.checked_add(32)?;
+    let len = as_usize_saturated!(U256::try_from_be_slice(data.get(offset..len_end)?)?);
+    let reason = data.get(len_end..len_end.checked_add(len)?)?;
+    String::from_utf8(reason.to_vec()).ok()
+}
+
+#[inline]
+fn is_create_kind(kind: &CallKind) -> bool {
+    matches!(
+        kind,
+        CallKind::Create | CallKind::Create2 | CallKind::EofCreate
+    )
+}
+
+fn call_kind_name(kind: &CallKind) -> &'static str {
+    match kind {
+        CallKind::Call => "CALL",
+        CallKind::Callcode => "CALLCODE",
+        CallKind::Delegatecall => "DELEGATECALL",
+        CallKind::Staticcall => "STATICCALL",
+        CallKind::ExtCall => "EXTCALL",
+        CallKind::ExtStaticcall => "EXTSTATICCALL",
+        CallKind::ExtDelegatecall => "EXTDELEGATECALL",
+        CallKind::Create => "CREATE",
+        CallKind::Create2 => "CREATE2",
+        CallKind::EofCreate => "EOFCREATE",
+        CallKind::ReturnContract => "RETURNCONTRACT",
+    }
+}
+
+fn serialize_hex_u64<S: Serializer>(value: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format!("{value:#x}"))
+}
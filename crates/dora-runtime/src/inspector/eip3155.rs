@@ -0,0 +1,152 @@
+//! Reference: [EIP-3155](https://eips.ethereum.org/EIPS/eip-3155)
+
+use std::io::Write;
+
+use dora_primitives::U256;
+use serde::Serialize;
+
+use crate::inspector::{Inspector, Step};
+use crate::result::ExecutionResult;
+
+/// A single EIP-3155 trace line which is emitted for every executed opcode.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Output<'a> {
+    pc: u64,
+    op: u8,
+    gas: String,
+    gas_cost: String,
+    stack: Vec<String>,
+    depth: usize,
+    return_data: String,
+    refund: String,
+    mem_size: usize,
+    op_name: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    memory: Option<String>,
+}
+
+/// The EIP-3155 summary line which is emitted at the end of the transaction.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Summary {
+    output: String,
+    gas_used: String,
+    pass: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fork: Option<String>,
+}
+
+/// An [`Inspector`] which writes the line-delimited JSON trace defined by EIP-3155, the
+/// same format used by `geth` and `revm`, so that the execution of Dora can be diffed
+/// against them.
+///
+/// Note that the `gasCost` field is the static gas cost of the opcode which is known at
+/// the compile time, and the `stateRoot` field is omitted from the summary line because
+/// the state is not committed during the execution.
+///
+/// # Example
+///
+/// ```ignore
+/// let tracer = Eip3155Tracer::new(std::io::stderr()).with_fork("CANCUN");
+/// let ctx = VMContext::new(db, env, spec_id, handler).with_inspector(tracer);
+/// VM::new(ctx).transact()?;
+/// ```
+#[derive(Debug)]
+pub struct Eip3155Tracer<W: Write> {
+    output: W,
+    include_memory: bool,
+    print_summary: bool,
+    fork: Option<String>,
+}
+
+impl<W: Write> Eip3155Tracer<W> {
+    /// Creates a new EIP-3155 tracer which writes the trace into the output.
+    pub fn new(output: W) -> Self {
+        Self {
+            output,
+            include_memory: false,
+            print_summary: true,
+            fork: None,
+        }
+    }
+
+    /// Include the hex encoded memory in each trace line.
+    pub fn with_memory(mut self) -> Self {
+        self.include_memory = true;
+        self
+    }
+
+    /// Do not print the summary line at the end of the transaction.
+    pub fn without_summary(mut self) -> Self {
+        self.print_summary = false;
+        self
+    }
+
+    /// Set the fork name printed in the summary line.
+    pub fn with_fork(mut self, fork: impl Into<String>) -> Self {
+        self.fork = Some(fork.into());
+        self
+    }
+
+    /// Consumes the tracer and returns the inner output.
+    pub fn into_inner(self) -> W {
+        self.output
+    }
+
+    /// Writes the value as one line with a single write, thus the lines of the tracers sharing
+    /// the output (e.g., the standard error) do not interleave.
+    fn write_value(&mut self, value: &impl Serialize) -> std::io::Result<()> {
+        let mut line = serde_json::to_vec(value)?;
+        line.push(b'\n');
+        self.output.write_all(&line)?;
+        self.output.flush()
+    }
+}
+
+impl<W: Write> Inspector for Eip3155Tracer<W> {
+    fn step(&mut self, step: &Step<'_>) {
+        let output = Output {
+            pc: step.pc,
+            op: step.opcode,
+            gas: hex_number(step.gas_remaining),
+            gas_cost: hex_number(step.gas_cost),
+            stack: step
+                .stack
+                .iter()
+                .map(|v| hex_number_u256(&v.to_u256()))
+                .collect(),
+            depth: step.depth,
+            return_data: format!("0x{}", hex::encode(step.return_data)),
+            refund: hex_number(step.refund.max(0) as u64),
+            mem_size: step.memory.len(),
+            op_name: step.op_name(),
+            memory: self
+                .include_memory
+                .then(|| format!("0x{}", hex::encode(step.memory))),
+        };
+        let _ = self.write_value(&output);
+    }
+
+    fn transaction_end(&mut self, result: &ExecutionResult) {
+        if self.print_summary {
+            let summary = Summary {
+                output: result.output().cloned().unwrap_or_default().to_string(),
+                gas_used: hex_number(result.gas_used()),
+                pass: result.is_success(),
+                fork: self.fork.clone(),
+            };
+            let _ = self.write_value(&summary);
+        }
+    }
+}
+
+#[inline]
+fn hex_number(value: u64) -> String {
+    format!("0x{value:x}")
+}
+
+#[inline]
+fn hex_number_u256(value: &U256) -> String {
+    format!("0x{value:x}")
+}
@@ -0,0 +1,331 @@
+use std::collections::BTreeMap;
+use std::fmt::{self, Write};
+use std::mem;
+
+use dora_primitives::Address;
+
+use crate::call::{CallMessage, CallResult};
+use crate::inspector::{Inspector, Step, WasmStep};
+
+/// The maximum number of rows printed for each section of the summary table.
+const SUMMARY_ROWS: usize = 20;
+
+/// The invocation count and the gas spent of an opcode, a program counter or a call frame.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GasStat {
+    /// The number of invocations.
+    pub count: u64,
+    /// The gas spent, the gas spent by the sub call frames is not included.
+    pub gas: u64,
+}
+
+impl GasStat {
+    #[inline]
+    fn add(&mut self, count: u64, gas: u64) {
+        self.count += count;
+        self.gas += gas;
+    }
+}
+
+/// A profiled call frame, which is identified by the code label (e.g., the contract address
+/// or the WASM function) and the call depth.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ProfileFrame {
+    /// The code label of the frame.
+    pub label: String,
+    /// The call depth of the frame.
+    pub depth: usize,
+}
+
+impl fmt::Display for ProfileFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{}", self.label, self.depth)
+    }
+}
+
+/// The gas profile which records the gas and invocation counts per opcode, per program
+/// counter and per call frame.
+#[derive(Debug, Clone, Default)]
+pub struct GasProfile {
+    /// The stats keyed by the opcode name.
+    pub opcodes: BTreeMap<String, GasStat>,
+    /// The stats keyed by the code label and the program counter.
+    pub pcs: BTreeMap<(String, u64), GasStat>,
+    /// The stats keyed by the call frame, `count` is the number of the frame invocations.
+    pub frames: BTreeMap<ProfileFrame, GasStat>,
+    /// The gas spent keyed by the folded call stack.
+    stacks: BTreeMap<String, u64>,
+}
+
+impl GasProfile {
+    /// Records a call frame invocation.
+    fn enter(&mut self, frame: &ProfileFrame) {
+        self.frames.entry(frame.clone()).or_default().add(1, 0);
+    }
+
+    /// Records `count` invocations of the opcode `op` at `pc` which spent `gas` in total,
+    /// `frames` is the call stack from the outermost frame to the current frame.
+    fn record(&mut self, frames: &[ProfileFrame], op: &str, pc: u64, count: u64, gas: u64) {
+        let Some(frame) = frames.last() else {
+            return;
+        };
+        self.opcodes
+            .entry(op.to_string())
+            .or_default()
+            .add(count, gas);
+        self.pcs
+            .entry((frame.label.clone(), pc))
+            .or_default()
+            .add(count, gas);
+        self.frames.entry(frame.clone()).or_default().add(0, gas);
+        let mut stack = frames
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(";");
+        stack.push(';');
+        stack.push_str(op);
+        *self.stacks.entry(stack).or_default() += gas;
+    }
+
+    /// Returns the total gas spent.
+    pub fn total_gas(&self) -> u64 {
+        self.opcodes.values().map(|stat| stat.gas).sum()
+    }
+
+    /// Returns the profile in the folded stack format, which can be consumed by the flamegraph
+    /// tools e.g., `inferno-flamegraph` and `flamegraph.pl`.
+    ///
+    /// Each line is `frame;frame;...;opcode gas`, where the frame is `label@depth`.
+    pub fn folded_stacks(&self) -> String {
+        let mut output = String::new();
+        for (stack, gas) in &self.stacks {
+            if *gas > 0 {
+                let _ = writeln!(output, "{stack} {gas}");
+            }
+        }
+        output
+    }
+
+    /// Returns a human-readable summary table, the rows are sorted by the gas spent.
+    pub fn summary(&self) -> String {
+        let total = self.total_gas();
+        let mut output = String::new();
+        let _ = writeln!(output, "Total gas: {total}");
+        write_section(
+            &mut output,
+            "Opcode",
+            self.opcodes.iter().map(|(op, stat)| (op.clone(), *stat)),
+            total,
+        );
+        write_section(
+            &mut output,
+            "PC",
+            self.pcs
+                .iter()
+                .map(|((label, pc), stat)| (format!("{label}:{pc}"), *stat)),
+            total,
+        );
+        write_section(
+            &mut output,
+            "Frame",
+            self.frames
+                .iter()
+                .map(|(frame, stat)| (frame.to_string(), *stat)),
+            total,
+        );
+        output
+    }
+}
+
+fn write_section(
+    output: &mut String,
+    title: &str,
+    rows: impl Iterator<Item = (String, GasStat)>,
+    total: u64,
+) {
+    let mut rows: Vec<_> = rows.collect();
+    rows.sort_by(|(a_name, a), (b_name, b)| b.gas.cmp(&a.gas).then_with(|| a_name.cmp(b_name)));
+    let _ = writeln!(output);
+    let _ = writeln!(
+        output,
+        "{:<50} {:>10} {:>14} {:>8}",
+        title, "Count", "Gas", "%"
+    );
+    for (name, stat) in rows.iter().take(SUMMARY_ROWS) {
+        let percent = if total == 0 {
+            0.0
+        } else {
+            stat.gas as f64 * 100.0 / total as f64
+        };
+        let _ = writeln!(
+            output,
+            "{:<50} {:>10} {:>14} {:>7.2}%",
+            name, stat.count, stat.gas, percent
+        );
+    }
+}
+
+/// The opcode which is being executed in the frame, the gas is settled at the next step
+/// or at the end of the frame.
+#[derive(Debug)]
+struct PendingStep {
+    op: &'static str,
+    pc: u64,
+    gas_remaining: u64,
+    /// The gas used by the sub call frames of the opcode.
+    sub_call_gas: u64,
+}
+
+#[derive(Debug, Default)]
+struct ActiveFrame {
+    /// The code address, it is resolved at the first step for the create-like frames.
+    address: Option<Address>,
+    /// Whether the frame invocation is recorded, i.e., the frame executed its first step.
+    entered: bool,
+    pending: Option<PendingStep>,
+}
+
+/// An [`Inspector`] which profiles the gas spent by the EVM and WASM execution per opcode, per
+/// program counter and per call frame (contract address + depth).
+///
+/// The gas of an opcode is the difference of the remaining gas between two consecutive steps
+/// in the same frame, thus the dynamic gas cost is included, while the gas used by the sub
+/// call frames is excluded. The gas of a WASM operator is its metering cost reported by the
+/// [`WasmStep`], and the program counter is the operator offset in the module.
+///
+/// # Example
+///
+/// ```ignore
+/// let mut profiler = GasProfiler::new();
+/// let ctx = VMContext::new(db, env, spec_id, handler).with_inspector(&mut profiler);
+/// VM::new(ctx).transact()?;
+/// let profile = profiler.into_profile();
+/// println!("{}", profile.summary());
+/// std::fs::write("gas.folded", profile.folded_stacks())?;
+/// ```
+#[derive(Debug, Default)]
+pub struct GasProfiler {
+    profile: GasProfile,
+    frames: Vec<ActiveFrame>,
+}
+
+impl GasProfiler {
+    /// Creates a new gas profiler.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the recorded gas profile.
+    pub fn profile(&self) -> &GasProfile {
+        &self.profile
+    }
+
+    /// Consumes the profiler and returns the recorded gas profile.
+    pub fn into_profile(self) -> GasProfile {
+        self.profile
+    }
+
+    fn profile_frames(&self) -> Vec<ProfileFrame> {
+        self.frames
+            .iter()
+            .enumerate()
+            .map(|(i, frame)| ProfileFrame {
+                label: frame.address.unwrap_or_default().to_string(),
+                depth: i + 1,
+            })
+            .collect()
+    }
+
+    fn settle(&mut self, pending: PendingStep, gas_remaining: u64) {
+        let gas = pending
+            .gas_remaining
+            .saturating_sub(gas_remaining)
+            .saturating_sub(pending.sub_call_gas);
+        let frames = self.profile_frames();
+        self.profile.record(&frames, pending.op, pending.pc, 1, gas);
+    }
+
+    fn frame_start(&mut self, address: Option<Address>) {
+        self.frames.push(ActiveFrame {
+            address,
+            ..Default::default()
+        });
+    }
+
+    /// Marks the current frame as entered at its first step and records the frame invocation.
+    fn enter(&mut self, address: Address) {
+        let Some(frame) = self.frames.last_mut() else {
+            return;
+        };
+        frame.address.get_or_insert(address);
+        if !mem::replace(&mut frame.entered, true) {
+            let frames = self.profile_frames();
+            if let Some(frame) = frames.last() {
+                self.profile.enter(frame);
+            }
+        }
+    }
+
+    fn frame_end(&mut self, result: &CallResult) {
+        if let Some(pending) = self
+            .frames
+            .last_mut()
+            .and_then(|frame| frame.pending.take())
+        {
+            self.settle(pending, result.gas_remaining);
+        }
+        self.frames.pop();
+        if let Some(pending) = self
+            .frames
+            .last_mut()
+            .and_then(|frame| frame.pending.as_mut())
+        {
+            pending.sub_call_gas += result.gas_used();
+        }
+    }
+}
+
+impl Inspector for GasProfiler {
+    fn step(&mut self, step: &Step<'_>) {
+        self.enter(step.address);
+        let Some(frame) = self.frames.last_mut() else {
+            return;
+        };
+        let pending = frame.pending.replace(PendingStep {
+            op: step.op_name(),
+            pc: step.pc,
+            gas_remaining: step.gas_remaining,
+            sub_call_gas: 0,
+        });
+        if let Some(pending) = pending {
+            self.settle(pending, step.gas_remaining);
+        }
+    }
+
+    fn wasm_step(&mut self, step: &WasmStep) {
+        if self.frames.is_empty() {
+            return;
+        }
+        self.enter(step.address);
+        let frames = self.profile_frames();
+        self.profile
+            .record(&frames, step.op, step.offset, 1, step.gas_cost);
+    }
+
+    fn call(&mut self, msg: &CallMessage) {
+        self.frame_start(Some(msg.code_address));
+    }
+
+    fn call_end(&mut self, _msg: &CallMessage, result: &CallResult) {
+        self.frame_end(result);
+    }
+
+    fn create(&mut self, _msg: &CallMessage) {
+        self.frame_start(None);
+    }
+
+    fn create_end(&mut self, _msg: &CallMessage, result: &CallResult) {
+        self.frame_end(result);
+    }
+}
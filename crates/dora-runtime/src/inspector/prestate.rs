@@ -0,0 +1,207 @@
+//! Reference: [geth prestateTracer](https://geth.ethereum.org/docs/developers/evm-tracing/built-in-tracers#prestate-tracer)
+
+use std::collections::BTreeMap;
+
+use dora_primitives::{Address, B256, Bytes, U256};
+use serde::Serialize;
+
+use crate::account::{Account, AccountInfo};
+use crate::db::Database;
+use crate::journaled_state::{JournaledState, State};
+
+/// The account view of the `prestateTracer` output, the unchanged fields are omitted in the
+/// `post` view of the diff mode.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct AccountState {
+    /// The account balance.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub balance: Option<U256>,
+    /// The account nonce.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<u64>,
+    /// The account code.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<Bytes>,
+    /// The accessed storage slots.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub storage: BTreeMap<B256, B256>,
+}
+
+impl AccountState {
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.balance.is_none()
+            && self.nonce.is_none()
+            && self.code.is_none()
+            && self.storage.is_empty()
+    }
+}
+
+/// The `prestateTracer` output, which has the same layout as geth.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+pub enum PrestateFrame {
+    /// The state of all accounts accessed by the transaction before the execution.
+    Prestate(BTreeMap<Address, AccountState>),
+    /// The state of the modified accounts before and after the execution.
+    Diff {
+        /// The modified accounts before the execution, the created accounts are omitted.
+        pre: BTreeMap<Address, AccountState>,
+        /// The changed fields of the modified accounts after the execution, the self-destructed
+        /// accounts are omitted.
+        post: BTreeMap<Address, AccountState>,
+    },
+}
+
+/// A tracer which produces the `prestateTracer` output from the transaction state, i.e.,
+/// `ResultAndState::state` or `JournaledState::state`, and the database before the state is
+/// committed.
+///
+/// The storage values before the execution are taken from `StorageSlot::original_value` and the
+/// values after the execution are taken from `StorageSlot::present_value`.
+///
+/// # Example
+///
+/// ```ignore
+/// let mut vm = VM::new(VMContext::new(db, env, spec_id, handler));
+/// let result = vm.transact()?;
+/// let frame = PrestateTracer::new().diff_mode().trace(&vm.db, &result.state)?;
+/// println!("{}", serde_json::to_string_pretty(&frame)?);
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PrestateTracer {
+    diff_mode: bool,
+}
+
+impl PrestateTracer {
+    /// Creates a new prestate tracer in the normal mode.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Output both the state before and after the execution, which is the `diffMode` of geth.
+    pub fn diff_mode(mut self) -> Self {
+        self.diff_mode = true;
+        self
+    }
+
+    /// Builds the prestate frame from the journaled state of an ongoing transaction.
+    pub fn trace_journaled_state<DB: Database>(
+        &self,
+        db: &DB,
+        journaled_state: &JournaledState,
+    ) -> Result<PrestateFrame, DB::Error> {
+        self.trace(db, &journaled_state.state)
+    }
+
+    /// Builds the prestate frame from the transaction state, `db` must be the database that the
+    /// state has not been committed to.
+    pub fn trace<DB: Database>(&self, db: &DB, state: &State) -> Result<PrestateFrame, DB::Error> {
+        let mut pre = BTreeMap::new();
+        let mut post = BTreeMap::new();
+        for (address, account) in state {
+            let pre_info = db.basic(*address)?;
+            if !self.diff_mode {
+                let info = pre_info.unwrap_or_default();
+                let mut account_state = account_view(db, &info)?;
+                account_state.storage = account
+                    .storage
+                    .iter()
+                    .map(|(key, slot)| {
+                        (
+                            B256::from(key.to_be_bytes()),
+                            B256::from(slot.original_value.to_be_bytes()),
+                        )
+                    })
+                    .collect();
+                pre.insert(*address, account_state);
+                continue;
+            }
+            if !account.is_touched() {
+                continue;
+            }
+            let pre_state = match &pre_info {
+                Some(info) if !account.is_created() => {
+                    let mut account_state = account_view(db, info)?;
+                    account_state.storage = changed_storage(account, false);
+                    account_state
+                }
+                _ => AccountState::default(),
+            };
+            let post_state = if account.is_selfdestructed() {
+                AccountState::default()
+            } else {
+                let mut account_state = account_view(db, &account.info)?;
+                account_state.storage = changed_storage(account, true);
+                // Only the changed fields are kept in the post state.
+                if !pre_state.is_empty() {
+                    if account_state.balance == pre_state.balance {
+                        account_state.balance = None;
+                    }
+                    if account_state.nonce == pre_state.nonce {
+                        account_state.nonce = None;
+                    }
+                    if account_state.code == pre_state.code {
+                        account_state.code = None;
+                    }
+                }
+                account_state
+            };
+            // The touched account without any change is not reported.
+            if post_state.is_empty() && !account.is_selfdestructed() {
+                continue;
+            }
+            if !pre_state.is_empty() {
+                pre.insert(*address, pre_state);
+            }
+            if !post_state.is_empty() {
+                post.insert(*address, post_state);
+            }
+        }
+        if self.diff_mode {
+            Ok(PrestateFrame::Diff { pre, post })
+        } else {
+            Ok(PrestateFrame::Prestate(pre))
+        }
+    }
+}
+
+/// Returns the balance, nonce and code view of the account, the zero nonce and empty code
+/// are omitted.
+fn account_view<DB: Database>(db: &DB, info: &AccountInfo) -> Result<AccountState, DB::Error> {
+    let code = if info.has_code() {
+        match &info.code {
+            Some(code) => code.bytes(),
+            None => db.code_by_hash(info.code_hash)?.bytes(),
+        }
+    } else {
+        Bytes::new()
+    };
+    Ok(AccountState {
+        balance: Some(info.balance),
+        nonce: (info.nonce > 0).then_some(info.nonce),
+        code: (!code.is_empty()).then_some(code),
+        storage: BTreeMap::new(),
+    })
+}
+
+/// Returns the changed storage slots with the original or the present values.
+fn changed_storage(account: &Account, present: bool) -> BTreeMap<B256, B256> {
+    account
+        .storage
+        .iter()
+        .filter(|(_, slot)| slot.original_value != slot.present_value)
+        .filter(|(_, slot)| !present || !slot.present_value.is_zero())
+        .map(|(key, slot)| {
+            let value = if present {
+                slot.present_value
+            } else {
+                slot.original_value
+            };
+            (
+                B256::from(key.to_be_bytes()),
+                B256::from(value.to_be_bytes()),
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,389 @@
+//! The interpreter tier, which executes the legacy EVM bytecode on the revm interpreter without
+//! the compile pipeline, e.g., the cold contracts of the tiered execution.
+//!
+//! The interpreter is driven by the [`Host`] of the VM, thus the state, the journal and the sub
+//! calls are shared with the compiled code, and an interpreted frame can call a compiled frame
+//! and vice versa.
+
+use dora_primitives::revm::interpreter::{
+    self as revm_interpreter, CallInputs, CallOutcome, CallScheme, CreateInputs, CreateOutcome,
+    CreateScheme, Gas, InstructionResult, Interpreter, InterpreterAction, InterpreterResult,
+    SharedMemory, opcode::make_instruction_table,
+};
+use dora_primitives::{
+    AccountLoad, Address, B256, Bytecode, Bytes, Bytes32, Eip7702CodeLoad, Env, Log,
+    SelfDestructResult, SpecId, StateLoad, U256, spec_to_generic,
+};
+
+use crate::ExitStatusCode;
+use crate::call::{CallKind, CallMessage, CallResult};
+use crate::handler::Frame;
+use crate::host::{Host, SStoreResult, SStoreStatus};
+use crate::result::VMError;
+
+/// Returns whether the code can be executed by the interpreter, only the legacy EVM bytecode is
+/// supported, the EOF, EIP-7702 and WASM code must be compiled.
+#[inline]
+pub fn is_interpretable(code: &Bytecode) -> bool {
+    code.is_evm() && !code.is_eof() && !code.is_eip7702()
+}
+
+/// Executes the frame on the revm interpreter, the sub calls are dispatched by [`Host::call`].
+///
+/// # Example
+///
+/// ```ignore
+/// let result = interpreter::execute_frame(frame, ctx.spec_id(), ctx)?;
+/// ```
+pub fn execute_frame<H: Host>(
+    frame: Frame,
+    spec_id: SpecId,
+    host: &mut H,
+) -> Result<CallResult, VMError> {
+    let code = match frame.contract.code {
+        Bytecode::EVM(code) if !code.is_eof() && !code.is_eip7702() => code,
+        _ => {
+            return Err(VMError::Handler(
+                "only the legacy EVM bytecode can be interpreted".to_string(),
+            ));
+        }
+    };
+    let contract = revm_interpreter::Contract::new(
+        frame.contract.input,
+        code,
+        frame.contract.hash,
+        frame.contract.target_address,
+        Some(frame.contract.code_address),
+        frame.contract.caller,
+        frame.contract.call_value,
+    );
+    let mut interpreter = Interpreter::new(contract, frame.gas_limit, frame.is_static);
+    let mut memory = SharedMemory::new();
+    loop {
+        let action = run(&mut interpreter, memory, spec_id, host);
+        memory = interpreter.take_memory();
+        match action {
+            InterpreterAction::Call { inputs } => {
+                let result = host.call(call_message(&inputs, frame.depth))?;
+                interpreter.insert_call_outcome(
+                    &mut memory,
+                    CallOutcome::new(
+                        interpreter_result(&result),
+                        inputs.return_memory_offset.clone(),
+                    ),
+                );
+            }
+            InterpreterAction::Create { inputs } => {
+                let result = host.call(create_message(&inputs, frame.depth, frame.is_static))?;
+                interpreter.insert_create_outcome(CreateOutcome::new(
+                    interpreter_result(&result),
+                    result.create_address,
+                ));
+            }
+            InterpreterAction::Return { result } => {
+                return Ok(CallResult {
+                    status: exit_status(result.result),
+                    gas_limit: frame.gas_limit,
+                    gas_remaining: result.gas.remaining(),
+                    gas_refunded: result.gas.refunded(),
+                    output: result.output,
+                    create_address: None,
+                });
+            }
+            InterpreterAction::EOFCreate { .. } => {
+                return Err(VMError::Handler(
+                    "EOF create is not supported by the interpreter".to_string(),
+                ));
+            }
+            InterpreterAction::None => {
+                return Ok(CallResult {
+                    status: exit_status(interpreter.instruction_result),
+                    gas_limit: frame.gas_limit,
+                    gas_remaining: interpreter.gas.remaining(),
+                    gas_refunded: interpreter.gas.refunded(),
+                    output: Bytes::new(),
+                    create_address: None,
+                });
+            }
+        }
+    }
+}
+
+/// Runs the interpreter until it returns or meets a call-like instruction.
+fn run<H: Host>(
+    interpreter: &mut Interpreter,
+    memory: SharedMemory,
+    spec_id: SpecId,
+    host: &mut H,
+) -> InterpreterAction {
+    let mut host = InterpreterHost { host };
+    let table = spec_to_generic!(
+        spec_id,
+        make_instruction_table::<InterpreterHost<'_, H>, SPEC>()
+    );
+    interpreter.run(memory, &table, &mut host)
+}
+
+fn call_message(inputs: &CallInputs, depth: usize) -> CallMessage {
+    CallMessage {
+        kind: match inputs.scheme {
+            CallScheme::Call => CallKind::Call,
+            CallScheme::CallCode => CallKind::Callcode,
+            CallScheme::DelegateCall => CallKind::Delegatecall,
+            CallScheme::StaticCall => CallKind::Staticcall,
+            CallScheme::ExtCall => CallKind::ExtCall,
+            CallScheme::ExtStaticCall => CallKind::ExtStaticcall,
+            CallScheme::ExtDelegateCall => CallKind::ExtDelegatecall,
+        },
+        input: inputs.input.clone(),
+        value: inputs.value.get(),
+        depth: depth as u32,
+        gas_limit: inputs.gas_limit,
+        caller: inputs.caller,
+        recipient: inputs.target_address,
+        salt: None,
+        code_address: inputs.bytecode_address,
+        is_static: inputs.is_static,
+        is_eof_init: false,
+        validate_eof: true,
+    }
+}
+
+fn create_message(inputs: &CreateInputs, depth: usize, is_static: bool) -> CallMessage {
+    let (kind, salt) = match inputs.scheme {
+        CreateScheme::Create => (CallKind::Create, None),
+        CreateScheme::Create2 { salt } => (CallKind::Create2, Some(B256::from(salt.to_be_bytes()))),
+    };
+    CallMessage {
+        kind,
+        input: inputs.init_code.clone(),
+        value: inputs.value,
+        depth: depth as u32,
+        gas_limit: inputs.gas_limit,
+        caller: inputs.caller,
+        recipient: Address::default(),
+        salt,
+        code_address: Address::default(),
+        is_static,
+        is_eof_init: false,
+        validate_eof: true,
+    }
+}
+
+/// Converts the sub call result to the interpreter result, the gas of the failed calls is
+/// settled by the interpreter according to the instruction result.
+fn interpreter_result(result: &CallResult) -> InterpreterResult {
+    let mut gas = Gas::new(result.gas_remaining);
+    gas.record_refund(result.gas_refunded);
+    InterpreterResult {
+        result: instruction_result(&result.status),
+        output: result.output.clone(),
+        gas,
+    }
+}
+
+fn instruction_result(status: &ExitStatusCode) -> InstructionResult {
+    match status {
+        ExitStatusCode::Continue => InstructionResult::Continue,
+        ExitStatusCode::Return => InstructionResult::Return,
+        ExitStatusCode::Stop => InstructionResult::Stop,
+        ExitStatusCode::Selfdestruct => InstructionResult::SelfDestruct,
+        ExitStatusCode::Revert => InstructionResult::Revert,
+        ExitStatusCode::CallTooDeep => InstructionResult::CallTooDeep,
+        ExitStatusCode::OutOfFunds => InstructionResult::OutOfFunds,
+        ExitStatusCode::CreateInitCodeStartingEF00 => InstructionResult::CreateInitCodeStartingEF00,
+        ExitStatusCode::InvalidEOFInitCode => InstructionResult::InvalidEOFInitCode,
+        ExitStatusCode::InvalidExtDelegatecallTarget => {
+            InstructionResult::InvalidExtDelegateCallTarget
+        }
+        ExitStatusCode::MemoryOOG => InstructionResult::MemoryOOG,
+        ExitStatusCode::MemoryLimitOOG => InstructionResult::MemoryLimitOOG,
+        ExitStatusCode::PrecompileOOG => InstructionResult::PrecompileOOG,
+        ExitStatusCode::InvalidOperandOOG => InstructionResult::InvalidOperandOOG,
+        ExitStatusCode::OpcodeNotFound => InstructionResult::OpcodeNotFound,
+        ExitStatusCode::InvalidFEOpcode => InstructionResult::InvalidFEOpcode,
+        ExitStatusCode::InvalidJump => InstructionResult::InvalidJump,
+        ExitStatusCode::StackUnderflow => InstructionResult::StackUnderflow,
+        ExitStatusCode::StackOverflow => InstructionResult::StackOverflow,
+        ExitStatusCode::CreateCollision => InstructionResult::CreateCollision,
+        ExitStatusCode::PrecompileError => InstructionResult::PrecompileError,
+        ExitStatusCode::NonceOverflow => InstructionResult::NonceOverflow,
+        ExitStatusCode::CreateContractSizeLimit => InstructionResult::CreateContractSizeLimit,
+        ExitStatusCode::CreateContractStartingWithEF => {
+            InstructionResult::CreateContractStartingWithEF
+        }
+        ExitStatusCode::CreateInitCodeSizeLimit => InstructionResult::CreateInitCodeSizeLimit,
+        // The remaining errors are only reported to the caller as a failed call, note that the
+        // fatal external error must not be reported, which panics the interpreter.
+        _ => InstructionResult::OutOfGas,
+    }
+}
+
+fn exit_status(result: InstructionResult) -> ExitStatusCode {
+    match result {
+        InstructionResult::Continue => ExitStatusCode::Continue,
+        InstructionResult::Return => ExitStatusCode::Return,
+        InstructionResult::Stop => ExitStatusCode::Stop,
+        InstructionResult::SelfDestruct => ExitStatusCode::Selfdestruct,
+        InstructionResult::Revert => ExitStatusCode::Revert,
+        InstructionResult::CallTooDeep => ExitStatusCode::CallTooDeep,
+        InstructionResult::OutOfFunds => ExitStatusCode::OutOfFunds,
+        InstructionResult::CreateInitCodeStartingEF00 => ExitStatusCode::CreateInitCodeStartingEF00,
+        InstructionResult::OutOfGas => ExitStatusCode::OutOfGas,
+        InstructionResult::MemoryOOG => ExitStatusCode::MemoryOOG,
+        InstructionResult::MemoryLimitOOG => ExitStatusCode::MemoryLimitOOG,
+        InstructionResult::PrecompileOOG => ExitStatusCode::PrecompileOOG,
+        InstructionResult::InvalidOperandOOG => ExitStatusCode::InvalidOperandOOG,
+        InstructionResult::OpcodeNotFound => ExitStatusCode::OpcodeNotFound,
+        InstructionResult::CallNotAllowedInsideStatic => ExitStatusCode::CallNotAllowedInsideStatic,
+        InstructionResult::StateChangeDuringStaticCall => {
+            ExitStatusCode::StateChangeDuringStaticcall
+        }
+        InstructionResult::InvalidFEOpcode => ExitStatusCode::InvalidFEOpcode,
+        InstructionResult::InvalidJump => ExitStatusCode::InvalidJump,
+        InstructionResult::NotActivated => ExitStatusCode::NotActivated,
+        InstructionResult::StackUnderflow => ExitStatusCode::StackUnderflow,
+        InstructionResult::StackOverflow => ExitStatusCode::StackOverflow,
+        InstructionResult::OutOfOffset => ExitStatusCode::OutOfOffset,
+        InstructionResult::CreateCollision => ExitStatusCode::CreateCollision,
+        InstructionResult::OverflowPayment => ExitStatusCode::OverflowPayment,
+        InstructionResult::PrecompileError => ExitStatusCode::PrecompileError,
+        InstructionResult::NonceOverflow => ExitStatusCode::NonceOverflow,
+        InstructionResult::CreateContractSizeLimit => ExitStatusCode::CreateContractSizeLimit,
+        InstructionResult::CreateContractStartingWithEF => {
+            ExitStatusCode::CreateContractStartingWithEF
+        }
+        InstructionResult::CreateInitCodeSizeLimit => ExitStatusCode::CreateInitCodeSizeLimit,
+        InstructionResult::EOFOpcodeDisabledInLegacy => ExitStatusCode::EOFOpcodeDisabledInLegacy,
+        _ => ExitStatusCode::FatalExternalError,
+    }
+}
+
+/// The adapter which implements the revm interpreter host with the [`Host`] of the VM.
+struct InterpreterHost<'h, H: Host> {
+    host: &'h mut H,
+}
+
+impl<H: Host> revm_interpreter::Host for InterpreterHost<'_, H> {
+    #[inline]
+    fn env(&self) -> &Env {
+        self.host.env()
+    }
+
+    #[inline]
+    fn env_mut(&mut self) -> &mut Env {
+        self.host.env_mut()
+    }
+
+    #[inline]
+    fn load_account_delegated(&mut self, address: Address) -> Option<AccountLoad> {
+        self.host.load_account_delegated(address)
+    }
+
+    #[inline]
+    fn block_hash(&mut self, number: u64) -> Option<B256> {
+        self.host.block_hash(number).map(|hash| hash.to_b256())
+    }
+
+    #[inline]
+    fn balance(&mut self, address: Address) -> Option<StateLoad<U256>> {
+        self.host
+            .balance(address)
+            .map(|balance| balance.map(Bytes32::into_u256))
+    }
+
+    #[inline]
+    fn code(&mut self, address: Address) -> Option<Eip7702CodeLoad<Bytes>> {
+        self.host
+            .code(address)
+            .map(|code| Eip7702CodeLoad::new_not_delegated(code.data, code.is_cold))
+    }
+
+    #[inline]
+    fn code_hash(&mut self, address: Address) -> Option<Eip7702CodeLoad<B256>> {
+        self.host
+            .code_hash(address)
+            .map(|hash| Eip7702CodeLoad::new_not_delegated(hash.data.to_b256(), hash.is_cold))
+    }
+
+    #[inline]
+    fn sload(&mut self, address: Address, index: U256) -> Option<StateLoad<U256>> {
+        self.host
+            .sload(address, index.into())
+            .map(|value| value.map(Bytes32::into_u256))
+    }
+
+    fn sstore(
+        &mut self,
+        address: Address,
+        index: U256,
+        value: U256,
+    ) -> Option<StateLoad<revm_interpreter::SStoreResult>> {
+        let result = self.host.sstore(address, index.into(), value.into())?;
+        match result.data {
+            SStoreResult::Slot(slot) => Some(StateLoad::new(
+                revm_interpreter::SStoreResult {
+                    original_value: slot.original_value.into_u256(),
+                    present_value: slot.present_value.into_u256(),
+                    new_value: slot.new_value.into_u256(),
+                },
+                result.is_cold,
+            )),
+            SStoreResult::Status(status) => {
+                Some(StateLoad::new(status_slot(status, value), result.is_cold))
+            }
+        }
+    }
+
+    #[inline]
+    fn tload(&mut self, address: Address, index: U256) -> U256 {
+        self.host.tload(address, index.into()).into_u256()
+    }
+
+    #[inline]
+    fn tstore(&mut self, address: Address, index: U256, value: U256) {
+        self.host.tstore(address, index.into(), value.into())
+    }
+
+    #[inline]
+    fn log(&mut self, log: Log) {
+        self.host.log(log)
+    }
+
+    #[inline]
+    fn selfdestruct(
+        &mut self,
+        address: Address,
+        target: Address,
+    ) -> Option<StateLoad<SelfDestructResult>> {
+        self.host.selfdestruct(address, target)
+    }
+}
+
+/// Converts the status of the `SSTORE` into the slot values which the interpreter needs to charge
+/// the gas and the refund, the `X`, `Y` and `Z` of the status are chosen such that the gas cost and
+/// the refund are the same as the ones of the status charged by the compiled code, see
+/// [`crate::gas::sstore_cost`] and [`crate::gas::sstore_refund`].
+fn status_slot(status: SStoreStatus, new: U256) -> revm_interpreter::SStoreResult {
+    // Any nonzero value other than the new value.
+    let other = if new == U256::from(1) {
+        U256::from(2)
+    } else {
+        U256::from(1)
+    };
+    let (original_value, present_value) = match status {
+        SStoreStatus::Assigned => (new, new),
+        SStoreStatus::Added => (U256::ZERO, U256::ZERO),
+        SStoreStatus::Deleted | SStoreStatus::Modified => (other, other),
+        SStoreStatus::DeletedAdded => (other, U256::ZERO),
+        SStoreStatus::ModifiedDeleted => (U256::from(1), U256::from(2)),
+        SStoreStatus::DeletedRestored => (new, U256::ZERO),
+        SStoreStatus::AddedDeleted => (U256::ZERO, other),
+        SStoreStatus::ModifiedRestored => (new, other),
+    };
+    revm_interpreter::SStoreResult {
+        original_value,
+        present_value,
+        new_value: new,
+    }
+}
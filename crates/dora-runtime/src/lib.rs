@@ -1,27 +1,53 @@
+pub mod access_list;
 pub mod account;
+pub mod aot;
 pub mod artifact;
+pub mod block;
+pub mod cache;
+pub mod cache_db;
 pub mod call;
 pub mod constants;
 pub mod context;
 pub mod db;
+pub mod disk_db;
+pub mod estimate;
 pub mod executor;
 pub mod gas;
+pub mod genesis;
 pub mod handler;
 pub mod host;
+pub mod inspector;
+pub mod interpreter;
 pub mod journaled_state;
+pub mod overrides;
+pub mod parallel;
 pub mod result;
 pub mod stack;
 pub mod symbols;
+pub mod trie;
 pub mod vm;
 pub mod wasm;
 
+pub use access_list::AccessListResult;
 pub use account::{Account, AccountInfo, AccountStatus};
-pub use artifact::{Artifact, SymbolArtifact};
+pub use artifact::{Artifact, ArtifactKey, SymbolArtifact};
+pub use block::{
+    Block, BlockError, BlockExecutor, BlockResult, BlockTransaction, Receipt, Withdrawal,
+};
+pub use cache_db::{AccountDiff, CacheDB, StateDiff};
 pub use call::{CallKind, CallMessage, CallResult, CallType, CallTypeParseError, ExtCallType};
 pub use context::{Contract, RuntimeContext, VMContext};
 pub use db::{Database, DatabaseCommit, MemoryDB};
+pub use disk_db::{DiskDB, DiskDBError};
+pub use estimate::GasEstimate;
 pub use executor::{ExecuteKind, ExecutionEngine, Executor, RUNTIME_STACK_SIZE};
+pub use genesis::{GenesisAccount, GenesisAlloc, Prestate};
 pub use host::{DummyHost, Host};
+pub use inspector::{
+    CallFrame, CallTracer, Eip3155Tracer, GasProfile, GasProfiler, Inspector, PrestateFrame,
+    PrestateTracer, PrintInspector, Step, WasmStep,
+};
+pub use overrides::{AccountOverride, BlockOverrides, OverlayDB, StateOverride};
 pub use result::{ExecutionResult, HaltReason, ResultAndState, VMError};
 pub use stack::Stack;
 pub use vm::VM;
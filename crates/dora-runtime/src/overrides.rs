@@ -0,0 +1,313 @@
+//! The state and block overrides of the `eth_call` style simulation.
+//!
+//! The [`OverlayDB`] applies the [`StateOverride`] on top of any [`Database`] without mutating
+//! it, and the state changes committed into the overlay are kept by the overlay as well, so
+//! several transactions can be simulated one after another. The artifacts compiled during the
+//! simulation are kept by the overlay too, the inner database only serves its own artifacts. The [`BlockOverrides`] are applied
+//! to the block environment of the [`Env`] before the transaction is executed.
+
+use dora_primitives::{Address, B256, Bytecode, Bytes, Env, KECCAK_EMPTY, U256};
+use rustc_hash::FxHashMap;
+
+use crate::{
+    account::{Account, AccountInfo},
+    artifact::ArtifactKey,
+    db::{Database, DatabaseCommit, StorageSlot},
+};
+
+/// The overrides of the accounts from the account address to the account override.
+pub type StateOverride = FxHashMap<Address, AccountOverride>;
+
+/// The override of the account, the fields that are not set are loaded from the database.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccountOverride {
+    /// The account balance.
+    pub balance: Option<U256>,
+    /// The account nonce.
+    pub nonce: Option<u64>,
+    /// The account code.
+    pub code: Option<Bytes>,
+    /// Replaces the whole storage of the account, the slots that are not listed are zero.
+    pub state: Option<FxHashMap<U256, U256>>,
+    /// Overrides the individual storage slots of the account.
+    pub state_diff: Option<FxHashMap<U256, U256>>,
+}
+
+/// The overrides of the block environment.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlockOverrides {
+    /// The block number.
+    pub number: Option<U256>,
+    /// The block timestamp.
+    pub timestamp: Option<U256>,
+    /// The block base fee.
+    pub basefee: Option<U256>,
+    /// The block beneficiary.
+    pub coinbase: Option<Address>,
+}
+
+impl BlockOverrides {
+    /// Applies the overrides to the block environment.
+    pub fn apply(&self, env: &mut Env) {
+        if let Some(number) = self.number {
+            env.block.number = number;
+        }
+        if let Some(timestamp) = self.timestamp {
+            env.block.timestamp = timestamp;
+        }
+        if let Some(basefee) = self.basefee {
+            env.block.basefee = basefee;
+        }
+        if let Some(coinbase) = self.coinbase {
+            env.block.coinbase = coinbase;
+        }
+    }
+}
+
+/// The overridden account in the overlay.
+#[derive(Debug, Clone, Default)]
+struct OverlayAccount {
+    balance: Option<U256>,
+    nonce: Option<u64>,
+    code_hash: Option<B256>,
+    storage: FxHashMap<U256, U256>,
+    /// Whether the storage of the database is replaced by the storage of the overlay.
+    storage_replaced: bool,
+}
+
+impl OverlayAccount {
+    fn apply(&self, info: &mut AccountInfo, codes: &FxHashMap<B256, Bytecode>) {
+        if let Some(balance) = self.balance {
+            info.balance = balance;
+        }
+        if let Some(nonce) = self.nonce {
+            info.nonce = nonce;
+        }
+        if let Some(code_hash) = self.code_hash {
+            info.code_hash = code_hash;
+            info.code = Some(codes.get(&code_hash).cloned().unwrap_or_default());
+        }
+    }
+}
+
+/// The database which applies the state overrides on top of the inner database, the inner
+/// database is not mutated by the overrides, the commits nor the compiled artifacts.
+///
+/// # Example
+///
+/// ```ignore
+/// let mut overrides = StateOverride::default();
+/// overrides.insert(caller, AccountOverride { balance: Some(U256::MAX), ..Default::default() });
+/// let db = OverlayDB::new(db, overrides);
+/// BlockOverrides { number: Some(U256::from(1)), ..Default::default() }.apply(&mut env);
+/// let mut vm = VM::new(VMContext::new(db, env, spec_id, handler));
+/// let result = vm.transact()?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct OverlayDB<DB: Database> {
+    inner: DB,
+    accounts: FxHashMap<Address, OverlayAccount>,
+    codes: FxHashMap<B256, Bytecode>,
+    artifacts: FxHashMap<ArtifactKey, DB::Artifact>,
+}
+
+impl<DB: Database> OverlayDB<DB> {
+    /// Create a new overlay database with the state overrides.
+    pub fn new(inner: DB, overrides: StateOverride) -> Self {
+        let mut db = Self {
+            inner,
+            accounts: FxHashMap::default(),
+            codes: FxHashMap::default(),
+            artifacts: FxHashMap::default(),
+        };
+        for (address, account) in overrides {
+            db.apply_override(address, account);
+        }
+        db
+    }
+
+    /// Applies the account override on top of the overlay.
+    pub fn apply_override(&mut self, address: Address, account: AccountOverride) {
+        let code_hash = account
+            .code
+            .map(|code| self.insert_code(Bytecode::new(code)));
+        let overlay = self.accounts.entry(address).or_default();
+        overlay.balance = account.balance.or(overlay.balance);
+        overlay.nonce = account.nonce.or(overlay.nonce);
+        overlay.code_hash = code_hash.or(overlay.code_hash);
+        if let Some(state) = account.state {
+            overlay.storage = state;
+            overlay.storage_replaced = true;
+        }
+        overlay
+            .storage
+            .extend(account.state_diff.unwrap_or_default());
+    }
+
+    /// Returns the reference of the inner database.
+    #[inline]
+    pub fn inner(&self) -> &DB {
+        &self.inner
+    }
+
+    /// Returns the inner database without the overrides.
+    #[inline]
+    pub fn into_inner(self) -> DB {
+        self.inner
+    }
+
+    fn insert_code(&mut self, code: Bytecode) -> B256 {
+        if code.is_empty() {
+            return KECCAK_EMPTY;
+        }
+        let code_hash = code.hash_slow();
+        self.codes.insert(code_hash, code);
+        code_hash
+    }
+}
+
+impl<DB: Database> Database for OverlayDB<DB> {
+    type Error = DB::Error;
+    type Artifact = DB::Artifact;
+
+    fn basic(&self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        let info = self.inner.basic(address)?;
+        Ok(match self.accounts.get(&address) {
+            Some(overlay) => {
+                let mut info = info.unwrap_or_else(AccountInfo::empty);
+                overlay.apply(&mut info, &self.codes);
+                Some(info)
+            }
+            None => info,
+        })
+    }
+
+    fn code_by_hash(&self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        match self.codes.get(&code_hash) {
+            Some(code) => Ok(code.clone()),
+            None => self.inner.code_by_hash(code_hash),
+        }
+    }
+
+    fn storage(&self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        match self.accounts.get(&address) {
+            Some(overlay) => match overlay.storage.get(&index) {
+                Some(value) => Ok(*value),
+                None if overlay.storage_replaced => Ok(U256::ZERO),
+                None => self.inner.storage(address, index),
+            },
+            None => self.inner.storage(address, index),
+        }
+    }
+
+    fn block_hash(&self, number: U256) -> Result<B256, Self::Error> {
+        self.inner.block_hash(number)
+    }
+
+    fn insert_contract(&mut self, address: Address, bytecode: Bytecode, balance: U256) {
+        let code_hash = self.insert_code(bytecode);
+        self.accounts.insert(
+            address,
+            OverlayAccount {
+                balance: Some(balance),
+                nonce: Some(1),
+                code_hash: Some(code_hash),
+                storage: FxHashMap::default(),
+                storage_replaced: true,
+            },
+        );
+    }
+
+    fn set_account(
+        &mut self,
+        address: Address,
+        nonce: u64,
+        balance: U256,
+        storage: FxHashMap<U256, U256>,
+    ) {
+        self.apply_override(
+            address,
+            AccountOverride {
+                balance: Some(balance),
+                nonce: Some(nonce),
+                state: Some(storage),
+                ..Default::default()
+            },
+        );
+    }
+
+    fn get_artifact(&self, key: &ArtifactKey) -> Result<Option<Self::Artifact>, Self::Error> {
+        match self.artifacts.get(key) {
+            Some(artifact) => Ok(Some(artifact.clone())),
+            None => self.inner.get_artifact(key),
+        }
+    }
+
+    fn set_artifact(&mut self, key: ArtifactKey, artifact: Self::Artifact) {
+        self.artifacts.insert(key, artifact);
+    }
+
+    fn into_state(self) -> FxHashMap<Address, Account> {
+        let mut state = self.inner.into_state();
+        for (address, overlay) in self.accounts {
+            let account = state
+                .entry(address)
+                .or_insert_with(|| Account::from(AccountInfo::empty()));
+            overlay.apply(&mut account.info, &self.codes);
+            if overlay.storage_replaced {
+                account.storage.clear();
+            }
+            account.storage.extend(
+                overlay
+                    .storage
+                    .into_iter()
+                    .map(|(key, value)| (key, StorageSlot::from(value))),
+            );
+        }
+        state
+    }
+}
+
+impl<DB: Database> DatabaseCommit for OverlayDB<DB> {
+    /// Commits the state changes into the overlay, the inner database is not changed.
+    fn commit(&mut self, changes: FxHashMap<Address, Account>) {
+        for (address, account) in changes {
+            if !account.is_touched() {
+                continue;
+            }
+            if account.is_selfdestructed() {
+                self.accounts.insert(
+                    address,
+                    OverlayAccount {
+                        balance: Some(U256::ZERO),
+                        nonce: Some(0),
+                        code_hash: Some(KECCAK_EMPTY),
+                        storage: FxHashMap::default(),
+                        storage_replaced: true,
+                    },
+                );
+                continue;
+            }
+            let code_hash = account.info.code.as_ref().map(|code| {
+                if account.info.has_code() {
+                    self.codes.insert(account.info.code_hash, code.clone());
+                }
+                account.info.code_hash
+            });
+            let overlay = self.accounts.entry(address).or_default();
+            overlay.balance = Some(account.info.balance);
+            overlay.nonce = Some(account.info.nonce);
+            overlay.code_hash = code_hash.or(overlay.code_hash);
+            if account.is_created() {
+                overlay.storage.clear();
+                overlay.storage_replaced = true;
+            }
+            overlay.storage.extend(
+                account
+                    .storage
+                    .into_iter()
+                    .map(|(key, slot)| (key, slot.present_value)),
+            );
+        }
+    }
+}
@@ -0,0 +1,539 @@
+//! The optimistic parallel execution of the block transactions in the style of Block-STM.
+//!
+//! The transactions are executed speculatively on the worker threads against the multi-version
+//! view of the database, [`MvDatabase`], which serves the latest values written by the lower
+//! transactions of the block and records the values read by the [`JournaledState`]. The write
+//! set of a transaction is taken from its finalized journaled state, i.e., the touched accounts
+//! and their changed storage slots.
+//!
+//! The transactions are validated in the block order by reading their read set again from the
+//! multi-version memory, where all the lower transactions are already validated. The transaction
+//! whose reads changed is re-executed in the next round together with the other invalidated
+//! transactions, thus the final state is equal to the sequential execution.
+//!
+//! The beneficiary reward is not paid by the speculative execution but when the transaction is
+//! validated, so that the transactions do not conflict on the beneficiary balance.
+//!
+//! The view follows the commit semantics of the [`MemoryDB`](crate::db::MemoryDB), i.e., the
+//! committed accounts exist, the self-destructed accounts are emptied and the storage of the
+//! created and self-destructed accounts is cleared.
+//!
+//! [`JournaledState`]: crate::journaled_state::JournaledState
+
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, hash_map::Entry},
+    mem,
+    sync::{
+        Arc,
+        mpsc::{self, Sender},
+    },
+    thread,
+};
+
+use dora_primitives::{Address, B256, Bytecode, Env, U256};
+use parking_lot::{Mutex, RwLock};
+use rustc_hash::FxHashMap;
+
+use crate::{
+    account::{Account, AccountInfo},
+    artifact::ArtifactKey,
+    block::{BlockError, BlockTransaction},
+    context::VMContext,
+    db::{Database, DatabaseCommit, DatabaseError},
+    handler::Handler,
+    journaled_state::State,
+    result::{ResultAndState, VMError},
+    vm::VM,
+};
+
+/// The state location read and written by the transactions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Location {
+    Account(Address),
+    Storage(Address, U256),
+}
+
+/// The value read by the transaction at the location.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ReadValue {
+    Account(Option<AccountInfo>),
+    Storage(U256),
+}
+
+/// The value written by the transaction at the location.
+#[derive(Debug, Clone)]
+enum WriteValue {
+    Account {
+        info: AccountInfo,
+        storage_cleared: bool,
+    },
+    Storage(U256),
+}
+
+/// The output of the speculative execution of the transaction.
+#[derive(Debug)]
+struct TxOutput {
+    result: Result<(ResultAndState, U256), VMError>,
+    reads: Vec<(Location, ReadValue)>,
+}
+
+/// The multi-version memory which holds the values written by each transaction of the block.
+#[derive(Debug)]
+struct MvMemory {
+    beneficiary: Address,
+    data: RwLock<MvData>,
+}
+
+#[derive(Debug, Default)]
+struct MvData {
+    values: FxHashMap<Location, BTreeMap<usize, WriteValue>>,
+    /// The locations written by the last execution of each transaction.
+    writes: Vec<Vec<Location>>,
+    /// The beneficiary reward of the last execution of each transaction.
+    rewards: Vec<U256>,
+    codes: FxHashMap<B256, Bytecode>,
+}
+
+impl MvMemory {
+    fn new(num_txs: usize, beneficiary: Address) -> Self {
+        Self {
+            beneficiary,
+            data: RwLock::new(MvData {
+                writes: vec![Vec::new(); num_txs],
+                rewards: vec![U256::ZERO; num_txs],
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// Replaces the writes of the previous execution of the transaction.
+    fn record(&self, index: usize, result: &Result<(ResultAndState, U256), VMError>) {
+        let (writes, codes, reward) = match result {
+            Ok((output, reward)) => {
+                let (writes, codes) = write_set(&output.state);
+                (writes, codes, *reward)
+            }
+            Err(_) => Default::default(),
+        };
+        let mut data = self.data.write();
+        for location in mem::take(&mut data.writes[index]) {
+            if let Some(versions) = data.values.get_mut(&location) {
+                versions.remove(&index);
+            }
+        }
+        data.writes[index] = writes.iter().map(|(location, _)| *location).collect();
+        for (location, value) in writes {
+            data.values
+                .entry(location)
+                .or_default()
+                .insert(index, value);
+        }
+        data.codes.extend(codes);
+        data.rewards[index] = reward;
+    }
+
+    fn read<DB: Database>(
+        &self,
+        index: usize,
+        location: &Location,
+        base: &DB,
+    ) -> Result<ReadValue, DB::Error> {
+        Ok(match *location {
+            Location::Account(address) => {
+                ReadValue::Account(self.read_account(index, address, base)?)
+            }
+            Location::Storage(address, key) => {
+                ReadValue::Storage(self.read_storage(index, address, key, base)?)
+            }
+        })
+    }
+
+    /// Reads the account written by the latest lower transaction, the beneficiary balance also
+    /// includes the rewards of the lower transactions.
+    fn read_account<DB: Database>(
+        &self,
+        index: usize,
+        address: Address,
+        base: &DB,
+    ) -> Result<Option<AccountInfo>, DB::Error> {
+        let data = self.data.read();
+        let latest = data
+            .values
+            .get(&Location::Account(address))
+            .and_then(|versions| versions.range(..index).next_back());
+        let mut info = match latest {
+            Some((_, WriteValue::Account { info, .. })) => Some(info.clone()),
+            _ => base.basic(address)?,
+        };
+        if address == self.beneficiary {
+            // The writer of the beneficiary is rewarded after its write.
+            let from = latest.map_or(0, |(writer, _)| *writer);
+            if from < index {
+                let reward = data.rewards[from..index]
+                    .iter()
+                    .fold(U256::ZERO, |acc, reward| acc.saturating_add(*reward));
+                let info = info.get_or_insert_with(AccountInfo::empty);
+                info.balance = info.balance.saturating_add(reward);
+            }
+        }
+        Ok(info)
+    }
+
+    /// Reads the storage slot written by the latest lower transaction, the slot is zero when the
+    /// storage is cleared after that.
+    fn read_storage<DB: Database>(
+        &self,
+        index: usize,
+        address: Address,
+        key: U256,
+        base: &DB,
+    ) -> Result<U256, DB::Error> {
+        let data = self.data.read();
+        let latest = data
+            .values
+            .get(&Location::Storage(address, key))
+            .and_then(|versions| versions.range(..index).next_back());
+        let cleared = data
+            .values
+            .get(&Location::Account(address))
+            .and_then(|versions| {
+                versions.range(..index).rev().find(|(_, value)| {
+                    matches!(
+                        value,
+                        WriteValue::Account {
+                            storage_cleared: true,
+                            ..
+                        }
+                    )
+                })
+            })
+            .map(|(writer, _)| *writer);
+        match (latest, cleared) {
+            (Some((writer, WriteValue::Storage(value))), cleared)
+                if cleared.is_none_or(|cleared| *writer >= cleared) =>
+            {
+                Ok(*value)
+            }
+            (_, Some(_)) => Ok(U256::ZERO),
+            _ => base.storage(address, key),
+        }
+    }
+
+    fn code(&self, code_hash: &B256) -> Option<Bytecode> {
+        self.data.read().codes.get(code_hash).cloned()
+    }
+
+    /// Returns whether the values read by the transaction are still the latest values.
+    fn validate<DB: Database>(
+        &self,
+        index: usize,
+        reads: &[(Location, ReadValue)],
+        base: &DB,
+    ) -> Result<bool, DB::Error> {
+        for (location, value) in reads {
+            if self.read(index, location, base)? != *value {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// Returns the written locations and the deployed codes of the finalized journaled state.
+fn write_set(state: &State) -> (Vec<(Location, WriteValue)>, Vec<(B256, Bytecode)>) {
+    let mut writes = Vec::new();
+    let mut codes = Vec::new();
+    for (address, acc) in state {
+        if !acc.is_touched() {
+            continue;
+        }
+        if acc.is_selfdestructed() {
+            writes.push((
+                Location::Account(*address),
+                WriteValue::Account {
+                    info: AccountInfo::empty(),
+                    storage_cleared: true,
+                },
+            ));
+            continue;
+        }
+        let mut info = acc.info.clone();
+        if let Some(code) = info.code.take().filter(|_| info.has_code()) {
+            codes.push((info.code_hash, code));
+        }
+        let created = acc.is_created();
+        writes.push((
+            Location::Account(*address),
+            WriteValue::Account {
+                info,
+                storage_cleared: created,
+            },
+        ));
+        for (key, slot) in &acc.storage {
+            if created || slot.is_changed() {
+                writes.push((
+                    Location::Storage(*address, *key),
+                    WriteValue::Storage(slot.present_value),
+                ));
+            }
+        }
+    }
+    (writes, codes)
+}
+
+/// The multi-version view of the database for the transaction of the block, which records the
+/// values read by the transaction.
+///
+/// Each worker borrows the database of the block, the artifacts compiled by the worker are kept
+/// in the view and reused by its later transactions.
+#[derive(Debug, Clone)]
+pub struct MvDatabase<'db, DB: Database> {
+    base: &'db DB,
+    memory: Arc<MvMemory>,
+    index: usize,
+    reads: RefCell<Vec<(Location, ReadValue)>>,
+    artifacts: FxHashMap<ArtifactKey, DB::Artifact>,
+}
+
+impl<'db, DB: Database> MvDatabase<'db, DB> {
+    fn new(base: &'db DB, memory: Arc<MvMemory>) -> Self {
+        Self {
+            base,
+            memory,
+            index: 0,
+            reads: RefCell::default(),
+            artifacts: FxHashMap::default(),
+        }
+    }
+}
+
+impl<DB: Database> Database for MvDatabase<'_, DB> {
+    type Error = DB::Error;
+    type Artifact = DB::Artifact;
+
+    fn basic(&self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        let info = self.memory.read_account(self.index, address, self.base)?;
+        self.reads
+            .borrow_mut()
+            .push((Location::Account(address), ReadValue::Account(info.clone())));
+        Ok(info)
+    }
+
+    fn code_by_hash(&self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        match self.memory.code(&code_hash) {
+            Some(code) => Ok(code),
+            None => self.base.code_by_hash(code_hash),
+        }
+    }
+
+    fn storage(&self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        let value = self
+            .memory
+            .read_storage(self.index, address, index, self.base)?;
+        self.reads
+            .borrow_mut()
+            .push((Location::Storage(address, index), ReadValue::Storage(value)));
+        Ok(value)
+    }
+
+    fn block_hash(&self, number: U256) -> Result<B256, Self::Error> {
+        self.base.block_hash(number)
+    }
+
+    fn insert_contract(&mut self, _address: Address, _bytecode: Bytecode, _balance: U256) {
+        unreachable!("the accounts of the parallel block view are read only")
+    }
+
+    fn set_account(
+        &mut self,
+        _address: Address,
+        _nonce: u64,
+        _balance: U256,
+        _storage: FxHashMap<U256, U256>,
+    ) {
+        unreachable!("the accounts of the parallel block view are read only")
+    }
+
+    fn get_artifact(&self, key: &ArtifactKey) -> Result<Option<Self::Artifact>, Self::Error> {
+        match self.artifacts.get(key) {
+            Some(artifact) => Ok(Some(artifact.clone())),
+            None => self.base.get_artifact(key),
+        }
+    }
+
+    fn set_artifact(&mut self, key: ArtifactKey, artifact: Self::Artifact) {
+        self.artifacts.insert(key, artifact);
+    }
+
+    /// Returns the state of the underlying database without the writes of the block.
+    fn into_state(self) -> FxHashMap<Address, Account> {
+        self.base.clone().into_state()
+    }
+}
+
+impl<DB: Database> DatabaseCommit for MvDatabase<'_, DB> {
+    /// The state changes are recorded into the multi-version memory by the executor instead.
+    fn commit(&mut self, _changes: FxHashMap<Address, Account>) {}
+}
+
+/// Creates the VM handler of each parallel worker, which executes the transactions against the
+/// [`MvDatabase`] borrowing the database of the block.
+pub trait WorkerHandler<DB: Database>: Sync {
+    fn handler<'w>(&self) -> Handler<'w, MvDatabase<'w, DB>>
+    where
+        DB: 'w;
+}
+
+/// The output of the worker, which is `None` when the worker panicked.
+type WorkerOutput = Option<(usize, TxOutput)>;
+
+/// Reports the panic of the worker to the coordinator, which stops waiting for its outputs.
+struct PanicGuard<'s>(&'s Sender<WorkerOutput>);
+
+impl Drop for PanicGuard<'_> {
+    fn drop(&mut self) {
+        if thread::panicking() {
+            let _ = self.0.send(None);
+        }
+    }
+}
+
+/// Executes the transactions of the block in parallel on the VM environment and database, and
+/// returns the results of all the transactions in the block order, the state changes are not
+/// committed.
+///
+/// The workers are spawned once for the block and borrow the database, each round sends the
+/// pending transactions to the workers and waits for all of their outputs.
+///
+/// The transactions are validated as the sequential execution does, i.e., the first invalid
+/// transaction or the first transaction exceeding the block gas limit fails the block.
+pub(crate) fn execute_transactions<DB, H>(
+    vm: &VM<'_, DB>,
+    transactions: &[BlockTransaction],
+    handler: &H,
+    concurrency: usize,
+) -> Result<Vec<ResultAndState>, BlockError>
+where
+    DB: Database + Send + Sync,
+    H: WorkerHandler<DB>,
+{
+    let num_txs = transactions.len();
+    let beneficiary = vm.env.block.coinbase;
+    let block_gas_limit: u64 = vm.env.block.gas_limit.saturating_to();
+    let memory = Arc::new(MvMemory::new(num_txs, beneficiary));
+    let env: &Env = &vm.env;
+    let spec_id = vm.spec_id();
+    let base = &vm.db;
+    let (task_sender, task_receiver) = mpsc::channel::<usize>();
+    let task_receiver = Mutex::new(task_receiver);
+    let (output_sender, output_receiver) = mpsc::channel::<WorkerOutput>();
+
+    thread::scope(|scope| {
+        // The workers exit once the task sender is dropped, also when the coordinator unwinds.
+        let task_sender = task_sender;
+        for _ in 0..concurrency.clamp(1, num_txs.max(1)) {
+            let (task_receiver, output_sender, memory) =
+                (&task_receiver, output_sender.clone(), memory.clone());
+            scope.spawn(move || {
+                let _guard = PanicGuard(&output_sender);
+                let db = MvDatabase::new(base, memory.clone());
+                let mut vm = VM::new(VMContext::new(db, env.clone(), spec_id, handler.handler()));
+                loop {
+                    // The lock is released before the execution, the receiver is shared by all
+                    // the workers.
+                    let task = task_receiver.lock().recv();
+                    let Ok(index) = task else {
+                        break;
+                    };
+                    vm.db.index = index;
+                    vm.env.tx = transactions[index].tx.clone();
+                    let result = vm.transact_without_reward();
+                    let reads = mem::take(vm.db.reads.get_mut());
+                    memory.record(index, &result);
+                    if output_sender
+                        .send(Some((index, TxOutput { result, reads })))
+                        .is_err()
+                    {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(output_sender);
+
+        let mut outputs: Vec<Option<TxOutput>> = (0..num_txs).map(|_| None).collect();
+        let mut finalized: Vec<ResultAndState> = Vec::with_capacity(num_txs);
+        let mut gas_used = 0u64;
+        while finalized.len() < num_txs {
+            let pending: Vec<usize> = (finalized.len()..num_txs)
+                .filter(|index| outputs[*index].is_none())
+                .collect();
+            for index in &pending {
+                task_sender.send(*index).expect("parallel worker panicked");
+            }
+            for _ in 0..pending.len() {
+                let (index, output) = output_receiver
+                    .recv()
+                    .ok()
+                    .flatten()
+                    .expect("parallel worker panicked");
+                outputs[index] = Some(output);
+            }
+
+            // Validate the transactions in the block order, all the lower transactions of the
+            // validated transaction are finalized.
+            while finalized.len() < num_txs {
+                let index = finalized.len();
+                let output = outputs[index].take().expect("the transaction is executed");
+                if !memory
+                    .validate(index, &output.reads, base)
+                    .map_err(|_| DatabaseError)?
+                {
+                    // Re-execute the invalid transaction with the later transactions whose reads
+                    // are stale as well in the next round.
+                    for later in index + 1..num_txs {
+                        let is_valid = match &outputs[later] {
+                            Some(output) => memory
+                                .validate(later, &output.reads, base)
+                                .map_err(|_| DatabaseError)?,
+                            None => true,
+                        };
+                        if !is_valid {
+                            outputs[later] = None;
+                        }
+                    }
+                    break;
+                }
+
+                let gas_limit = transactions[index].tx.gas_limit;
+                let gas_left = block_gas_limit.saturating_sub(gas_used);
+                if gas_limit > gas_left {
+                    return Err(BlockError::GasLimitExceeded {
+                        index,
+                        gas_limit,
+                        gas_left,
+                    });
+                }
+                let (mut output, reward) = output
+                    .result
+                    .map_err(|error| BlockError::Transaction { index, error })?;
+                // Pay the reward on top of the beneficiary left by the lower transactions.
+                let account = match output.state.entry(beneficiary) {
+                    Entry::Occupied(entry) => entry.into_mut(),
+                    Entry::Vacant(entry) => {
+                        let info = memory
+                            .read_account(index, beneficiary, base)
+                            .map_err(|_| DatabaseError)?;
+                        entry.insert(info.map_or_else(Account::new_not_existing, Account::from))
+                    }
+                };
+                account.mark_touch();
+                account.info.balance = account.info.balance.saturating_add(reward);
+                gas_used += output.result.gas_used();
+                finalized.push(output);
+            }
+        }
+        Ok(finalized)
+    })
+}
@@ -239,6 +239,8 @@ impl fmt::Display for VMError {
     }
 }
 
+impl std::error::Error for VMError {}
+
 impl From<InvalidTransaction> for VMError {
     fn from(value: InvalidTransaction) -> Self {
         Self::Transaction(value)
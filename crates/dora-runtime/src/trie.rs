@@ -0,0 +1,528 @@
+//! The Merkle-Patricia trie roots of the state, the account storage and the block items.
+//!
+//! The state root is computed either at once from the state returned by
+//! [`Database::into_state`](crate::db::Database::into_state), or incrementally using the
+//! [`StateTrie`], which is updated with the state changes of each [`ResultAndState`] committed
+//! into the database.
+//!
+//! All the roots are computed by the in-memory [`MerkleTrie`], which caches the references of
+//! the unchanged nodes, so that only the nodes on the paths of the updated keys are rehashed.
+//!
+//! [`ResultAndState`]: crate::result::ResultAndState
+
+use alloy_rlp::{EMPTY_STRING_CODE, Encodable, Header, RlpEncodable, RlpMaxEncodedLen};
+use dora_primitives::{Address, B256, U256, keccak256};
+use rustc_hash::FxHashMap;
+
+use crate::{
+    account::{Account, AccountInfo},
+    journaled_state::State,
+};
+
+/// Computes the state root of the accounts which exist in the state, see [`is_state_account`].
+pub fn state_root(state: &State) -> B256 {
+    state_merkle_trie_root(state.iter().filter(|(_, acc)| is_state_account(acc)))
+}
+
+/// Computes the state root of all the given accounts.
+pub fn state_merkle_trie_root<'a>(
+    accounts: impl IntoIterator<Item = (&'a Address, &'a Account)>,
+) -> B256 {
+    trie_root(accounts.into_iter().map(|(address, acc)| {
+        (
+            address,
+            alloy_rlp::encode(TrieAccount::new(
+                &acc.info,
+                storage_root(acc.storage.iter().map(|(k, v)| (k, &v.present_value))),
+            )),
+        )
+    }))
+}
+
+/// Computes the storage root of the account from its storage slots, the zero slots are skipped.
+pub fn storage_root<'a>(storage: impl IntoIterator<Item = (&'a U256, &'a U256)>) -> B256 {
+    trie_root(
+        storage
+            .into_iter()
+            .filter(|(_, value)| !value.is_zero())
+            .map(|(key, value)| (key.to_be_bytes::<32>(), alloy_rlp::encode(value))),
+    )
+}
+
+/// Returns whether the account is a part of the state trie, the touched empty accounts that were
+/// loaded as not existing are not included.
+#[inline]
+pub fn is_state_account(acc: &Account) -> bool {
+    !acc.is_loaded_as_not_existing() || acc.is_touched() && !acc.is_empty()
+}
+
+/// Computes the root of the trie keyed by the keccak256 hash of the keys.
+#[inline]
+pub fn trie_root<I, A, B>(input: I) -> B256
+where
+    I: IntoIterator<Item = (A, B)>,
+    A: AsRef<[u8]>,
+    B: AsRef<[u8]>,
+{
+    let mut trie = MerkleTrie::new();
+    for (key, value) in input {
+        trie.insert(keccak256(key).as_slice(), value.as_ref().to_vec());
+    }
+    trie.root()
+}
+
+/// Computes the root of the trie keyed by the RLP encoded index of the items, e.g., the
+/// transactions root, the receipts root and the withdrawals root of the block.
+#[inline]
+pub fn ordered_trie_root<I, B>(input: I) -> B256
+where
+    I: IntoIterator<Item = B>,
+    B: AsRef<[u8]>,
+{
+    let mut trie = MerkleTrie::new();
+    for (index, value) in input.into_iter().enumerate() {
+        trie.insert(&alloy_rlp::encode(index), value.as_ref().to_vec());
+    }
+    trie.root()
+}
+
+/// The account leaf of the state trie.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, RlpEncodable, RlpMaxEncodedLen)]
+pub struct TrieAccount {
+    pub nonce: u64,
+    pub balance: U256,
+    pub storage_root: B256,
+    pub code_hash: B256,
+}
+
+impl TrieAccount {
+    /// Create a new account leaf with the storage root.
+    pub fn new(info: &AccountInfo, storage_root: B256) -> Self {
+        Self {
+            nonce: info.nonce,
+            balance: info.balance,
+            storage_root,
+            code_hash: info.code_hash,
+        }
+    }
+}
+
+/// The state trie which is updated incrementally by the committed state changes.
+///
+/// The account trie and the storage tries of the accounts are kept in memory, after the commit
+/// only the changed storage slots and the changed account leaves are rehashed.
+#[derive(Debug, Clone, Default)]
+pub struct StateTrie {
+    accounts: FxHashMap<Address, TrieAccount>,
+    account_trie: MerkleTrie,
+    storage_tries: FxHashMap<Address, MerkleTrie>,
+}
+
+impl StateTrie {
+    /// Create a new empty state trie.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create the state trie from the whole state, e.g., [`Database::into_state`].
+    ///
+    /// [`Database::into_state`]: crate::db::Database::into_state
+    pub fn from_state(state: &State) -> Self {
+        let mut trie = Self::new();
+        for (address, acc) in state.iter().filter(|(_, acc)| is_state_account(acc)) {
+            let mut storage_trie = MerkleTrie::new();
+            for (key, slot) in acc.storage.iter() {
+                if !slot.present_value.is_zero() {
+                    storage_trie.insert(
+                        keccak256(key.to_be_bytes::<32>()).as_slice(),
+                        alloy_rlp::encode(slot.present_value),
+                    );
+                }
+            }
+            let leaf = TrieAccount::new(&acc.info, storage_trie.root());
+            trie.insert_account(*address, leaf);
+            if !storage_trie.is_empty() {
+                trie.storage_tries.insert(*address, storage_trie);
+            }
+        }
+        trie
+    }
+
+    /// Applies the state changes committed into the database, e.g., the state of the
+    /// [`ResultAndState`](crate::result::ResultAndState). The untouched accounts are skipped.
+    pub fn commit(&mut self, changes: &State) {
+        for (address, acc) in changes {
+            if !acc.is_touched() {
+                continue;
+            }
+            if acc.is_selfdestructed() || !is_state_account(acc) {
+                if self.accounts.remove(address).is_some() {
+                    self.account_trie.remove(keccak256(address).as_slice());
+                }
+                self.storage_tries.remove(address);
+                continue;
+            }
+            let storage_trie = self.storage_tries.entry(*address).or_default();
+            if acc.is_created() {
+                *storage_trie = MerkleTrie::new();
+            }
+            for (key, slot) in acc.changed_storage_slots() {
+                let key = keccak256(key.to_be_bytes::<32>());
+                if slot.present_value.is_zero() {
+                    storage_trie.remove(key.as_slice());
+                } else {
+                    storage_trie.insert(key.as_slice(), alloy_rlp::encode(slot.present_value));
+                }
+            }
+            let leaf = TrieAccount::new(&acc.info, storage_trie.root());
+            if storage_trie.is_empty() {
+                self.storage_tries.remove(address);
+            }
+            if self.accounts.get(address) != Some(&leaf) {
+                self.insert_account(*address, leaf);
+            }
+        }
+    }
+
+    /// Returns the account leaf of the address.
+    #[inline]
+    pub fn account(&self, address: &Address) -> Option<&TrieAccount> {
+        self.accounts.get(address)
+    }
+
+    /// Returns the storage root of the account.
+    #[inline]
+    pub fn storage_root(&self, address: &Address) -> Option<B256> {
+        self.accounts.get(address).map(|leaf| leaf.storage_root)
+    }
+
+    /// Returns the state root, the unchanged nodes are not rehashed since the last call.
+    pub fn root(&mut self) -> B256 {
+        self.account_trie.root()
+    }
+
+    fn insert_account(&mut self, address: Address, leaf: TrieAccount) {
+        self.account_trie
+            .insert(keccak256(address).as_slice(), alloy_rlp::encode(leaf));
+        self.accounts.insert(address, leaf);
+    }
+}
+
+/// The in-memory Merkle-Patricia trie keyed by the raw bytes, the secure trie is built by
+/// hashing the keys before the insertion, see [`trie_root`].
+///
+/// The RLP reference of each node is cached until the node is changed, so that computing the
+/// root after the updates only rehashes the nodes on the updated paths.
+#[derive(Debug, Clone, Default)]
+pub struct MerkleTrie {
+    root: Node,
+}
+
+impl MerkleTrie {
+    /// Create a new empty trie.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns whether the trie has no keys.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        matches!(self.root, Node::Empty)
+    }
+
+    /// Inserts the value of the key, the empty value removes the key.
+    pub fn insert(&mut self, key: &[u8], value: Vec<u8>) {
+        if value.is_empty() {
+            self.remove(key);
+            return;
+        }
+        self.root = std::mem::take(&mut self.root).insert(&nibbles(key), value);
+    }
+
+    /// Removes the key from the trie.
+    pub fn remove(&mut self, key: &[u8]) {
+        self.root = std::mem::take(&mut self.root).remove(&nibbles(key));
+    }
+
+    /// Returns the root hash of the trie.
+    pub fn root(&mut self) -> B256 {
+        let reference = self.root.reference();
+        // The references of the nodes no shorter than 32 bytes are the RLP encoded hashes.
+        if reference.len() == 33 {
+            B256::from_slice(&reference[1..])
+        } else {
+            keccak256(&reference)
+        }
+    }
+}
+
+/// The node of the [`MerkleTrie`], the paths are the nibbles of the keys and the `cache` is the
+/// RLP reference of the node used by its parent node.
+#[derive(Debug, Clone, Default)]
+enum Node {
+    #[default]
+    Empty,
+    Leaf {
+        path: Vec<u8>,
+        value: Vec<u8>,
+        cache: Option<Vec<u8>>,
+    },
+    Extension {
+        path: Vec<u8>,
+        child: Box<Node>,
+        cache: Option<Vec<u8>>,
+    },
+    Branch {
+        children: Box<[Node; 16]>,
+        value: Option<Vec<u8>>,
+        cache: Option<Vec<u8>>,
+    },
+}
+
+impl Node {
+    fn leaf(path: Vec<u8>, value: Vec<u8>) -> Self {
+        Self::Leaf {
+            path,
+            value,
+            cache: None,
+        }
+    }
+
+    fn branch(children: Box<[Node; 16]>, value: Option<Vec<u8>>) -> Self {
+        Self::Branch {
+            children,
+            value,
+            cache: None,
+        }
+    }
+
+    /// Prefixes the node with the path, the prefix is merged into the leaf and the extension.
+    fn extension(mut path: Vec<u8>, child: Node) -> Self {
+        if path.is_empty() {
+            return child;
+        }
+        match child {
+            Self::Empty => Self::Empty,
+            Self::Leaf {
+                path: rest, value, ..
+            } => {
+                path.extend(rest);
+                Self::leaf(path, value)
+            }
+            Self::Extension {
+                path: rest, child, ..
+            } => {
+                path.extend(rest);
+                Self::Extension {
+                    path,
+                    child,
+                    cache: None,
+                }
+            }
+            child => Self::Extension {
+                path,
+                child: Box::new(child),
+                cache: None,
+            },
+        }
+    }
+
+    fn insert(self, path: &[u8], value: Vec<u8>) -> Self {
+        match self {
+            Self::Empty => Self::leaf(path.to_vec(), value),
+            Self::Leaf {
+                path: leaf_path,
+                value: leaf_value,
+                ..
+            } => {
+                let common = common_prefix_len(&leaf_path, path);
+                if common == leaf_path.len() && common == path.len() {
+                    return Self::leaf(leaf_path, value);
+                }
+                let branch = Self::branch(Default::default(), None)
+                    .insert(&leaf_path[common..], leaf_value)
+                    .insert(&path[common..], value);
+                Self::extension(path[..common].to_vec(), branch)
+            }
+            Self::Extension {
+                path: ext_path,
+                child,
+                ..
+            } => {
+                let common = common_prefix_len(&ext_path, path);
+                if common == ext_path.len() {
+                    let child = child.insert(&path[common..], value);
+                    return Self::extension(ext_path, child);
+                }
+                let mut children: Box<[Node; 16]> = Default::default();
+                children[ext_path[common] as usize] = if common + 1 == ext_path.len() {
+                    *child
+                } else {
+                    Self::Extension {
+                        path: ext_path[common + 1..].to_vec(),
+                        child,
+                        cache: None,
+                    }
+                };
+                let branch = Self::branch(children, None).insert(&path[common..], value);
+                Self::extension(path[..common].to_vec(), branch)
+            }
+            Self::Branch {
+                mut children,
+                value: branch_value,
+                ..
+            } => match path.split_first() {
+                None => Self::branch(children, Some(value)),
+                Some((&index, rest)) => {
+                    let index = index as usize;
+                    children[index] = std::mem::take(&mut children[index]).insert(rest, value);
+                    Self::branch(children, branch_value)
+                }
+            },
+        }
+    }
+
+    fn remove(self, path: &[u8]) -> Self {
+        match self {
+            Self::Empty => Self::Empty,
+            Self::Leaf {
+                path: leaf_path, ..
+            } if leaf_path == path => Self::Empty,
+            leaf @ Self::Leaf { .. } => leaf,
+            Self::Extension {
+                path: ext_path,
+                child,
+                cache,
+            } => match path.strip_prefix(ext_path.as_slice()) {
+                Some(rest) => Self::extension(ext_path, child.remove(rest)),
+                None => Self::Extension {
+                    path: ext_path,
+                    child,
+                    cache,
+                },
+            },
+            Self::Branch {
+                mut children,
+                mut value,
+                cache,
+            } => {
+                match path.split_first() {
+                    None if value.is_some() => value = None,
+                    Some((&index, rest)) if !matches!(children[index as usize], Self::Empty) => {
+                        let index = index as usize;
+                        children[index] = std::mem::take(&mut children[index]).remove(rest);
+                    }
+                    _ => {
+                        return Self::Branch {
+                            children,
+                            value,
+                            cache,
+                        };
+                    }
+                }
+                Self::collapse_branch(children, value)
+            }
+        }
+    }
+
+    /// Collapses the branch with less than two items into the leaf or the extension.
+    fn collapse_branch(mut children: Box<[Node; 16]>, value: Option<Vec<u8>>) -> Self {
+        let mut indices = (0..16).filter(|index| !matches!(children[*index], Self::Empty));
+        match (indices.next(), indices.next(), value) {
+            (None, _, None) => Self::Empty,
+            (None, _, Some(value)) => Self::leaf(Vec::new(), value),
+            (Some(index), None, None) => {
+                Self::extension(vec![index as u8], std::mem::take(&mut children[index]))
+            }
+            (_, _, value) => Self::branch(children, value),
+        }
+    }
+
+    /// Returns the RLP reference of the node, which is the RLP encoding of the node shorter than
+    /// 32 bytes, or the RLP encoded hash of the encoding.
+    fn reference(&mut self) -> Vec<u8> {
+        let cache = match self {
+            Self::Empty => return vec![EMPTY_STRING_CODE],
+            Self::Leaf { cache, .. }
+            | Self::Extension { cache, .. }
+            | Self::Branch { cache, .. } => cache,
+        };
+        if let Some(reference) = cache {
+            return reference.clone();
+        }
+        let encoded = self.encode();
+        let reference = if encoded.len() < 32 {
+            encoded
+        } else {
+            alloy_rlp::encode(keccak256(&encoded))
+        };
+        if let Self::Leaf { cache, .. }
+        | Self::Extension { cache, .. }
+        | Self::Branch { cache, .. } = self
+        {
+            *cache = Some(reference.clone());
+        }
+        reference
+    }
+
+    /// Returns the RLP encoding of the node, the references of the children are computed.
+    fn encode(&mut self) -> Vec<u8> {
+        let mut payload = Vec::new();
+        match self {
+            Self::Empty => return vec![EMPTY_STRING_CODE],
+            Self::Leaf { path, value, .. } => {
+                compact_path(path, true).as_slice().encode(&mut payload);
+                value.as_slice().encode(&mut payload);
+            }
+            Self::Extension { path, child, .. } => {
+                compact_path(path, false).as_slice().encode(&mut payload);
+                payload.extend(child.reference());
+            }
+            Self::Branch {
+                children, value, ..
+            } => {
+                for child in children.iter_mut() {
+                    payload.extend(child.reference());
+                }
+                match value {
+                    Some(value) => value.as_slice().encode(&mut payload),
+                    None => payload.push(EMPTY_STRING_CODE),
+                }
+            }
+        }
+        let mut out = Vec::with_capacity(payload.len() + 3);
+        Header {
+            list: true,
+            payload_length: payload.len(),
+        }
+        .encode(&mut out);
+        out.extend(payload);
+        out
+    }
+}
+
+/// Splits the bytes into the nibbles.
+fn nibbles(key: &[u8]) -> Vec<u8> {
+    key.iter()
+        .flat_map(|byte| [byte >> 4, byte & 0x0f])
+        .collect()
+}
+
+/// Returns the hex-prefix encoding of the nibbles path with the leaf flag.
+fn compact_path(path: &[u8], leaf: bool) -> Vec<u8> {
+    let flag = if leaf { 0x20 } else { 0x00 };
+    let mut out = Vec::with_capacity(path.len() / 2 + 1);
+    let rest = if path.len() % 2 == 1 {
+        out.push(flag | 0x10 | path[0]);
+        &path[1..]
+    } else {
+        out.push(flag);
+        path
+    };
+    out.extend(rest.chunks(2).map(|pair| (pair[0] << 4) | pair[1]));
+    out
+}
+
+fn common_prefix_len(a: &[u8], b: &[u8]) -> usize {
+    a.iter().zip(b).take_while(|(a, b)| a == b).count()
+}
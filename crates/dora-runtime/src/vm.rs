@@ -3,13 +3,14 @@ use std::{
     ops::{Deref, DerefMut},
 };
 
-use dora_primitives::{Env, InvalidTransaction, SpecId, U256, spec_to_generic};
+use dora_primitives::{
+    Address, Bytes, Env, InvalidTransaction, SpecId, TxKind, U256, spec_to_generic,
+};
 
 use crate::{
     ExitStatusCode,
     account::Account,
     call::{CallKind, CallMessage, CallResult},
-    constants::env::DORA_TRACING,
     context::VMContext,
     db::{Database, DatabaseError},
     gas::{self, InitialGas},
@@ -18,6 +19,9 @@ use crate::{
     },
 };
 
+/// The gas limit of the system calls made by the client at the start or the end of the block.
+pub const SYSTEM_CALL_GAS_LIMIT: u64 = 30_000_000;
+
 /// EVM/WASM instance containing internal VM context and run actions
 pub struct VM<'a, DB: Database> {
     pub context: VMContext<'a, DB>,
@@ -49,6 +53,18 @@ impl<'a, DB: Database> VM<'a, DB> {
         output
     }
 
+    /// Transact transaction without rewarding the beneficiary, the reward is returned along with
+    /// the result to be paid by the caller, see [`crate::parallel`].
+    pub(crate) fn transact_without_reward(&mut self) -> Result<(ResultAndState, U256), VMError> {
+        let gas = self.preverify_transaction().inspect_err(|_| {
+            self.clear();
+        })?;
+
+        let output = self.execute_preverified(gas, false);
+        self.clear();
+        output
+    }
+
     /// Commit the changes to the database.
     pub fn transact_commit(&mut self) -> Result<ExecutionResult, VMError> {
         let ResultAndState { result, state } = self.transact()?;
@@ -56,6 +72,54 @@ impl<'a, DB: Database> VM<'a, DB> {
         Ok(result)
     }
 
+    /// Calls the system contract with the data as the system caller without committing the state.
+    ///
+    /// The system call e.g., EIP-4788 and EIP-2935 is not validated, does not charge the fee,
+    /// does not bump the nonce and the system caller is removed from the returned state.
+    pub fn transact_system_call(
+        &mut self,
+        caller: Address,
+        contract: Address,
+        data: Bytes,
+    ) -> Result<ResultAndState, VMError> {
+        let tx = &mut self.context.env.tx;
+        tx.caller = caller;
+        tx.transact_to = TxKind::Call(contract);
+        tx.data = data.clone();
+        tx.value = U256::ZERO;
+        tx.gas_limit = SYSTEM_CALL_GAS_LIMIT;
+        tx.gas_price = U256::ZERO;
+        tx.gas_priority_fee = None;
+        tx.nonce = None;
+        tx.access_list.clear();
+        tx.blob_hashes.clear();
+        tx.authorization_list = None;
+
+        self.context.set_precompiles();
+        let call_msg = CallMessage {
+            kind: CallKind::Call,
+            input: data,
+            value: U256::ZERO,
+            depth: 0,
+            gas_limit: SYSTEM_CALL_GAS_LIMIT,
+            caller,
+            recipient: contract,
+            salt: None,
+            code_address: contract,
+            is_static: false,
+            is_eof_init: false,
+            validate_eof: true,
+        };
+        let output = self.context.call(call_msg).map(|mut result| {
+            self.context.last_frame_return(&mut result);
+            let mut output = self.output(result);
+            output.state.remove(&caller);
+            output
+        });
+        self.clear();
+        output
+    }
+
     /// Pre verify transaction inner.
     #[inline]
     fn preverify_transaction(&mut self) -> Result<InitialGas, VMError> {
@@ -83,8 +147,8 @@ impl<'a, DB: Database> VM<'a, DB> {
         Ok(())
     }
 
-    /// Validate initial transaction gas.
-    fn validate_initial_tx_gas(env: &Env, spec_id: SpecId) -> Result<InitialGas, VMError> {
+    /// Calculate the initial transaction gas and the EIP-7623 floor gas.
+    pub(crate) fn initial_tx_gas(env: &Env, spec_id: SpecId) -> InitialGas {
         let is_create = env.tx.transact_to.is_create();
         let authorization_list_num = env
             .tx
@@ -92,13 +156,18 @@ impl<'a, DB: Database> VM<'a, DB> {
             .as_ref()
             .map(|l| l.len() as u64)
             .unwrap_or_default();
-        let gas = gas::calculate_initial_tx_gas(
+        gas::calculate_initial_tx_gas(
             spec_id,
             &env.tx.data,
             is_create,
             &env.tx.access_list,
             authorization_list_num,
-        );
+        )
+    }
+
+    /// Validate initial transaction gas.
+    fn validate_initial_tx_gas(env: &Env, spec_id: SpecId) -> Result<InitialGas, VMError> {
+        let gas = Self::initial_tx_gas(env, spec_id);
         // Additional check to see if limit is big enough to cover initial gas.
         if gas.initial_gas > env.tx.gas_limit {
             return Err(VMError::Transaction(
@@ -171,7 +240,19 @@ impl<'a, DB: Database> VM<'a, DB> {
     }
 
     /// Transact pre-verified transaction.
+    #[inline]
     fn transact_preverified(&mut self, gas: InitialGas) -> Result<ResultAndState, VMError> {
+        self.execute_preverified(gas, true)
+            .map(|(output, _)| output)
+    }
+
+    /// Execute pre-verified transaction and return the output with the beneficiary reward, which
+    /// is only paid when `reward_beneficiary` is set.
+    fn execute_preverified(
+        &mut self,
+        gas: InitialGas,
+        reward_beneficiary: bool,
+    ) -> Result<(ResultAndState, U256), VMError> {
         let ctx = &mut self.context;
         // Pre execution
         let pre_exec_gas_refund = {
@@ -206,14 +287,8 @@ impl<'a, DB: Database> VM<'a, DB> {
                 is_eof_init: false,
                 validate_eof: true,
             };
-            if std::env::var(DORA_TRACING).is_ok() {
-                println!("info: tx call msg {:?}", call_msg);
-            }
             let mut result = ctx.call(call_msg)?;
             ctx.last_frame_return(&mut result);
-            if std::env::var(DORA_TRACING).is_ok() {
-                println!("info: tx call ret {:?}", result);
-            }
             result
         };
 
@@ -232,10 +307,19 @@ impl<'a, DB: Database> VM<'a, DB> {
             // Reimburse the caller with gas that were not used.
             ctx.reimburse_caller(result.gas_remaining, result.gas_refunded)?;
             // Reward beneficiary
-            ctx.reward_beneficiary(result.gas_used(), result.gas_refunded)?;
+            if reward_beneficiary {
+                ctx.reward_beneficiary(result.gas_used(), result.gas_refunded)?;
+            }
         }
+        let reward = self
+            .context
+            .beneficiary_reward(result.gas_used(), result.gas_refunded);
         // Returns output of transaction.
-        Ok(self.output(result))
+        let output = self.output(result);
+        if let Some(inspector) = self.context.inspector.as_mut() {
+            inspector.transaction_end(&output.result);
+        }
+        Ok((output, reward))
     }
 
     /// Build output using the call result
@@ -247,6 +331,12 @@ impl<'a, DB: Database> VM<'a, DB> {
 
         let return_values = result.output.to_vec();
         let exit_status = result.status;
+        // The output of a contract creation is the deployed code and the created address.
+        let output = if self.env.tx.transact_to.is_create() {
+            Output::Create(result.output.clone(), result.create_address)
+        } else {
+            Output::Call(result.output.clone())
+        };
         // Reset journal and return present state.
         let (state, logs) = self.journaled_state.finalize();
 
@@ -255,21 +345,21 @@ impl<'a, DB: Database> VM<'a, DB> {
                 reason: SuccessReason::Return,
                 gas_used,
                 gas_refunded,
-                output: Output::Call(return_values.into()),
+                output,
                 logs,
             },
             ExitStatusCode::Stop => ExecutionResult::Success {
                 reason: SuccessReason::Stop,
                 gas_used,
                 gas_refunded,
-                output: Output::Call(return_values.into()),
+                output,
                 logs,
             },
             ExitStatusCode::Selfdestruct => ExecutionResult::Success {
                 reason: SuccessReason::Selfdestruct,
                 gas_used,
                 gas_refunded,
-                output: Output::Call(return_values.into()),
+                output,
                 logs,
             },
             ExitStatusCode::Revert
@@ -13,6 +13,7 @@ use crate::ExitStatusCode;
 use crate::call::{CallKind, CallMessage, CallResult, CallType};
 use crate::constants::env::DORA_DISABLE_CONSOLE;
 use crate::context::RuntimeContext;
+use crate::inspector::{WasmStep, wasm_op_name};
 use dora_primitives::{
     Address, B256, Bytes32, KECCAK_EMPTY, Log, LogData, U256, as_u64_saturated,
     keccak256 as native_keccak256,
@@ -707,6 +708,24 @@ pub fn gas_limit() -> u64 {
     with_runtime_context(|runtime_context| runtime_context.gas_limit())
 }
 
+/// Reports the WASM operator compiled with tracing to the inspector, `op_id` is the operator
+/// name id, see [`wasm_op_id`](crate::inspector::wasm_op_id).
+pub extern "C" fn wasm_tracing(op_id: u32, offset: u64, gas_cost: u64) {
+    with_runtime_context(|runtime_context| {
+        let depth = runtime_context.inner.depth;
+        let address = runtime_context.contract.target_address;
+        if let Some(inspector) = runtime_context.host.inspector() {
+            inspector.wasm_step(&WasmStep {
+                op: wasm_op_name(op_id),
+                offset,
+                gas_cost,
+                depth,
+                address,
+            });
+        }
+    })
+}
+
 /// Gets the address of the account that called the program.
 pub fn msg_sender(
     mut env: WASMEnvMut,
@@ -7,22 +7,22 @@
 //! cargo install --path .
 //! dora-ethertest run tests/GeneralStateTests
 //! ```
-use alloy_rlp::{Decodable, Error as RlpError, Header, RlpEncodable, RlpMaxEncodedLen};
+use alloy_rlp::{Decodable, Error as RlpError, Header};
 use anyhow::Result;
 use clap::{Args, Parser, Subcommand};
 use dora::compile_handler;
 use dora_primitives::{
-    AccessList, Address, AuthorizationList, B256, Bytecode, Bytes, Env, EvmStorageSlot, Log,
+    AccessList, Address, AuthorizationList, B256, Bytecode, Bytes, Env, EvmStorageSlot,
     PrimitiveSignature, SignedAuthorization, SpecId, SpecName, TxKind, U256, as_u64_saturated,
     calc_excess_blob_gas, keccak256,
 };
 use dora_runtime::{
-    Account, Database, MemoryDB, RUNTIME_STACK_SIZE, VM, VMContext, constants::env::DORA_TRACING,
+    Database, Eip3155Tracer, MemoryDB, RUNTIME_STACK_SIZE, VM, VMContext,
+    constants::env::DORA_TRACING, trie::state_root,
 };
 use dora_tools::find_all_json_tests;
-use hash_db::Hasher;
+use dora_tools::trie::log_rlp_hash;
 use indicatif::{ProgressBar, ProgressDrawTarget};
-use plain_hasher::PlainHasher;
 use serde::{Deserialize, Serialize, de};
 use std::{
     collections::{BTreeMap, HashMap},
@@ -31,7 +31,6 @@ use std::{
 };
 use thiserror::Error;
 use tracing::{error, info};
-use triehash::sec_trie_root;
 
 /// Gas consumption of a single data blob (== blob byte size)
 pub const GAS_PER_BLOB: u64 = 1 << 17;
@@ -58,6 +57,10 @@ enum Commands {
 #[derive(Args)]
 struct RunArgs {
     path: Vec<PathBuf>,
+    /// Print the EIP-3155 JSON trace of each test transaction to the standard error, which is
+    /// separated from the logs and the progress bar
+    #[arg(long)]
+    trace_json: bool,
 }
 
 #[derive(Debug, PartialEq, Eq, Deserialize)]
@@ -330,55 +333,6 @@ pub enum TestErrorKind {
     },
 }
 
-fn log_rlp_hash(logs: &[Log]) -> B256 {
-    let mut out = Vec::with_capacity(alloy_rlp::list_length(logs));
-    alloy_rlp::encode_list(logs, &mut out);
-    B256::from_slice(keccak256(&out).as_slice())
-}
-
-pub fn state_merkle_trie_root<'a>(
-    accounts: impl IntoIterator<Item = (&'a Address, &'a Account)>,
-) -> B256 {
-    B256::from_slice(
-        trie_root(accounts.into_iter().map(|(address, acc)| {
-            (
-                address,
-                alloy_rlp::encode_fixed_size(&TrieAccount::new(address, acc)),
-            )
-        }))
-        .as_slice(),
-    )
-}
-
-#[derive(RlpEncodable, RlpMaxEncodedLen)]
-struct TrieAccount {
-    nonce: u64,
-    balance: U256,
-    root_hash: B256,
-    code_hash: B256,
-}
-
-impl TrieAccount {
-    fn new(_address: &Address, acc: &Account) -> Self {
-        Self {
-            nonce: acc.info.nonce,
-            balance: acc.info.balance,
-            root_hash: sec_trie_root::<KeccakHasher, _, _, _>(
-                acc.storage
-                    .iter()
-                    .filter(|(_k, v)| !v.present_value.is_zero())
-                    .map(|(k, v)| {
-                        (
-                            k.to_be_bytes::<32>(),
-                            alloy_rlp::encode_fixed_size(&v.present_value),
-                        )
-                    }),
-            ),
-            code_hash: acc.info.code_hash,
-        }
-    }
-}
-
 /// This type keeps track of the current value of a storage slot.
 #[derive(
     Debug, Copy, Clone, Default, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize,
@@ -433,30 +387,6 @@ impl StorageSlot {
     }
 }
 
-#[inline]
-pub fn trie_root<I, A, B>(input: I) -> B256
-where
-    I: IntoIterator<Item = (A, B)>,
-    A: AsRef<[u8]>,
-    B: AsRef<[u8]>,
-{
-    sec_trie_root::<KeccakHasher, _, _, _>(input)
-}
-
-#[derive(Default, Debug, Clone, PartialEq, Eq, Hash)]
-pub struct KeccakHasher;
-
-impl Hasher for KeccakHasher {
-    type Out = B256;
-    type StdHasher = PlainHasher;
-    const LENGTH: usize = 32;
-
-    #[inline]
-    fn hash(x: &[u8]) -> Self::Out {
-        keccak256(x)
-    }
-}
-
 fn should_skip(path: &Path) -> bool {
     let path_str = path.to_str().expect("Path is not valid UTF-8");
     let name = path.file_name().unwrap().to_str().unwrap();
@@ -513,7 +443,7 @@ fn should_skip(path: &Path) -> bool {
         path_str.contains("stEOF")
 }
 
-fn execute_test(path: &Path) -> Result<(), TestError> {
+fn execute_test(path: &Path, trace_json: bool) -> Result<(), TestError> {
     if should_skip(path) {
         return Ok(());
     }
@@ -671,7 +601,13 @@ fn execute_test(path: &Path) -> Result<(), TestError> {
                     let _ = evm.build().transact();
                 }
                 // Run the VM and get the state result.
-                let mut vm = VM::new(VMContext::new(db.clone(), env, spec_id, compile_handler()));
+                let mut ctx = VMContext::new(db.clone(), env, spec_id, compile_handler());
+                if trace_json {
+                    ctx = ctx.with_inspector(
+                        Eip3155Tracer::new(std::io::stderr()).with_fork(format!("{:?}", spec_id)),
+                    );
+                }
+                let mut vm = VM::new(ctx);
                 let res = vm.transact_commit();
                 // Calculate the logs root.
                 let logs_root = log_rlp_hash(res.as_ref().map(|r| r.logs()).unwrap_or_default());
@@ -747,10 +683,7 @@ fn execute_test(path: &Path) -> Result<(), TestError> {
                             }
                         }
                         // Check the state root
-                        let state_list = db_state.iter().filter(|(_, acc)| {
-                            !acc.is_loaded_as_not_existing() || acc.is_touched() && !acc.is_empty()
-                        });
-                        let state_root = state_merkle_trie_root(state_list);
+                        let state_root = state_root(&db_state);
                         if state_root != test_case.hash {
                             let kind = TestErrorKind::StateRootMismatch {
                                 got: state_root,
@@ -905,13 +838,14 @@ fn main() -> Result<()> {
                 info!("\nRunning tests in {}...", path.display());
 
                 let tests = find_all_json_tests(path);
+                let trace_json = run_args.trace_json;
                 let pb = ProgressBar::new(tests.len() as u64);
                 pb.set_draw_target(ProgressDrawTarget::stdout());
                 let builder = std::thread::Builder::new().stack_size(RUNTIME_STACK_SIZE);
                 let handle = builder.spawn(move || {
                     let mut errors = vec![];
                     for test_path in tests {
-                        match execute_test(&test_path) {
+                        match execute_test(&test_path, trace_json) {
                             Ok(_) => pb.inc(1),
                             Err(e) => {
                                 errors.push(e);
@@ -1,6 +1,11 @@
 use std::path::{Path, PathBuf};
 use walkdir::{DirEntry, WalkDir};
 
+pub mod t8n;
+#[cfg(test)]
+mod tests;
+pub mod trie;
+
 pub fn find_all_json_tests(path: &Path) -> Vec<PathBuf> {
     let mut paths = if path.is_file() {
         vec![path.to_path_buf()]
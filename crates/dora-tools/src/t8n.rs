@@ -0,0 +1,906 @@
+//! The state transition tool compatible with the `evm t8n` command of
+//! [geth](https://github.com/ethereum/go-ethereum/tree/master/cmd/evm), which is used by
+//! [execution-spec-tests](https://github.com/ethereum/execution-spec-tests) to fill and verify
+//! the tests.
+//!
+//! The tool reads the pre-state allocation, the block environment and the transactions in the
+//! JSON or RLP format, applies the transactions one by one with [`VM`] on the in-memory database,
+//! and outputs the execution result containing the roots, the receipts and the rejected
+//! transactions, the post-state allocation and the RLP encoded transactions of the block body.
+//!
+//! The EIP-4788 beacon root and the EIP-2935 parent block hash system calls are applied before
+//! the transactions, the parent block hash is read from the `blockHashes` of the environment.
+
+use std::{
+    collections::BTreeMap,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use alloy_rlp::{Decodable, Encodable, Header};
+use anyhow::{Context, Result, bail};
+use dora::compile_handler;
+use dora_primitives::{
+    AccessList, Address, B256, Bytes, Env, GAS_PER_BLOB, Log, SignedAuthorization, SpecId,
+    SpecName, TxEnv, TxKind, U256,
+    alloy_primitives::{Bloom, U64, U128},
+    calc_excess_blob_gas, keccak256,
+};
+use dora_runtime::{
+    Block, BlockError, BlockExecutor, BlockResult, BlockTransaction, Database, ExecutionResult, VM,
+    VMContext, Withdrawal,
+    block::{EIP1559_TX_TYPE, EIP2930_TX_TYPE, EIP4844_TX_TYPE, EIP7702_TX_TYPE, LEGACY_TX_TYPE},
+    genesis::{GenesisAlloc, Prestate},
+    result::Output,
+    trie::{ordered_trie_root, state_root},
+};
+use k256::ecdsa::{RecoveryId, Signature, SigningKey, VerifyingKey};
+use serde::{Deserialize, Deserializer, Serialize, de, de::DeserializeOwned};
+
+use crate::trie::{encodable_trie_root, log_rlp_hash};
+
+/// The file name which reads the input from the standard input.
+pub const STDIN: &str = "stdin";
+/// The file name which writes the output to the standard output.
+pub const STDOUT: &str = "stdout";
+/// The file name which writes the output to the standard error.
+pub const STDERR: &str = "stderr";
+
+/// The EIP-1559 base fee max change denominator.
+const BASE_FEE_MAX_CHANGE_DENOMINATOR: u64 = 8;
+/// The EIP-1559 elasticity multiplier.
+const ELASTICITY_MULTIPLIER: u64 = 2;
+/// The half of the secp256k1 curve order, the EIP-2 upper bound of the signature `s` value.
+const SECP256K1N_HALF: U256 = U256::from_limbs([
+    0xdfe92f46681b20a0,
+    0x5d576e7357a4501d,
+    0xffffffffffffffff,
+    0x7fffffffffffffff,
+]);
+
+/// The block environment of the `env.json` input.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct T8nEnv {
+    pub current_coinbase: Address,
+    pub current_gas_limit: U256,
+    pub current_number: U256,
+    pub current_timestamp: U256,
+    pub current_difficulty: Option<U256>,
+    pub current_random: Option<B256>,
+    pub current_base_fee: Option<U256>,
+    pub parent_base_fee: Option<U256>,
+    pub parent_gas_used: Option<U256>,
+    pub parent_gas_limit: Option<U256>,
+    pub current_excess_blob_gas: Option<U256>,
+    pub parent_excess_blob_gas: Option<U256>,
+    pub parent_blob_gas_used: Option<U256>,
+    #[serde(default)]
+    pub block_hashes: BTreeMap<U256, B256>,
+    pub withdrawals: Option<Vec<Withdrawal>>,
+    pub parent_beacon_block_root: Option<B256>,
+}
+
+impl T8nEnv {
+    /// Returns the block base fee, which is computed from the parent block when it is not given.
+    pub fn base_fee(&self, spec_id: SpecId) -> Option<U256> {
+        if !spec_id.is_enabled_in(SpecId::LONDON) {
+            return None;
+        }
+        if self.current_base_fee.is_some() {
+            return self.current_base_fee;
+        }
+        let parent_base_fee: u64 = self.parent_base_fee?.saturating_to();
+        let parent_gas_used: u64 = self.parent_gas_used?.saturating_to();
+        let gas_target = self.parent_gas_limit?.saturating_to::<u64>() / ELASTICITY_MULTIPLIER;
+        if gas_target == 0 || parent_gas_used == gas_target {
+            return Some(U256::from(parent_base_fee));
+        }
+        let base_fee = if parent_gas_used > gas_target {
+            let delta = (parent_base_fee as u128 * (parent_gas_used - gas_target) as u128
+                / gas_target as u128
+                / BASE_FEE_MAX_CHANGE_DENOMINATOR as u128)
+                .max(1);
+            parent_base_fee as u128 + delta
+        } else {
+            let delta = parent_base_fee as u128 * (gas_target - parent_gas_used) as u128
+                / gas_target as u128
+                / BASE_FEE_MAX_CHANGE_DENOMINATOR as u128;
+            (parent_base_fee as u128).saturating_sub(delta)
+        };
+        Some(U256::from(base_fee))
+    }
+
+    /// Returns the block excess blob gas, which is computed from the parent block when it is
+    /// not given.
+    pub fn excess_blob_gas(&self, spec_id: SpecId) -> Option<u64> {
+        if !spec_id.is_enabled_in(SpecId::CANCUN) {
+            return None;
+        }
+        if let Some(excess_blob_gas) = self.current_excess_blob_gas {
+            return Some(excess_blob_gas.saturating_to());
+        }
+        let target_blob_gas = if spec_id.is_enabled_in(SpecId::PRAGUE) {
+            6 * GAS_PER_BLOB
+        } else {
+            3 * GAS_PER_BLOB
+        };
+        Some(calc_excess_blob_gas(
+            self.parent_excess_blob_gas
+                .unwrap_or_default()
+                .saturating_to(),
+            self.parent_blob_gas_used
+                .unwrap_or_default()
+                .saturating_to(),
+            target_blob_gas,
+        ))
+    }
+
+    /// Returns the parent block hash of the EIP-2935 system call, which is looked up in the block
+    /// hashes by the parent block number.
+    pub fn parent_hash(&self) -> Option<B256> {
+        let parent_number = self.current_number.checked_sub(U256::from(1))?;
+        self.block_hashes.get(&parent_number).copied()
+    }
+
+    /// Builds the VM environment of the block.
+    pub fn to_env(&self, spec_id: SpecId, chain_id: u64) -> Env {
+        let mut env = Env::default();
+        env.cfg.chain_id = chain_id;
+        env.block.number = self.current_number;
+        env.block.coinbase = self.current_coinbase;
+        env.block.timestamp = self.current_timestamp;
+        env.block.gas_limit = self.current_gas_limit;
+        env.block.difficulty = self.current_difficulty.unwrap_or_default();
+        env.block.prevrandao = self.current_random;
+        if spec_id.is_enabled_in(SpecId::MERGE) && env.block.prevrandao.is_none() {
+            env.block.prevrandao = Some(B256::ZERO);
+        }
+        env.block.basefee = self.base_fee(spec_id).unwrap_or_default();
+        if let Some(excess_blob_gas) = self.excess_blob_gas(spec_id) {
+            env.block.set_blob_excess_gas_and_price(
+                excess_blob_gas,
+                spec_id.is_enabled_in(SpecId::PRAGUE),
+            );
+        }
+        env
+    }
+}
+
+/// The transaction of the `txs.json` input, it is signed with the secret key when the key is
+/// given, otherwise the signature fields are used.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct T8nTransaction {
+    #[serde(rename = "type")]
+    pub tx_type: Option<U64>,
+    pub chain_id: Option<U64>,
+    #[serde(default)]
+    pub nonce: U64,
+    #[serde(alias = "gasLimit")]
+    pub gas: U64,
+    pub gas_price: Option<U128>,
+    pub max_priority_fee_per_gas: Option<U128>,
+    pub max_fee_per_gas: Option<U128>,
+    #[serde(default, deserialize_with = "deserialize_to")]
+    pub to: Option<Address>,
+    #[serde(default)]
+    pub value: U256,
+    #[serde(default, alias = "data")]
+    pub input: Bytes,
+    pub access_list: Option<AccessList>,
+    pub max_fee_per_blob_gas: Option<U128>,
+    #[serde(default)]
+    pub blob_versioned_hashes: Vec<B256>,
+    pub authorization_list: Option<Vec<SignedAuthorization>>,
+    pub v: Option<U64>,
+    pub y_parity: Option<U64>,
+    pub r: Option<U256>,
+    pub s: Option<U256>,
+    pub secret_key: Option<B256>,
+    /// Whether the legacy transaction is EIP-155 replay protected, default is true.
+    pub protected: Option<bool>,
+}
+
+impl T8nTransaction {
+    /// Converts the JSON transaction into the transaction, and signs it when the secret key is
+    /// given.
+    pub fn into_transaction(self, chain_id: u64) -> Result<Transaction> {
+        let tx_type = match self.tx_type {
+            Some(tx_type) => tx_type.saturating_to(),
+            None if self.authorization_list.is_some() => EIP7702_TX_TYPE,
+            None if self.max_fee_per_blob_gas.is_some() => EIP4844_TX_TYPE,
+            None if self.max_fee_per_gas.is_some() => EIP1559_TX_TYPE,
+            None if self.access_list.is_some() => EIP2930_TX_TYPE,
+            None => LEGACY_TX_TYPE,
+        };
+        if tx_type > EIP7702_TX_TYPE {
+            bail!("unsupported transaction type {tx_type}");
+        }
+        let signature = TxSignature {
+            v: self.v.or(self.y_parity).unwrap_or_default().saturating_to(),
+            r: self.r.unwrap_or_default(),
+            s: self.s.unwrap_or_default(),
+        };
+        let chain_id = self
+            .chain_id
+            .map(|chain_id| chain_id.saturating_to())
+            .unwrap_or(chain_id);
+        let chain_id = if tx_type != LEGACY_TX_TYPE {
+            Some(chain_id)
+        } else if self.secret_key.is_some() {
+            self.protected.unwrap_or(true).then_some(chain_id)
+        } else {
+            signature.legacy_chain_id()
+        };
+        let mut tx = Transaction {
+            tx_type,
+            chain_id,
+            nonce: self.nonce.saturating_to(),
+            gas_price: self
+                .gas_price
+                .or(self.max_fee_per_gas)
+                .unwrap_or_default()
+                .saturating_to(),
+            max_priority_fee_per_gas: self.max_priority_fee_per_gas.map(|fee| fee.saturating_to()),
+            gas_limit: self.gas.saturating_to(),
+            to: self.to.map_or(TxKind::Create, TxKind::Call),
+            value: self.value,
+            input: self.input,
+            access_list: self.access_list.unwrap_or_default(),
+            max_fee_per_blob_gas: self
+                .max_fee_per_blob_gas
+                .unwrap_or_default()
+                .saturating_to(),
+            blob_versioned_hashes: self.blob_versioned_hashes,
+            authorization_list: self.authorization_list.unwrap_or_default(),
+            signature,
+        };
+        if let Some(secret_key) = self.secret_key {
+            tx.sign(&secret_key)?;
+        }
+        Ok(tx)
+    }
+}
+
+/// The transactions of the `txs.json` input, either the JSON transactions or the hex string of
+/// the RLP encoded transaction list.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum T8nTransactions {
+    Json(Vec<T8nTransaction>),
+    Rlp(Bytes),
+}
+
+impl Default for T8nTransactions {
+    fn default() -> Self {
+        Self::Json(Vec::new())
+    }
+}
+
+impl T8nTransactions {
+    /// Decodes the transactions.
+    pub fn into_transactions(self, chain_id: u64) -> Result<Vec<Transaction>> {
+        match self {
+            Self::Json(txs) => txs
+                .into_iter()
+                .enumerate()
+                .map(|(index, tx)| {
+                    tx.into_transaction(chain_id)
+                        .with_context(|| format!("Invalid transaction {index}"))
+                })
+                .collect(),
+            Self::Rlp(rlp) => {
+                let mut buf = rlp.as_ref();
+                let header = Header::decode(&mut buf)?;
+                if !header.list {
+                    bail!("The RLP transactions must be encoded as a list");
+                }
+                let mut txs = Vec::new();
+                while !buf.is_empty() {
+                    txs.push(Transaction::decode(&mut buf)?);
+                }
+                Ok(txs)
+            }
+        }
+    }
+}
+
+/// The signature values of the transaction, the `v` is the y parity for the typed transactions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TxSignature {
+    pub v: u64,
+    pub r: U256,
+    pub s: U256,
+}
+
+impl TxSignature {
+    /// Returns the EIP-155 chain id of the legacy transaction signature.
+    fn legacy_chain_id(&self) -> Option<u64> {
+        (self.v >= 35).then(|| (self.v - 35) / 2)
+    }
+}
+
+/// The signed transaction of all the transaction types.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Transaction {
+    pub tx_type: u8,
+    /// The chain id, which is none for the legacy transaction without the EIP-155 protection.
+    pub chain_id: Option<u64>,
+    pub nonce: u64,
+    /// The gas price of the legacy and EIP-2930 transactions, or the max fee per gas.
+    pub gas_price: u128,
+    pub max_priority_fee_per_gas: Option<u128>,
+    pub gas_limit: u64,
+    pub to: TxKind,
+    pub value: U256,
+    pub input: Bytes,
+    pub access_list: AccessList,
+    pub max_fee_per_blob_gas: u128,
+    pub blob_versioned_hashes: Vec<B256>,
+    pub authorization_list: Vec<SignedAuthorization>,
+    pub signature: TxSignature,
+}
+
+impl Transaction {
+    /// Encodes the fields without the signature in the order of the transaction type.
+    fn encode_fields(&self, out: &mut Vec<u8>) {
+        if self.tx_type == LEGACY_TX_TYPE {
+            self.nonce.encode(out);
+            self.gas_price.encode(out);
+            self.gas_limit.encode(out);
+            self.to.encode(out);
+            self.value.encode(out);
+            self.input.encode(out);
+            return;
+        }
+        self.chain_id.unwrap_or_default().encode(out);
+        self.nonce.encode(out);
+        if self.tx_type == EIP2930_TX_TYPE {
+            self.gas_price.encode(out);
+        } else {
+            self.max_priority_fee_per_gas
+                .unwrap_or_default()
+                .encode(out);
+            self.gas_price.encode(out);
+        }
+        self.gas_limit.encode(out);
+        self.to.encode(out);
+        self.value.encode(out);
+        self.input.encode(out);
+        self.access_list.encode(out);
+        if self.tx_type == EIP4844_TX_TYPE {
+            self.max_fee_per_blob_gas.encode(out);
+            self.blob_versioned_hashes.encode(out);
+        } else if self.tx_type == EIP7702_TX_TYPE {
+            self.authorization_list.encode(out);
+        }
+    }
+
+    /// Decodes the fields in the order of the transaction type.
+    fn decode_fields(tx_type: u8, buf: &mut &[u8]) -> alloy_rlp::Result<Self> {
+        let header = Header::decode(buf)?;
+        if !header.list {
+            return Err(alloy_rlp::Error::UnexpectedString);
+        }
+        let mut tx = Self {
+            tx_type,
+            ..Default::default()
+        };
+        if tx_type != LEGACY_TX_TYPE {
+            tx.chain_id = Some(Decodable::decode(buf)?);
+        }
+        tx.nonce = Decodable::decode(buf)?;
+        if tx_type != LEGACY_TX_TYPE && tx_type != EIP2930_TX_TYPE {
+            tx.max_priority_fee_per_gas = Some(Decodable::decode(buf)?);
+        }
+        tx.gas_price = Decodable::decode(buf)?;
+        tx.gas_limit = Decodable::decode(buf)?;
+        tx.to = Decodable::decode(buf)?;
+        tx.value = Decodable::decode(buf)?;
+        tx.input = Decodable::decode(buf)?;
+        if tx_type != LEGACY_TX_TYPE {
+            tx.access_list = Decodable::decode(buf)?;
+        }
+        if tx_type == EIP4844_TX_TYPE {
+            tx.max_fee_per_blob_gas = Decodable::decode(buf)?;
+            tx.blob_versioned_hashes = Decodable::decode(buf)?;
+        } else if tx_type == EIP7702_TX_TYPE {
+            tx.authorization_list = Decodable::decode(buf)?;
+        }
+        tx.signature = TxSignature {
+            v: Decodable::decode(buf)?,
+            r: Decodable::decode(buf)?,
+            s: Decodable::decode(buf)?,
+        };
+        if tx_type == LEGACY_TX_TYPE {
+            tx.chain_id = tx.signature.legacy_chain_id();
+        }
+        Ok(tx)
+    }
+
+    /// Decodes the transaction of the block body, which is the RLP list of the legacy
+    /// transaction, or the RLP string of the EIP-2718 encoded typed transaction.
+    pub fn decode(buf: &mut &[u8]) -> Result<Self> {
+        if buf
+            .first()
+            .is_some_and(|byte| *byte >= alloy_rlp::EMPTY_LIST_CODE)
+        {
+            return Ok(Self::decode_fields(LEGACY_TX_TYPE, buf)?);
+        }
+        let header = Header::decode(buf)?;
+        if header.list || header.payload_length > buf.len() {
+            bail!("Invalid typed transaction encoding");
+        }
+        let (mut payload, rest) = buf.split_at(header.payload_length);
+        *buf = rest;
+        let Some((&tx_type, _)) = payload.split_first() else {
+            bail!("Empty typed transaction");
+        };
+        if tx_type == LEGACY_TX_TYPE || tx_type > EIP7702_TX_TYPE {
+            bail!("unsupported transaction type {tx_type}");
+        }
+        payload = &payload[1..];
+        Ok(Self::decode_fields(tx_type, &mut payload)?)
+    }
+
+    /// Wraps the fields into the RLP list prefixed by the transaction type.
+    fn envelope(&self, fields: Vec<u8>) -> Vec<u8> {
+        let mut out = Vec::with_capacity(fields.len() + 10);
+        if self.tx_type != LEGACY_TX_TYPE {
+            out.push(self.tx_type);
+        }
+        Header {
+            list: true,
+            payload_length: fields.len(),
+        }
+        .encode(&mut out);
+        out.extend(fields);
+        out
+    }
+
+    /// Returns the hash signed by the sender.
+    pub fn signature_hash(&self) -> B256 {
+        let mut fields = Vec::new();
+        self.encode_fields(&mut fields);
+        if self.tx_type == LEGACY_TX_TYPE {
+            if let Some(chain_id) = self.chain_id {
+                chain_id.encode(&mut fields);
+                0u8.encode(&mut fields);
+                0u8.encode(&mut fields);
+            }
+        }
+        keccak256(self.envelope(fields))
+    }
+
+    /// Returns the EIP-2718 encoding of the signed transaction.
+    pub fn encoded(&self) -> Vec<u8> {
+        let mut fields = Vec::new();
+        self.encode_fields(&mut fields);
+        self.signature.v.encode(&mut fields);
+        self.signature.r.encode(&mut fields);
+        self.signature.s.encode(&mut fields);
+        self.envelope(fields)
+    }
+
+    /// Returns the transaction hash.
+    pub fn hash(&self) -> B256 {
+        keccak256(self.encoded())
+    }
+
+    /// Signs the transaction with the secret key.
+    pub fn sign(&mut self, secret_key: &B256) -> Result<()> {
+        let key = SigningKey::from_slice(secret_key.as_slice()).context("Invalid secret key")?;
+        let (signature, recovery_id) = key
+            .sign_prehash_recoverable(self.signature_hash().as_slice())
+            .context("Failed to sign the transaction")?;
+        let bytes = signature.to_bytes();
+        let parity = recovery_id.to_byte() as u64;
+        self.signature = TxSignature {
+            v: match (self.tx_type, self.chain_id) {
+                (LEGACY_TX_TYPE, Some(chain_id)) => parity + 35 + 2 * chain_id,
+                (LEGACY_TX_TYPE, None) => parity + 27,
+                _ => parity,
+            },
+            r: U256::from_be_slice(&bytes[..32]),
+            s: U256::from_be_slice(&bytes[32..]),
+        };
+        Ok(())
+    }
+
+    /// Recovers the sender address from the signature, the signature with the `s` value greater
+    /// than the half of the curve order is rejected since the Homestead (EIP-2).
+    pub fn recover_sender(&self, spec_id: SpecId) -> Result<Address> {
+        let TxSignature { v, r, s } = self.signature;
+        if spec_id.is_enabled_in(SpecId::HOMESTEAD) && s > SECP256K1N_HALF {
+            bail!("invalid signature s value {s}, greater than secp256k1n/2");
+        }
+        let parity = match (self.tx_type, self.chain_id) {
+            (LEGACY_TX_TYPE, Some(chain_id)) => v.checked_sub(35 + 2 * chain_id),
+            (LEGACY_TX_TYPE, None) => v.checked_sub(27),
+            _ => Some(v),
+        }
+        .filter(|parity| *parity <= 1)
+        .with_context(|| format!("invalid signature v value {v}"))?;
+        let mut bytes = [0u8; 64];
+        bytes[..32].copy_from_slice(&r.to_be_bytes::<32>());
+        bytes[32..].copy_from_slice(&s.to_be_bytes::<32>());
+        let mut signature = Signature::from_slice(&bytes).context("invalid signature")?;
+        let mut recovery_id = RecoveryId::from_byte(parity as u8).context("invalid signature")?;
+        // The high `s` value is valid before the Homestead, recover the sender from the
+        // equivalent low `s` signature which has the opposite y parity.
+        if let Some(normalized) = signature.normalize_s() {
+            signature = normalized;
+            recovery_id = RecoveryId::new(!recovery_id.is_y_odd(), recovery_id.is_x_reduced());
+        }
+        let key = VerifyingKey::recover_from_prehash(
+            self.signature_hash().as_slice(),
+            &signature,
+            recovery_id,
+        )
+        .context("invalid signature")?;
+        Ok(Address::from_raw_public_key(
+            &key.to_encoded_point(false).as_bytes()[1..],
+        ))
+    }
+
+    /// Returns the price per gas paid by the sender.
+    pub fn effective_gas_price(&self, base_fee: U256) -> U256 {
+        match self.max_priority_fee_per_gas {
+            Some(priority_fee) if self.tx_type >= EIP1559_TX_TYPE => {
+                U256::from(self.gas_price).min(base_fee + U256::from(priority_fee))
+            }
+            _ => U256::from(self.gas_price),
+        }
+    }
+
+    /// Converts into the block transaction sent by the sender.
+    pub fn to_block_transaction(&self, sender: Address) -> BlockTransaction {
+        BlockTransaction {
+            tx_type: self.tx_type,
+            tx: TxEnv {
+                caller: sender,
+                nonce: Some(self.nonce),
+                chain_id: self.chain_id,
+                gas_limit: self.gas_limit,
+                gas_price: U256::from(self.gas_price),
+                gas_priority_fee: self.max_priority_fee_per_gas.map(U256::from),
+                transact_to: self.to,
+                value: self.value,
+                data: self.input.clone(),
+                access_list: self.access_list.0.clone(),
+                blob_hashes: self.blob_versioned_hashes.clone(),
+                max_fee_per_blob_gas: (self.tx_type == EIP4844_TX_TYPE)
+                    .then(|| U256::from(self.max_fee_per_blob_gas)),
+                authorization_list: (self.tx_type == EIP7702_TX_TYPE).then(|| {
+                    dora_primitives::AuthorizationList::Signed(self.authorization_list.clone())
+                        .into_recovered()
+                }),
+            },
+        }
+    }
+}
+
+/// The receipt of the included transaction.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct T8nReceipt {
+    #[serde(rename = "type")]
+    pub tx_type: U64,
+    pub root: Bytes,
+    pub status: U64,
+    pub cumulative_gas_used: U64,
+    pub logs_bloom: Bloom,
+    pub logs: Vec<Log>,
+    pub transaction_hash: B256,
+    pub contract_address: Address,
+    pub gas_used: U64,
+    pub effective_gas_price: U256,
+    pub block_hash: B256,
+    pub transaction_index: U64,
+}
+
+/// The transaction rejected by the block.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RejectedTx {
+    pub index: usize,
+    pub error: String,
+}
+
+/// The execution result of the `result.json` output.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct T8nResult {
+    pub state_root: B256,
+    pub tx_root: B256,
+    pub receipts_root: B256,
+    pub logs_hash: B256,
+    pub logs_bloom: Bloom,
+    pub receipts: Vec<T8nReceipt>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub rejected: Vec<RejectedTx>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_difficulty: Option<U256>,
+    pub gas_used: U64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_base_fee: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub withdrawals_root: Option<B256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_excess_blob_gas: Option<U64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blob_gas_used: Option<U64>,
+}
+
+/// The input of the state transition.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct T8nInput {
+    #[serde(default)]
+    pub alloc: GenesisAlloc,
+    #[serde(default)]
+    pub env: T8nEnv,
+    #[serde(default)]
+    pub txs: T8nTransactions,
+}
+
+impl T8nInput {
+    /// Reads the input files, the input named [`STDIN`] is read from the JSON object containing
+    /// the `alloc`, `env` and `txs` fields on the standard input.
+    pub fn read(alloc: &str, env: &str, txs: &str) -> Result<Self> {
+        let mut stdin = None;
+        if [alloc, env, txs].contains(&STDIN) {
+            let mut json = String::new();
+            std::io::stdin()
+                .read_to_string(&mut json)
+                .context("Failed to read the standard input")?;
+            stdin = Some(serde_json::from_str::<Self>(&json).context("Invalid standard input")?);
+        }
+        let stdin = stdin.unwrap_or_default();
+        Ok(Self {
+            alloc: if alloc == STDIN {
+                stdin.alloc
+            } else {
+                read_json(alloc)?
+            },
+            env: if env == STDIN {
+                stdin.env
+            } else {
+                read_json(env)?
+            },
+            txs: if txs == STDIN {
+                stdin.txs
+            } else {
+                read_json(txs)?
+            },
+        })
+    }
+}
+
+/// The output of the state transition.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct T8nOutput {
+    pub alloc: GenesisAlloc,
+    pub result: T8nResult,
+    /// The RLP encoded transactions of the block body.
+    pub body: Bytes,
+}
+
+impl T8nOutput {
+    /// Writes the outputs into the files under the base directory, the outputs named [`STDOUT`]
+    /// or [`STDERR`] are written as a JSON object to the standard output or the standard error.
+    pub fn write(
+        &self,
+        basedir: &Path,
+        result: &str,
+        alloc: &str,
+        body: Option<&str>,
+    ) -> Result<()> {
+        let mut stdout = serde_json::Map::new();
+        let mut stderr = serde_json::Map::new();
+        let outputs = [
+            ("result", Some(result), serde_json::to_value(&self.result)?),
+            ("alloc", Some(alloc), serde_json::to_value(&self.alloc)?),
+            ("body", body, serde_json::to_value(&self.body)?),
+        ];
+        for (name, file, value) in outputs {
+            match file {
+                None => {}
+                Some(STDOUT) => {
+                    stdout.insert(name.to_string(), value);
+                }
+                Some(STDERR) => {
+                    stderr.insert(name.to_string(), value);
+                }
+                Some(file) => {
+                    let path: PathBuf = basedir.join(file);
+                    std::fs::write(&path, serde_json::to_string_pretty(&value)?)
+                        .with_context(|| format!("Failed to write {}", path.display()))?;
+                }
+            }
+        }
+        if !stdout.is_empty() {
+            println!("{}", serde_json::to_string_pretty(&stdout)?);
+        }
+        if !stderr.is_empty() {
+            eprintln!("{}", serde_json::to_string_pretty(&stderr)?);
+        }
+        Ok(())
+    }
+}
+
+/// Parses the fork name used by the tests e.g., `Cancun`, `Shanghai` or `Paris`.
+pub fn parse_fork(fork: &str) -> Result<SpecId> {
+    let spec_name: SpecName = serde_json::from_value(serde_json::Value::String(fork.to_string()))?;
+    if spec_name == SpecName::Unknown {
+        bail!("Unsupported fork: {fork}");
+    }
+    Ok(spec_name.to_spec_id())
+}
+
+/// Applies the transactions on the pre-state, the block reward is paid to the coinbase before
+/// the merge when it is given.
+pub fn transition(
+    input: T8nInput,
+    spec_id: SpecId,
+    chain_id: u64,
+    reward: Option<U256>,
+) -> Result<T8nOutput> {
+    let T8nInput { alloc, env, txs } = input;
+    let txs = txs.into_transactions(chain_id)?;
+    let block_env = env.to_env(spec_id, chain_id);
+    let db = Prestate {
+        alloc,
+        block_hashes: env.block_hashes.clone(),
+    }
+    .into_db();
+    if spec_id.is_enabled_in(SpecId::CANCUN) && env.parent_beacon_block_root.is_none() {
+        bail!("The post-Cancun environment requires the parentBeaconBlockRoot");
+    }
+    let block = Block {
+        env: block_env.block.clone(),
+        withdrawals: env.withdrawals.clone(),
+        parent_beacon_block_root: env.parent_beacon_block_root,
+        parent_hash: env.parent_hash(),
+        ..Default::default()
+    };
+    let mut executor = BlockExecutor::new(VM::new(VMContext::new(
+        db,
+        block_env.clone(),
+        spec_id,
+        compile_handler(),
+    )));
+    executor.apply_pre_block_system_calls(&block)?;
+
+    let mut included = Vec::with_capacity(txs.len());
+    let mut rejected = Vec::new();
+    let mut block_result = BlockResult::default();
+    for (index, tx) in txs.into_iter().enumerate() {
+        let sender = match tx.recover_sender(spec_id) {
+            Ok(sender) => sender,
+            Err(err) => {
+                rejected.push(RejectedTx {
+                    index,
+                    error: err.to_string(),
+                });
+                continue;
+            }
+        };
+        let transaction = tx.to_block_transaction(sender);
+        if let Err(err) = executor.execute_transaction(index, &transaction, &mut block_result) {
+            let error = match err {
+                BlockError::Transaction { error, .. } => error.to_string(),
+                err => err.to_string(),
+            };
+            rejected.push(RejectedTx { index, error });
+            continue;
+        }
+        included.push(tx);
+    }
+
+    // Apply the block reward before the withdrawals are applied by the executor.
+    if let Some(reward) = reward.filter(|_| !spec_id.is_enabled_in(SpecId::MERGE)) {
+        let db = &mut executor.vm_mut().db;
+        let balance = db.get_balance(env.current_coinbase).unwrap_or_default();
+        db.set_balance(env.current_coinbase, balance + reward);
+    }
+    let block_result = executor.finish_block(&block, block_result)?;
+    let vm = executor.into_vm();
+
+    let receipts = included
+        .iter()
+        .zip(&block_result.receipts)
+        .zip(&block_result.results)
+        .enumerate()
+        .map(|(index, ((tx, receipt), result))| T8nReceipt {
+            tx_type: U64::from(receipt.tx_type),
+            root: Bytes::new(),
+            status: U64::from(receipt.status as u64),
+            cumulative_gas_used: U64::from(receipt.cumulative_gas_used),
+            logs_bloom: receipt.logs_bloom,
+            logs: receipt.logs.clone(),
+            transaction_hash: tx.hash(),
+            contract_address: match result {
+                ExecutionResult::Success {
+                    output: Output::Create(_, address),
+                    ..
+                } => address.unwrap_or_default(),
+                _ => Address::ZERO,
+            },
+            gas_used: U64::from(receipt.gas_used),
+            effective_gas_price: tx.effective_gas_price(block_env.block.basefee),
+            block_hash: B256::ZERO,
+            transaction_index: U64::from(index),
+        })
+        .collect();
+    let logs: Vec<Log> = block_result
+        .receipts
+        .iter()
+        .flat_map(|receipt| receipt.logs.iter().cloned())
+        .collect();
+
+    let state_root = state_root(&vm.db.clone().into_state());
+    let result = T8nResult {
+        state_root,
+        tx_root: ordered_trie_root(included.iter().map(Transaction::encoded)),
+        receipts_root: block_result.receipts_root,
+        logs_hash: log_rlp_hash(&logs),
+        logs_bloom: block_result.logs_bloom,
+        receipts,
+        rejected,
+        current_difficulty: (!spec_id.is_enabled_in(SpecId::MERGE))
+            .then(|| env.current_difficulty.unwrap_or_default()),
+        gas_used: U64::from(block_result.gas_used),
+        current_base_fee: env.base_fee(spec_id),
+        withdrawals_root: env
+            .withdrawals
+            .as_ref()
+            .filter(|_| spec_id.is_enabled_in(SpecId::SHANGHAI))
+            .map(|withdrawals| encodable_trie_root(withdrawals)),
+        current_excess_blob_gas: env.excess_blob_gas(spec_id).map(U64::from),
+        blob_gas_used: spec_id
+            .is_enabled_in(SpecId::CANCUN)
+            .then(|| U64::from(block_result.blob_gas_used)),
+    };
+    Ok(T8nOutput {
+        alloc: vm.db.dump_state(),
+        result,
+        body: encode_body(&included).into(),
+    })
+}
+
+/// Encodes the transactions of the block body, the typed transactions are wrapped into the RLP
+/// strings.
+pub fn encode_body(txs: &[Transaction]) -> Vec<u8> {
+    let mut items = Vec::new();
+    for tx in txs {
+        let encoded = tx.encoded();
+        if tx.tx_type != LEGACY_TX_TYPE {
+            Header {
+                list: false,
+                payload_length: encoded.len(),
+            }
+            .encode(&mut items);
+        }
+        items.extend(encoded);
+    }
+    let mut body = Vec::with_capacity(items.len() + 10);
+    Header {
+        list: true,
+        payload_length: items.len(),
+    }
+    .encode(&mut body);
+    body.extend(items);
+    body
+}
+
+fn read_json<T: DeserializeOwned>(path: &str) -> Result<T> {
+    let json = std::fs::read_to_string(path).with_context(|| format!("Failed to read {path}"))?;
+    serde_json::from_str(&json).with_context(|| format!("Invalid JSON file: {path}"))
+}
+
+fn deserialize_to<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Address>, D::Error> {
+    match Option::<String>::deserialize(deserializer)?.as_deref() {
+        None | Some("") => Ok(None),
+        Some(to) => to.parse().map(Some).map_err(de::Error::custom),
+    }
+}
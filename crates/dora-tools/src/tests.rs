@@ -0,0 +1 @@
+mod t8n;
@@ -0,0 +1,168 @@
+use dora_primitives::{Address, B256, SpecId, U256, address, b256};
+use dora_runtime::block::BEACON_ROOTS_ADDRESS;
+use serde_json::json;
+
+use crate::t8n::{T8nInput, transition};
+
+/// The sender of the EIP-155 example transaction, whose secret key is `0x4646..46`.
+const SENDER: Address = address!("9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f");
+const SECRET_KEY: B256 = b256!("4646464646464646464646464646464646464646464646464646464646464646");
+/// The signed transaction of the EIP-155 example.
+const EIP155_TX_HEX: &str = "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83";
+const EIP155_TX_HASH: B256 =
+    b256!("33469b22e9f636356c4160a87eb19df52b7412e8eac32a4a55ffe88ea8350788");
+/// The root of the empty trie.
+const EMPTY_ROOT: B256 = b256!("56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421");
+/// Stores the first word of the call data into the storage slot 0.
+const BEACON_ROOTS_CODE_HEX: &str = "0x5f355f5500";
+
+fn input(txs: serde_json::Value, env: serde_json::Value) -> T8nInput {
+    let mut input = json!({
+        "alloc": {
+            SENDER.to_string(): { "balance": "0x3635c9adc5dea00000", "nonce": "0x9" },
+            BEACON_ROOTS_ADDRESS.to_string(): { "code": BEACON_ROOTS_CODE_HEX },
+        },
+        "env": {
+            "currentCoinbase": "0x2adc25665018aa1fe0e6bc666dac8fc2697ff9ba",
+            "currentGasLimit": "0x1000000",
+            "currentNumber": "0x1",
+            "currentTimestamp": "0x3e8",
+            "currentRandom": "0x0000000000000000000000000000000000000000000000000000000000020000",
+            "currentDifficulty": "0x0",
+            "currentBaseFee": "0x7",
+            "currentExcessBlobGas": "0x0",
+            "parentBeaconBlockRoot": "0x1111111111111111111111111111111111111111111111111111111111111111",
+            "withdrawals": [],
+        },
+        "txs": txs,
+    });
+    for (key, value) in env.as_object().unwrap() {
+        input["env"][key] = value.clone();
+    }
+    serde_json::from_value(input).unwrap()
+}
+
+fn rlp_txs(tx_hex: &str) -> serde_json::Value {
+    let tx = hex::decode(tx_hex).unwrap();
+    let mut list = vec![0xf8, tx.len() as u8];
+    list.extend(tx);
+    json!(format!("0x{}", hex::encode(list)))
+}
+
+#[test]
+fn t8n_legacy_transaction_vector() {
+    let input = input(rlp_txs(EIP155_TX_HEX), json!({}));
+    let txs = input.txs.clone().into_transactions(1).unwrap();
+    assert_eq!(txs[0].hash(), EIP155_TX_HASH);
+    assert_eq!(txs[0].recover_sender(SpecId::CANCUN).unwrap(), SENDER);
+
+    let output = transition(input, SpecId::CANCUN, 1, None).unwrap();
+    let result = output.result;
+    assert!(result.rejected.is_empty());
+    assert_eq!(result.receipts.len(), 1);
+    assert_eq!(result.receipts[0].transaction_hash, EIP155_TX_HASH);
+    assert_eq!(
+        result.tx_root,
+        b256!("36cf58bec935fe50593ac7443cb728dd37dedac603d60fddfae59fd3bdbfcd7f")
+    );
+    assert_eq!(
+        result.receipts_root,
+        b256!("056b23fbba480696b65fe5a59b8f2148a1299103c4f57df839233af2cf4ca2d2")
+    );
+    assert_eq!(result.withdrawals_root, Some(EMPTY_ROOT));
+    assert_eq!(result.gas_used.to::<u64>(), 21_000);
+}
+
+#[test]
+fn t8n_signed_dynamic_fee_transaction_vector() {
+    let txs = json!([{
+        "type": "0x2",
+        "chainId": "0x1",
+        "nonce": "0x9",
+        "maxPriorityFeePerGas": "0x3b9aca00",
+        "maxFeePerGas": "0x4a817c800",
+        "gas": "0x5208",
+        "to": "0x3535353535353535353535353535353535353535",
+        "value": "0x1",
+        "input": "0x",
+        "accessList": [],
+        "secretKey": SECRET_KEY,
+    }]);
+    let output = transition(input(txs, json!({})), SpecId::CANCUN, 1, None).unwrap();
+    let result = output.result;
+    assert!(result.rejected.is_empty());
+    assert_eq!(
+        result.receipts[0].transaction_hash,
+        b256!("2032b64d2c5d0e993f18e20a1b63df3e4779a0cdf142a4c6c0835da9437c3ea7")
+    );
+    assert_eq!(
+        result.tx_root,
+        b256!("429d68aacd02ca845c58047a3f1da89f762211a5a1108730a6b6cf7d24b2f6ac")
+    );
+    assert_eq!(
+        result.receipts_root,
+        b256!("f78dfb743fbd92ade140711c8bbc542b5e307f0ab7984eff35d751969fe57efa")
+    );
+    // The sender pays the base fee and the priority fee of the transaction.
+    let balance = output.alloc[&SENDER].balance;
+    assert_eq!(
+        balance,
+        U256::from(1_000_000_000_000_000_000_000u128)
+            - U256::from(1 + 21_000 * (7 + 1_000_000_000u64))
+    );
+}
+
+#[test]
+fn t8n_withdrawals_root_vector() {
+    let env = json!({
+        "withdrawals": [
+            { "index": "0x0", "validatorIndex": "0x1", "address": "0x00000000000000000000000000000000000000aa", "amount": "0x3e8" },
+            { "index": "0x1", "validatorIndex": "0x2", "address": "0x00000000000000000000000000000000000000bb", "amount": "0x7d0" },
+        ],
+    });
+    let output = transition(input(json!([]), env), SpecId::SHANGHAI, 1, None).unwrap();
+    assert_eq!(
+        output.result.withdrawals_root,
+        Some(b256!(
+            "8ad342a2dfa92103e73b171f913fbdc2797e436f2cfa5ddcff6df9f175e94b89"
+        ))
+    );
+    assert_eq!(output.result.tx_root, EMPTY_ROOT);
+    assert_eq!(output.result.receipts_root, EMPTY_ROOT);
+    assert_eq!(
+        output.alloc[&address!("00000000000000000000000000000000000000aa")].balance,
+        U256::from(1_000_000_000_000u64)
+    );
+}
+
+#[test]
+fn t8n_rejects_high_s_signature_after_homestead() {
+    // The EIP-155 example signature with `s` replaced by `secp256k1n - s` and the flipped parity.
+    let tx_hex = EIP155_TX_HEX.replace("8025a0", "8026a0").replace(
+        "67cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83",
+        "98341627668089e51348fccfb4c7ff31c55912f2d2e47ef09652acf665fad3be",
+    );
+    let input = input(rlp_txs(&tx_hex), json!({}));
+    let tx = input.txs.clone().into_transactions(1).unwrap().remove(0);
+    assert!(tx.recover_sender(SpecId::CANCUN).is_err());
+    assert_eq!(tx.recover_sender(SpecId::FRONTIER).unwrap(), SENDER);
+
+    let result = transition(input, SpecId::CANCUN, 1, None).unwrap().result;
+    assert!(result.receipts.is_empty());
+    assert_eq!(result.rejected.len(), 1);
+    assert_eq!(result.rejected[0].index, 0);
+    assert_eq!(result.tx_root, EMPTY_ROOT);
+}
+
+#[test]
+fn t8n_applies_beacon_root_system_call() {
+    let output = transition(input(json!([]), json!({})), SpecId::CANCUN, 1, None).unwrap();
+    assert_eq!(
+        output.alloc[&BEACON_ROOTS_ADDRESS].storage[&U256::ZERO],
+        U256::from_be_bytes([0x11; 32])
+    );
+
+    let mut input = input(json!([]), json!({}));
+    input.env.parent_beacon_block_root = None;
+    assert!(transition(input, SpecId::CANCUN, 1, None).is_err());
+}
@@ -0,0 +1,17 @@
+//! The hash of the logs and the roots of the block items, the state root is computed by the
+//! [`dora_runtime::trie`] module.
+
+use alloy_rlp::Encodable;
+use dora_primitives::{B256, Log, keccak256};
+use dora_runtime::trie::ordered_trie_root;
+
+pub fn log_rlp_hash(logs: &[Log]) -> B256 {
+    let mut out = Vec::with_capacity(alloy_rlp::list_length(logs));
+    alloy_rlp::encode_list(logs, &mut out);
+    B256::from_slice(keccak256(&out).as_slice())
+}
+
+/// Computes the root of the trie of the RLP encodable items keyed by the RLP encoded index.
+pub fn encodable_trie_root<T: Encodable>(items: &[T]) -> B256 {
+    ordered_trie_root(items.iter().map(alloy_rlp::encode))
+}
@@ -0,0 +1,180 @@
+//! Emits the intermediate representation of every compile pipeline stage, which is used to
+//! inspect a miscompiled contract without writing the test code, see `dora compile --emit`.
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anyhow::bail;
+use dora_compiler::llvm::translate_to_llvm_ir;
+use dora_primitives::{Bytecode, OptimizationLevel, SpecId};
+
+use crate::{
+    Context, EVMCompileOptions, EVMCompiler, ExecuteKind, Executor, Module, Program,
+    WASMCompileOptions, WASMCompiler, lower_evm_module, lower_wasm_module,
+};
+
+/// The stage of the compile pipeline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum EmitStage {
+    /// The EVM or WASM dialect module produced by the frontend compiler.
+    Source,
+    /// The Dora dialect module after `evm::pass::run` or `wasm::pass::run`.
+    Dora,
+    /// The MLIR builtin dialects module after `dora::pass::run`.
+    Builtin,
+    /// The LLVM dialect module after `pass::run`.
+    Llvm,
+    /// The LLVM IR translated from the LLVM dialect module.
+    LlvmIr,
+    /// The native object code emitted by the execution engine.
+    Object,
+}
+
+impl EmitStage {
+    /// All the stages in the pipeline order.
+    pub const ALL: [EmitStage; 6] = [
+        EmitStage::Source,
+        EmitStage::Dora,
+        EmitStage::Builtin,
+        EmitStage::Llvm,
+        EmitStage::LlvmIr,
+        EmitStage::Object,
+    ];
+
+    /// Returns the stage name used by `--emit`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            EmitStage::Source => "source",
+            EmitStage::Dora => "dora",
+            EmitStage::Builtin => "builtin",
+            EmitStage::Llvm => "llvm",
+            EmitStage::LlvmIr => "llvm-ir",
+            EmitStage::Object => "obj",
+        }
+    }
+
+    /// Returns the file extension of the stage output.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            EmitStage::Source | EmitStage::Dora | EmitStage::Builtin | EmitStage::Llvm => "mlir",
+            EmitStage::LlvmIr => "ll",
+            EmitStage::Object => "o",
+        }
+    }
+
+    /// Returns whether the stage output is binary, which can not be printed.
+    #[inline]
+    pub fn is_binary(&self) -> bool {
+        matches!(self, EmitStage::Object)
+    }
+}
+
+impl fmt::Display for EmitStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for EmitStage {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        EmitStage::ALL
+            .into_iter()
+            .find(|stage| stage.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<&str> = EmitStage::ALL.iter().map(EmitStage::name).collect();
+                format!(
+                    "unknown emit stage: {s}, expected one of: {}",
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// The output of a compile stage.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmittedStage {
+    /// The compile stage.
+    pub stage: EmitStage,
+    /// The textual IR, or the native object code for [`EmitStage::Object`].
+    pub content: Vec<u8>,
+}
+
+/// Compile the EVM or WASM bytecode and returns the output of the selected stages in the
+/// pipeline order. The module of every stage is verified, and the compilation fails at the first
+/// stage which fails to verify.
+///
+/// # Example
+///
+/// ```ignore
+/// let stages = compile_stages(&code, SpecId::CANCUN, OptimizationLevel::Default, &[EmitStage::Dora])?;
+/// println!("{}", String::from_utf8_lossy(&stages[0].content));
+/// ```
+pub fn compile_stages(
+    code: &Bytecode,
+    spec_id: SpecId,
+    opt_level: OptimizationLevel,
+    stages: &[EmitStage],
+) -> anyhow::Result<Vec<EmittedStage>> {
+    let mut emitted = Vec::new();
+    let mut inspect = |stage: EmitStage, module: &Module| {
+        if !module.mlir_module.as_operation().verify() {
+            bail!("the {stage} stage module fails to verify");
+        }
+        if stages.contains(&stage) {
+            emitted.push(EmittedStage {
+                stage,
+                content: module.mlir_module.as_operation().to_string().into_bytes(),
+            });
+        }
+        Ok(())
+    };
+    let context = Context::new();
+    let (module, kind) = match code {
+        Bytecode::EVM(code) => {
+            let program = Program::from_opcodes(code.bytecode(), code.eof().cloned());
+            let opts = EVMCompileOptions::default()
+                .spec_id(spec_id)
+                .opt_level(opt_level);
+            let compiler = EVMCompiler::new(&context, opts);
+            let module = lower_evm_module(&compiler, &program, &mut inspect)?;
+            (module, ExecuteKind::EVM)
+        }
+        Bytecode::WASM(code) => {
+            let opts = WASMCompileOptions::default().opt_level(opt_level);
+            let compiler = WASMCompiler::new(&context, opts);
+            let module = lower_wasm_module(&compiler, code, &mut inspect)?;
+            (
+                module,
+                ExecuteKind::new_wasm(compiler.build_instance(code)?),
+            )
+        }
+    };
+    if stages.contains(&EmitStage::LlvmIr) {
+        emitted.push(EmittedStage {
+            stage: EmitStage::LlvmIr,
+            content: translate_to_llvm_ir(&module.mlir_module)?.into_bytes(),
+        });
+    }
+    if stages.contains(&EmitStage::Object) {
+        let executor = Executor::new_with_object_dump(module.module(), opt_level, kind);
+        // The concurrent emits in the same process must not share the temporary object file.
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "dora-emit-{}-{}.o",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let object = executor
+            .emit_object(&path)
+            .and_then(|_| Ok(std::fs::read(&path)?));
+        let _ = std::fs::remove_file(&path);
+        emitted.push(EmittedStage {
+            stage: EmitStage::Object,
+            content: object?,
+        });
+    }
+    Ok(emitted)
+}
@@ -1,5 +1,7 @@
+pub mod emit;
 #[cfg(test)]
 mod tests;
+pub mod tiered;
 
 pub use dora_compiler as compiler;
 pub use dora_ir as ir;
@@ -7,24 +9,36 @@ pub use dora_primitives as primitives;
 pub use dora_runtime as runtime;
 
 pub use dora_compiler::{
-    Compiler,
+    Compiler, Module,
     context::Context,
     dora,
-    evm::{self, EVMCompileOptions, EVMCompiler, program::Program},
+    evm::{
+        self, EVMCompileOptions, EVMCompiler,
+        asm::{AsmError, assemble, assemble_operations},
+        program::Program,
+    },
     pass,
     wasm::{self, WASMCompileOptions, WASMCompiler},
 };
 pub use dora_primitives::{
-    Bytecode, Bytes, Bytes32, EVMBytecode, Env, TxKind, WASMBytecode, spec::SpecId,
+    B256, Bytecode, Bytes, Bytes32, EVMBytecode, Env, OptimizationLevel, TxKind, WASMBytecode,
+    spec::SpecId,
 };
+pub use dora_runtime::cache::{ArtifactCache, CacheLimit, CacheStats, DiskCache};
 pub use dora_runtime::context::RuntimeContext;
+use dora_runtime::db::DatabaseError;
 pub use dora_runtime::executor::{ExecuteKind, Executor};
+use dora_runtime::parallel::{MvDatabase, WorkerHandler};
 pub use dora_runtime::stack::Stack;
 pub use dora_runtime::{
-    artifact::Artifact,
+    artifact::{Artifact, ArtifactKey},
     call::CallResult,
     context::VMContext,
     handler::{Frame, Handler},
+    inspector::{
+        CallFrame, CallTracer, Eip3155Tracer, GasProfile, GasProfiler, Inspector, PrestateFrame,
+        PrestateTracer, PrintInspector, Step, WasmStep,
+    },
     result::{ExecutionResult, VMError},
     vm::VM,
 };
@@ -32,7 +46,9 @@ pub use dora_runtime::{
     db::{Database, MemoryDB},
     result::ResultAndState,
 };
-use std::sync::Arc;
+pub use emit::{EmitStage, EmittedStage, compile_stages};
+use std::sync::{Arc, OnceLock};
+pub use tiered::{TieredCompiler, TieredStats};
 
 /// Run EVM or WASM with the environment configuration for the execution, given state database and return the execution result and final state.
 ///
@@ -57,11 +73,196 @@ pub fn run<DB: Database + 'static>(
     VM::new(VMContext::new(db, env, spec_id, compile_handler())).transact_commit()
 }
 
-/// Compile Handler for the VM.
+/// Run EVM or WASM with the environment configuration and the inspector attached to observe the execution,
+/// e.g., using the [`Eip3155Tracer`] to output the EIP-3155 JSON trace.
+///
+/// # Arguments
+///
+/// * `env` - The environment configuration for the execution (e.g., gas limit, transaction data, etc.).
+/// * `db` - The state database.
+/// * `spec_id` - The VM spec ID.
+/// * `inspector` - The inspector which receives the execution steps and call frames.
+///
+/// # Returns
+///
+/// Returns the execution result, the state changes are committed into the database.
+#[inline]
+pub fn run_with_inspector<'a, DB: Database + 'a, I: Inspector + 'a>(
+    env: Env,
+    db: DB,
+    spec_id: SpecId,
+    inspector: I,
+) -> Result<ExecutionResult, VMError> {
+    VM::new(VMContext::new(db, env, spec_id, compile_handler()).with_inspector(inspector))
+        .transact_commit()
+}
+
+/// Run EVM or WASM with the environment configuration and return the execution result and state
+/// alongside the call tree of the transaction, the state changes are not committed into the database.
+///
+/// # Arguments
+///
+/// * `env` - The environment configuration for the execution (e.g., gas limit, transaction data, etc.).
+/// * `db` - The state database.
+/// * `spec_id` - The VM spec ID.
+///
+/// # Returns
+///
+/// Returns `ResultAndState` and the root [`CallFrame`], the gas of the root frame includes the
+/// intrinsic gas of the transaction.
+pub fn transact_with_call_trace<DB: Database>(
+    env: Env,
+    db: DB,
+    spec_id: SpecId,
+) -> Result<(ResultAndState, CallFrame), VMError> {
+    let gas_limit = env.tx.gas_limit;
+    let mut tracer = CallTracer::new().with_log();
+    let result =
+        VM::new(VMContext::new(db, env, spec_id, compile_handler()).with_inspector(&mut tracer))
+            .transact()?;
+    let mut call_frame = tracer.into_call_frame().unwrap_or_default();
+    call_frame.gas = gas_limit;
+    call_frame.gas_used = result.result.gas_used();
+    Ok((result, call_frame))
+}
+
+/// Run EVM or WASM with the environment configuration and return the execution result and state
+/// alongside the geth `prestateTracer` output, the state changes are not committed into the database.
+///
+/// # Arguments
+///
+/// * `env` - The environment configuration for the execution (e.g., gas limit, transaction data, etc.).
+/// * `db` - The state database.
+/// * `spec_id` - The VM spec ID.
+/// * `diff_mode` - Whether to output the state before and after the execution.
+///
+/// # Returns
+///
+/// Returns `ResultAndState` and the [`PrestateFrame`] of the accounts accessed by the transaction.
+pub fn transact_with_prestate_trace<DB: Database>(
+    env: Env,
+    db: DB,
+    spec_id: SpecId,
+    diff_mode: bool,
+) -> Result<(ResultAndState, PrestateFrame), VMError> {
+    let mut vm = VM::new(VMContext::new(db, env, spec_id, compile_handler()));
+    let result = vm.transact()?;
+    let tracer = if diff_mode {
+        PrestateTracer::new().diff_mode()
+    } else {
+        PrestateTracer::new()
+    };
+    let frame = tracer
+        .trace(&vm.db, &result.state)
+        .map_err(|_| VMError::Database(DatabaseError))?;
+    Ok((result, frame))
+}
+
+/// Run EVM or WASM with the environment configuration and return the execution result and state
+/// alongside the gas profile per opcode, per program counter and per call frame, the state changes
+/// are not committed into the database.
+///
+/// # Arguments
+///
+/// * `env` - The environment configuration for the execution (e.g., gas limit, transaction data, etc.).
+/// * `db` - The state database.
+/// * `spec_id` - The VM spec ID.
+///
+/// # Returns
+///
+/// Returns `ResultAndState` and the [`GasProfile`], the intrinsic gas of the transaction is not
+/// included in the profile.
+pub fn transact_with_gas_profile<DB: Database>(
+    env: Env,
+    db: DB,
+    spec_id: SpecId,
+) -> Result<(ResultAndState, GasProfile), VMError> {
+    let mut profiler = GasProfiler::new();
+    let result =
+        VM::new(VMContext::new(db, env, spec_id, compile_handler()).with_inspector(&mut profiler))
+            .transact()?;
+    Ok((result, profiler.into_profile()))
+}
+
+/// The options of compiling the EVM or WASM bytecode to the native artifact, see
+/// [`compile_handler_with_options`], [`build_artifact_with_options`] and
+/// [`artifact_key_with_options`].
+///
+/// # Example
+///
+/// ```ignore
+/// let opts = CompileOptions::from_env().opt_level(OptimizationLevel::None);
+/// let mut vm = VM::new(VMContext::new(db, env, spec_id, compile_handler_with_options(opts)));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CompileOptions {
+    /// The optimization level, the lower level trades the run time for the compile latency.
+    pub opt_level: OptimizationLevel,
+    /// The on-disk artifact cache, the compiled EVM artifacts are stored in the cache and reused
+    /// across the process restarts. The artifacts are keyed by the optimization level as well.
+    pub disk_cache: Option<DiskCache>,
+}
+
+impl CompileOptions {
+    /// The default options with the on-disk artifact cache enabled when the `DORA_CACHE_DIR`
+    /// environment variable is set.
+    pub fn from_env() -> Self {
+        static DISK_CACHE: OnceLock<Option<DiskCache>> = OnceLock::new();
+        Self {
+            disk_cache: DISK_CACHE.get_or_init(DiskCache::from_env).clone(),
+            ..Default::default()
+        }
+    }
+
+    /// Set the optimization level.
+    pub fn opt_level(mut self, opt_level: OptimizationLevel) -> Self {
+        self.opt_level = opt_level;
+        self
+    }
+
+    /// Set the on-disk artifact cache.
+    pub fn disk_cache(mut self, disk_cache: DiskCache) -> Self {
+        self.disk_cache = Some(disk_cache);
+        self
+    }
+}
+
+/// The parallel block workers compile the code with the compile options, see
+/// [`dora_runtime::BlockExecutor::execute_block_parallel`].
+impl<DB: Database> WorkerHandler<DB> for CompileOptions {
+    fn handler<'w>(&self) -> Handler<'w, MvDatabase<'w, DB>>
+    where
+        DB: 'w,
+    {
+        compile_handler_with_options(self.clone())
+    }
+}
+
+/// Compile Handler for the VM, the on-disk artifact cache is enabled when the `DORA_CACHE_DIR`
+/// environment variable is set.
 #[inline]
 pub fn compile_handler<'a, DB: Database + 'a>() -> Handler<'a, DB> {
+    compile_handler_with_options(CompileOptions::from_env())
+}
+
+/// Compile Handler for the VM which compiles the code with the compile options.
+pub fn compile_handler_with_options<'a, DB: Database + 'a>(
+    opts: CompileOptions,
+) -> Handler<'a, DB> {
+    Handler {
+        call_handler: Arc::new(move |frame: Frame, ctx: &mut VMContext<'a, DB>| {
+            compile_call_handler(frame, ctx, &opts)
+        }),
+    }
+}
+
+/// Tiered Handler for the VM, the cold legacy EVM code is executed by the interpreter and the hot
+/// code is compiled in the background by the [`TieredCompiler`], see [`tiered`] for details.
+pub fn tiered_handler<'a, DB: Database + 'a>(compiler: TieredCompiler) -> Handler<'a, DB> {
     Handler {
-        call_handler: Arc::new(compile_call_handler),
+        call_handler: Arc::new(move |frame: Frame, ctx: &mut VMContext<'a, DB>| {
+            compiler.call(frame, ctx)
+        }),
     }
 }
 
@@ -69,6 +270,7 @@ pub fn compile_handler<'a, DB: Database + 'a>() -> Handler<'a, DB> {
 fn compile_call_handler<DB: Database>(
     frame: Frame,
     ctx: &mut VMContext<'_, DB>,
+    opts: &CompileOptions,
 ) -> Result<CallResult, VMError> {
     // When meets empty account code, just return the default call result.
     if frame.contract.code.is_empty() {
@@ -76,22 +278,47 @@ fn compile_call_handler<DB: Database>(
     }
     let code_hash = frame.contract.hash.unwrap_or_default();
     let spec_id = ctx.spec_id();
-    // When code hash is empty, we do not save the artifact
+    // When an inspector is attached, the code is compiled with the tracing calls, which is
+    // covered by the artifact key, thus the tracing artifacts never replace the normal ones.
+    let tracing = ctx.inspector.is_some();
+    let opt_level = opts.opt_level;
+    // When code hash is empty, we do not save the artifact.
     let artifact = if !code_hash.is_zero() {
-        let artifact = ctx.db.get_artifact(code_hash);
+        let key =
+            compile_artifact_key(&frame.contract.code, code_hash, spec_id, tracing, opt_level);
+        let artifact = ctx.db.get_artifact(&key);
         if let Ok(Some(artifact)) = artifact {
             artifact
         } else {
-            let artifact = build_artifact::<DB>(&frame.contract.code, ctx.spec_id())
-                .map_err(|e| VMError::Compile(e.to_string()))?;
-            ctx.db.set_artifact(code_hash, artifact.clone());
+            let artifact = match &opts.disk_cache {
+                Some(disk_cache) if !tracing => cached_artifact::<DB>(
+                    disk_cache,
+                    code_hash,
+                    &frame.contract.code,
+                    spec_id,
+                    opt_level,
+                ),
+                _ => compile_artifact::<DB>(&frame.contract.code, spec_id, tracing, opt_level),
+            }
+            .map_err(|e| VMError::Compile(e.to_string()))?;
+            ctx.db.set_artifact(key, artifact.clone());
             artifact
         }
     } else {
-        build_artifact::<DB>(&frame.contract.code, ctx.spec_id())
+        compile_artifact::<DB>(&frame.contract.code, spec_id, tracing, opt_level)
             .map_err(|e| VMError::Compile(e.to_string()))?
     };
-    let runtime_context = RuntimeContext::new(
+    execute_artifact(&artifact, frame, ctx)
+}
+
+/// Execute the compiled artifact of the frame.
+fn execute_artifact<DB: Database>(
+    artifact: &DB::Artifact,
+    frame: Frame,
+    ctx: &mut VMContext<'_, DB>,
+) -> Result<CallResult, VMError> {
+    let spec_id = ctx.spec_id();
+    let mut runtime_context = RuntimeContext::new(
         frame.contract,
         frame.depth,
         frame.is_static,
@@ -100,6 +327,7 @@ fn compile_call_handler<DB: Database>(
         spec_id,
         frame.gas_limit,
     );
+    runtime_context.inner.is_create = frame.is_create;
     artifact
         .execute(runtime_context)
         .map_err(|err| VMError::Handler(err.to_string()))
@@ -150,44 +378,168 @@ pub fn run_with_context<DB: Database>(
 }
 
 /// Build the EVM or WASM bytecode to the native artifact.
+#[inline]
 pub fn build_artifact<DB: Database>(
     code: &Bytecode,
     spec_id: SpecId,
+) -> anyhow::Result<DB::Artifact> {
+    build_artifact_with_options::<DB>(code, spec_id, &CompileOptions::default())
+}
+
+/// Build the EVM or WASM bytecode to the native artifact with the compile options. When the
+/// on-disk artifact cache is set, the artifact is loaded from the cache, or built and stored into
+/// the cache when it is missing.
+///
+/// Note that only the EVM artifacts are cached on disk, the WASM artifacts are always built.
+pub fn build_artifact_with_options<DB: Database>(
+    code: &Bytecode,
+    spec_id: SpecId,
+    opts: &CompileOptions,
+) -> anyhow::Result<DB::Artifact> {
+    match &opts.disk_cache {
+        Some(disk_cache) => {
+            cached_artifact::<DB>(disk_cache, code.hash_slow(), code, spec_id, opts.opt_level)
+        }
+        None => compile_artifact::<DB>(code, spec_id, false, opts.opt_level),
+    }
+}
+
+/// Returns the artifact cache key of the code compiled by [`build_artifact`], which covers the code
+/// hash, the spec ID, the compile options and the compiler version.
+#[inline]
+pub fn artifact_key(code: &Bytecode, code_hash: B256, spec_id: SpecId) -> ArtifactKey {
+    artifact_key_with_options(code, code_hash, spec_id, &CompileOptions::default())
+}
+
+/// Returns the artifact cache key of the code compiled by [`build_artifact_with_options`].
+#[inline]
+pub fn artifact_key_with_options(
+    code: &Bytecode,
+    code_hash: B256,
+    spec_id: SpecId,
+    opts: &CompileOptions,
+) -> ArtifactKey {
+    compile_artifact_key(code, code_hash, spec_id, false, opts.opt_level)
+}
+
+fn cached_artifact<DB: Database>(
+    disk_cache: &DiskCache,
+    code_hash: B256,
+    code: &Bytecode,
+    spec_id: SpecId,
+    opt_level: OptimizationLevel,
+) -> anyhow::Result<DB::Artifact> {
+    let key = compile_artifact_key(code, code_hash, spec_id, false, opt_level);
+    let Bytecode::EVM(code) = code else {
+        return compile_artifact::<DB>(code, spec_id, false, opt_level);
+    };
+    let executor = cached_executor(disk_cache, &key, code, spec_id, opt_level)?;
+    Ok(DB::Artifact::new(executor))
+}
+
+/// Loads the EVM executor of the key from the disk cache, or compiles the code and stores the
+/// executor into the disk cache.
+fn cached_executor(
+    disk_cache: &DiskCache,
+    key: &ArtifactKey,
+    code: &EVMBytecode,
+    spec_id: SpecId,
+    opt_level: OptimizationLevel,
+) -> anyhow::Result<Executor> {
+    // A broken cached artifact is treated as missing and will be replaced.
+    if let Ok(Some(executor)) = disk_cache.load(key) {
+        return Ok(executor);
+    }
+    let executor = build_evm_executor(code, evm_compile_options(spec_id, false, opt_level), true)?;
+    // The disk cache is best-effort, the artifact is still usable when it fails to be stored
+    // e.g., the linker is not available.
+    if let Err(err) = disk_cache.store(key, &executor) {
+        tracing::warn!(
+            "Failed to store the artifact into the disk cache {}: {err}",
+            disk_cache.dir().display()
+        );
+    }
+    Ok(executor)
+}
+
+/// Build the EVM or WASM bytecode to the native artifact, when `tracing` is `true`, the artifact
+/// reports every execution step to the attached inspector.
+fn compile_artifact<DB: Database>(
+    code: &Bytecode,
+    spec_id: SpecId,
+    tracing: bool,
+    opt_level: OptimizationLevel,
 ) -> anyhow::Result<DB::Artifact> {
     match code {
         Bytecode::EVM(code) => {
-            build_evm_artifact::<DB>(code, EVMCompileOptions::default().spec_id(spec_id))
+            build_evm_artifact::<DB>(code, evm_compile_options(spec_id, tracing, opt_level))
         }
-        Bytecode::WASM(code) => build_wasm_artifact::<DB>(code, WASMCompileOptions::default()),
+        Bytecode::WASM(code) => build_wasm_artifact::<DB>(
+            code,
+            WASMCompileOptions::default()
+                .tracing(tracing)
+                .opt_level(opt_level),
+        ),
     }
 }
 
+fn compile_artifact_key(
+    code: &Bytecode,
+    code_hash: B256,
+    spec_id: SpecId,
+    tracing: bool,
+    opt_level: OptimizationLevel,
+) -> ArtifactKey {
+    let options = match code {
+        Bytecode::EVM(_) => evm_compile_options(spec_id, tracing, opt_level).fingerprint(),
+        Bytecode::WASM(_) => WASMCompileOptions::default()
+            .tracing(tracing)
+            .opt_level(opt_level)
+            .fingerprint(),
+    };
+    ArtifactKey::new(code_hash, spec_id, options)
+}
+
+#[inline]
+fn evm_compile_options(
+    spec_id: SpecId,
+    tracing: bool,
+    opt_level: OptimizationLevel,
+) -> EVMCompileOptions {
+    let opts = EVMCompileOptions::default()
+        .spec_id(spec_id)
+        .opt_level(opt_level);
+    if tracing { opts.tracing(true) } else { opts }
+}
+
 /// Build the EVM bytecode to the artifact
 pub fn build_evm_artifact<DB: Database>(
     code: &EVMBytecode,
     opts: EVMCompileOptions,
 ) -> anyhow::Result<DB::Artifact> {
-    let spec_id = opts.spec_id;
+    Ok(DB::Artifact::new(build_evm_executor(code, opts, false)?))
+}
+
+/// Build the EVM bytecode to the executor, when `object_dump` is `true`, the native object code is
+/// kept and can be emitted as the AOT artifact, see [`Executor::emit_shared_library`].
+pub fn build_evm_executor(
+    code: &EVMBytecode,
+    opts: EVMCompileOptions,
+    object_dump: bool,
+) -> anyhow::Result<Executor> {
+    let opt_level = opts.opt_level;
     // Compile the contract code
     let program = Program::from_opcodes(code.bytecode(), code.eof().cloned());
     let context = Context::new();
     let compiler = EVMCompiler::new(&context, opts);
-    let mut module = compiler.compile(&program)?;
-    // Lowering the EVM dialect to MLIR builtin dialects.
-    evm::pass::run(&context.mlir_context, &mut module.mlir_module)?;
-    dora::pass::run(
-        &context.mlir_context,
-        &mut module.mlir_module,
-        &dora::pass::PassOptions {
-            code_size: program.code_size(),
-            spec_id,
-            ..Default::default()
-        },
-    )?;
-    pass::run(&context.mlir_context, &mut module.mlir_module)?;
+    let module = lower_evm_module(&compiler, &program, &mut |_, _| Ok(()))?;
     debug_assert!(module.mlir_module.as_operation().verify());
-    let executor = Executor::new(module.module(), Default::default(), ExecuteKind::EVM);
-    Ok(DB::Artifact::new(executor))
+    let executor = if object_dump {
+        Executor::new_with_object_dump(module.module(), opt_level, ExecuteKind::EVM)
+    } else {
+        Executor::new(module.module(), opt_level, ExecuteKind::EVM)
+    };
+    Ok(executor)
 }
 
 /// Build WASM opcode to the artifact
@@ -195,29 +547,78 @@ pub fn build_wasm_artifact<DB: Database>(
     code: &WASMBytecode,
     opts: WASMCompileOptions,
 ) -> anyhow::Result<DB::Artifact> {
+    let opt_level = opts.opt_level;
     let context = Context::new();
     let compiler = WASMCompiler::new(&context, opts);
+    let module = lower_wasm_module(&compiler, code, &mut |_, _| Ok(()))?;
+    let instance = compiler.build_instance(code)?;
+    debug_assert!(module.mlir_module.as_operation().verify());
+
+    let executor = Executor::new(module.module(), opt_level, ExecuteKind::new_wasm(instance));
+    Ok(DB::Artifact::new(executor))
+}
+
+/// The callback which receives the module of each compile stage, see [`lower_evm_module`].
+type StageInspector<'i, 'c> = dyn FnMut(EmitStage, &Module<'c>) -> anyhow::Result<()> + 'i;
+
+/// Compile the EVM program and lower it to the LLVM dialect module, `inspect` is called with the
+/// module of each compile stage.
+fn lower_evm_module<'c>(
+    compiler: &EVMCompiler<'c>,
+    program: &Program,
+    inspect: &mut StageInspector<'_, 'c>,
+) -> anyhow::Result<Module<'c>> {
+    let context = &compiler.ctx.mlir_context;
+    let mut module = compiler.compile(program)?;
+    inspect(EmitStage::Source, &module)?;
+    // Lowering the EVM dialect to the Dora dialect.
+    evm::pass::run(context, &mut module.mlir_module)?;
+    inspect(EmitStage::Dora, &module)?;
+    // Lowering the Dora dialect to MLIR builtin dialects.
+    dora::pass::run(
+        context,
+        &mut module.mlir_module,
+        &dora::pass::PassOptions {
+            code_size: program.code_size(),
+            spec_id: compiler.opts.spec_id,
+            ..Default::default()
+        },
+    )?;
+    inspect(EmitStage::Builtin, &module)?;
+    pass::run(context, &mut module.mlir_module, compiler.opts.opt_level)?;
+    inspect(EmitStage::Llvm, &module)?;
+    Ok(module)
+}
+
+/// Compile the WASM module and lower it to the LLVM dialect module, `inspect` is called with the
+/// module of each compile stage.
+fn lower_wasm_module<'c>(
+    compiler: &WASMCompiler<'c>,
+    code: &[u8],
+    inspect: &mut StageInspector<'_, 'c>,
+) -> anyhow::Result<Module<'c>> {
+    let context = &compiler.ctx.mlir_context;
     // Compile WASM Bytecode to MLIR WASM Dialect
     let mut module = compiler.compile(code)?;
-    let instance = compiler.build_instance(code)?;
+    inspect(EmitStage::Source, &module)?;
     // Lowering the WASM dialect to the Dora dialect.
-    wasm::pass::run(&context.mlir_context, &mut module.mlir_module)?;
+    wasm::pass::run(context, &mut module.mlir_module)?;
+    inspect(EmitStage::Dora, &module)?;
     // Lowering the Dora dialect to MLIR builtin dialects.
     dora::pass::run(
-        &context.mlir_context,
+        context,
         &mut module.mlir_module,
         &dora::pass::PassOptions {
             code_size: code.len() as u32,
             ..Default::default()
         },
     )?;
-    pass::run(&context.mlir_context, &mut module.mlir_module)?;
-    debug_assert!(module.mlir_module.as_operation().verify());
-
-    let executor = Executor::new(
-        module.module(),
-        Default::default(),
-        ExecuteKind::new_wasm(instance),
-    );
-    Ok(DB::Artifact::new(executor))
+    inspect(EmitStage::Builtin, &module)?;
+    pass::run(
+        context,
+        &mut module.mlir_module,
+        compiler.config().opt_level,
+    )?;
+    inspect(EmitStage::Llvm, &module)?;
+    Ok(module)
 }
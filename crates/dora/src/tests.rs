@@ -1,9 +1,24 @@
-use crate::run_bytecode_hex;
-use dora_primitives::spec::SpecId;
+use crate::{VM, VMContext, compile_handler, run_bytecode_hex};
+use dora_primitives::{Address, Bytecode, Bytes, Env, TxKind, U256, address, spec::SpecId};
+use dora_runtime::{BlockExecutor, Database, db::MemoryDB};
 
+mod access_list;
+mod block;
 mod bytecode;
+mod cache;
+mod cache_db;
+mod create;
+mod disk_db;
+mod emit;
+mod estimate;
+mod genesis;
+mod inspector;
 mod operations;
+mod overrides;
+mod parallel;
 mod results;
+mod tiered;
+mod trie;
 pub(crate) mod utils;
 mod wasm;
 
@@ -12,6 +27,51 @@ const ERC20_TRANSFER_BYTECODE_HEX: &str = "608060405234801561000f575f80fd5b50600
 
 const INIT_GAS: u64 = 1_000_000_000;
 
+/// Increments the storage slot 0 and returns the new value.
+const COUNTER_CODE_HEX: &str = "5f54600101805f555f5260205ff3";
+/// Stores the first word of the call data into the storage slot 0.
+const STORE_CODE_HEX: &str = "5f355f5500";
+/// The caller of the test transactions.
+const CALLER: Address = address!("0000000000000000000000000000000000002000");
+
+/// Decodes the hex code into the bytecode.
+fn bytecode(code_hex: &str) -> Bytecode {
+    Bytecode::new(Bytes::from(hex::decode(code_hex).unwrap()))
+}
+
+/// Creates the VM which calls the contract from [`CALLER`] with 100,000 gas.
+fn call_vm<DB: Database + 'static>(db: DB, to: Address) -> VM<'static, DB> {
+    let mut env = Env::default();
+    env.tx.caller = CALLER;
+    env.tx.gas_limit = 100_000;
+    env.tx.transact_to = TxKind::Call(to);
+    VM::new(VMContext::new(db, env, SpecId::CANCUN, compile_handler()))
+}
+
+/// Creates the block executor, the environment is set by the executed blocks.
+fn block_executor(db: MemoryDB) -> BlockExecutor<'static, MemoryDB> {
+    BlockExecutor::new(VM::new(VMContext::new(
+        db,
+        Env::default(),
+        SpecId::CANCUN,
+        compile_handler(),
+    )))
+}
+
+/// Executes the transaction without committing it and returns the output word.
+fn transact_word<DB: Database + 'static>(vm: &mut VM<'static, DB>) -> U256 {
+    let result = vm.transact().unwrap().result;
+    assert!(result.is_success(), "{result:?}");
+    U256::from_be_slice(result.output().unwrap())
+}
+
+/// Executes and commits the transaction and returns the output word.
+fn transact_commit_word<DB: Database + 'static>(vm: &mut VM<'static, DB>) -> U256 {
+    let result = vm.transact_commit().unwrap();
+    assert!(result.is_success(), "{result:?}");
+    U256::from_be_slice(result.output().unwrap())
+}
+
 #[test]
 fn test_counter() {
     let result = run_bytecode_hex(COUNTER_BYTECODE_HEX, "d09de08a", INIT_GAS, SpecId::CANCUN);
@@ -0,0 +1,60 @@
+use dora_primitives::{AccessListItem, Address, B256, Bytecode, Bytes, Env, TxKind, spec::SpecId};
+use dora_runtime::db::MemoryDB;
+
+use crate::{VM, VMContext, compile_handler};
+
+/// Loads the storage slot 1.
+const SLOAD_CODE_HEX: &str = "60015400";
+
+/// Calls the storage contract, reads the balance of the account and calls the identity
+/// precompile.
+fn caller_code(store: Address, account: Address) -> Bytecode {
+    let mut code = hex::decode("5f5f5f5f5f73").unwrap();
+    code.extend_from_slice(store.as_slice());
+    code.extend(hex::decode("5af15073").unwrap());
+    code.extend_from_slice(account.as_slice());
+    code.extend(hex::decode("31505f5f5f5f60045afa5000").unwrap());
+    Bytecode::new(Bytes::from(code))
+}
+
+#[test]
+fn create_access_list_of_accessed_accounts_and_slots() {
+    let contract = Address::left_padding_from(&[0x30, 0x00]);
+    let store = Address::left_padding_from(&[0x10, 0x00]);
+    let account = Address::left_padding_from(&[0x11, 0x00]);
+    let db = MemoryDB::new()
+        .with_contract(
+            store,
+            Bytecode::new(Bytes::from(hex::decode(SLOAD_CODE_HEX).unwrap())),
+        )
+        .with_contract(contract, caller_code(store, account));
+    let mut env = Env::default();
+    env.tx.caller = Address::left_padding_from(&[0x20, 0x00]);
+    env.tx.gas_limit = 1_000_000;
+    env.tx.transact_to = TxKind::Call(contract);
+    let mut vm = VM::new(VMContext::new(db, env, SpecId::CANCUN, compile_handler()));
+    let gas_used = vm.transact().unwrap().result.gas_used();
+
+    let result = vm.create_access_list().unwrap();
+    assert!(result.result.is_success(), "{:?}", result.result);
+    assert_eq!(
+        result.access_list.0,
+        vec![
+            AccessListItem {
+                address: store,
+                storage_keys: vec![B256::with_last_byte(1)],
+            },
+            AccessListItem {
+                address: account,
+                storage_keys: vec![],
+            },
+        ]
+    );
+    assert!(vm.env.tx.access_list.is_empty());
+    // Each warm account and slot saves 100 gas.
+    assert_eq!(result.gas_used, gas_used - 300);
+    assert_eq!(result.gas_used, result.result.gas_used());
+
+    vm.env.tx.access_list = result.access_list.0.clone();
+    assert_eq!(vm.create_access_list().unwrap(), result);
+}
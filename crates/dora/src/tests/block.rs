@@ -0,0 +1,155 @@
+use dora_primitives::{Address, B256, TxEnv, TxKind, U256};
+use dora_runtime::{
+    Block, BlockError, BlockTransaction, Database, Withdrawal,
+    block::{BEACON_ROOTS_ADDRESS, EIP2930_TX_TYPE, LEGACY_TX_TYPE, SYSTEM_ADDRESS},
+    db::MemoryDB,
+    trie::{StateTrie, state_root},
+};
+
+use super::{STORE_CODE_HEX, block_executor, bytecode};
+
+/// Emits an empty log.
+const LOG_CODE_HEX: &str = "5f5fa000";
+
+fn transaction(caller: Address, to: Address, value: u64) -> BlockTransaction {
+    BlockTransaction {
+        tx_type: LEGACY_TX_TYPE,
+        tx: TxEnv {
+            caller,
+            gas_limit: 100_000,
+            transact_to: TxKind::Call(to),
+            value: U256::from(value),
+            ..Default::default()
+        },
+    }
+}
+
+#[test]
+fn execute_block_with_system_calls_and_withdrawals() {
+    let caller = Address::left_padding_from(&[0x20, 0x00]);
+    let recipient = Address::left_padding_from(&[0x30, 0x00]);
+    let logger = Address::left_padding_from(&[0x40, 0x00]);
+    let validator = Address::left_padding_from(&[0x50, 0x00]);
+    let beacon_root = B256::repeat_byte(0x11);
+    let mut db = MemoryDB::new()
+        .with_contract(BEACON_ROOTS_ADDRESS, bytecode(STORE_CODE_HEX))
+        .with_contract(logger, bytecode(LOG_CODE_HEX));
+    db.set_balance(caller, U256::from(1_000));
+
+    let mut block = Block {
+        transactions: vec![
+            transaction(caller, recipient, 100),
+            transaction(caller, logger, 0),
+        ],
+        withdrawals: Some(vec![Withdrawal {
+            address: validator,
+            amount: 2,
+            ..Default::default()
+        }]),
+        parent_beacon_block_root: Some(beacon_root),
+        ..Default::default()
+    };
+    block.env.gas_limit = U256::from(1_000_000);
+    let state_trie = StateTrie::from_state(&db.clone().into_state());
+    let mut executor = block_executor(db).with_state_trie(state_trie);
+    let result = executor.execute_block(&block).unwrap();
+
+    assert_eq!(result.receipts.len(), 2);
+    let (transfer, log) = (&result.receipts[0], &result.receipts[1]);
+    assert!(transfer.status && log.status, "{:?}", result.results);
+    assert_eq!(transfer.gas_used, 21_000);
+    assert_eq!(transfer.cumulative_gas_used, 21_000);
+    assert_eq!(log.cumulative_gas_used, 21_000 + log.gas_used);
+    assert_eq!(result.gas_used, log.cumulative_gas_used);
+    assert!(transfer.logs.is_empty());
+    assert_eq!(log.logs.len(), 1);
+    assert_eq!(log.logs[0].address, logger);
+    assert_eq!(result.logs_bloom, log.logs_bloom);
+    assert_ne!(result.receipts_root, B256::ZERO);
+    assert_eq!(
+        result.state_root,
+        Some(state_root(&executor.vm().db.clone().into_state()))
+    );
+
+    let db = &executor.vm().db;
+    assert_eq!(
+        db.sload(BEACON_ROOTS_ADDRESS, U256::ZERO),
+        U256::from_be_bytes(beacon_root.0)
+    );
+    assert_eq!(db.get_balance(SYSTEM_ADDRESS), None);
+    assert_eq!(db.get_balance(recipient), Some(U256::from(100)));
+    assert_eq!(db.get_balance(caller), Some(U256::from(900)));
+    assert_eq!(
+        db.get_balance(validator),
+        Some(U256::from(2_000_000_000u64))
+    );
+}
+
+#[test]
+fn execute_block_enforces_gas_limit() {
+    let caller = Address::left_padding_from(&[0x20, 0x00]);
+    let recipient = Address::left_padding_from(&[0x30, 0x00]);
+    let mut block = Block {
+        transactions: vec![
+            transaction(caller, recipient, 0),
+            transaction(caller, recipient, 0),
+        ],
+        ..Default::default()
+    };
+    block.env.gas_limit = U256::from(150_000);
+    let result = block_executor(MemoryDB::new()).execute_block(&block);
+    assert_eq!(
+        result,
+        Err(BlockError::GasLimitExceeded {
+            index: 1,
+            gas_limit: 100_000,
+            gas_left: 129_000,
+        })
+    );
+}
+
+#[test]
+fn execute_block_keeps_explicit_transaction_type() {
+    let caller = Address::left_padding_from(&[0x20, 0x00]);
+    let recipient = Address::left_padding_from(&[0x30, 0x00]);
+    let mut db = MemoryDB::new();
+    db.set_balance(caller, U256::from(1_000));
+    // The EIP-2930 transaction with an empty access list.
+    let mut transaction = transaction(caller, recipient, 100);
+    transaction.tx_type = EIP2930_TX_TYPE;
+    let mut block = Block {
+        transactions: vec![transaction],
+        ..Default::default()
+    };
+    block.env.gas_limit = U256::from(1_000_000);
+    let result = block_executor(db).execute_block(&block).unwrap();
+    assert_eq!(result.receipts[0].tx_type, EIP2930_TX_TYPE);
+    assert_eq!(result.receipts[0].encoded()[0], EIP2930_TX_TYPE);
+}
+
+#[test]
+fn execute_transaction_skips_invalid_transaction() {
+    let caller = Address::left_padding_from(&[0x20, 0x00]);
+    let recipient = Address::left_padding_from(&[0x30, 0x00]);
+    let mut db = MemoryDB::new();
+    db.set_balance(caller, U256::from(1_000));
+    let mut executor = block_executor(db);
+    executor.vm_mut().env.block.gas_limit = U256::from(1_000_000);
+
+    let mut result = Default::default();
+    let invalid = transaction(caller, recipient, 10_000);
+    assert!(matches!(
+        executor.execute_transaction(0, &invalid, &mut result),
+        Err(BlockError::Transaction { index: 0, .. })
+    ));
+    assert!(result.receipts.is_empty());
+    executor
+        .execute_transaction(1, &transaction(caller, recipient, 100), &mut result)
+        .unwrap();
+    assert_eq!(result.receipts.len(), 1);
+    assert_eq!(result.receipts[0].cumulative_gas_used, 21_000);
+    assert_eq!(
+        executor.vm().db.get_balance(recipient),
+        Some(U256::from(100))
+    );
+}
@@ -0,0 +1,206 @@
+use dora_compiler::evm::program::Operation;
+use dora_primitives::{Address, B256, OptimizationLevel, spec::SpecId};
+use dora_runtime::artifact::SymbolArtifact;
+
+use crate::{
+    ArtifactCache, ArtifactKey, CacheLimit, CompileOptions, Database, DiskCache, VM, VMContext,
+    artifact_key, artifact_key_with_options, compile_handler, compile_handler_with_options,
+    tests::utils::default_env_and_db_setup,
+};
+
+#[test]
+fn disk_cache_reuses_shared_library_artifact() {
+    let dir = std::env::temp_dir().join(format!("dora-disk-cache-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let operations = vec![
+        Operation::Push((1_u8, 1_u8.into())),
+        Operation::Push((1_u8, 2_u8.into())),
+        Operation::Add,
+        Operation::Push0,
+        Operation::MStore,
+        Operation::Push((1_u8, 32_u8.into())),
+        Operation::Push0,
+        Operation::Return,
+    ];
+    let mut outputs = Vec::new();
+    // The first run compiles and stores the artifact, the second run loads it from the disk.
+    for _ in 0..2 {
+        let (env, db) = default_env_and_db_setup(operations.clone());
+        let disk_cache = DiskCache::new(&dir).unwrap();
+        let mut vm = VM::new(VMContext::new(
+            db,
+            env,
+            SpecId::CANCUN,
+            compile_handler_with_options(CompileOptions::default().disk_cache(disk_cache)),
+        ));
+        let result = vm.transact().unwrap();
+        assert!(result.result.is_success());
+        outputs.push((result.result.output().cloned(), result.result.gas_used()));
+    }
+    assert_eq!(outputs[0], outputs[1]);
+    let artifacts = std::fs::read_dir(&dir)
+        .unwrap()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "so"))
+        .count();
+    assert_eq!(artifacts, 1);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn disk_cache_keys_opt_level() {
+    let dir = std::env::temp_dir().join(format!("dora-disk-cache-opt-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let operations = vec![
+        Operation::Push((1_u8, 1_u8.into())),
+        Operation::Push((1_u8, 2_u8.into())),
+        Operation::Add,
+        Operation::Stop,
+    ];
+    for opt_level in [OptimizationLevel::None, OptimizationLevel::Aggressive] {
+        let (env, db) = default_env_and_db_setup(operations.clone());
+        let opts = CompileOptions::default()
+            .opt_level(opt_level)
+            .disk_cache(DiskCache::new(&dir).unwrap());
+        let mut vm = VM::new(VMContext::new(
+            db,
+            env,
+            SpecId::CANCUN,
+            compile_handler_with_options(opts),
+        ));
+        assert!(vm.transact().unwrap().result.is_success());
+    }
+    // The artifacts of the different optimization levels never collide in the disk cache.
+    let artifacts = std::fs::read_dir(&dir)
+        .unwrap()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "so"))
+        .count();
+    assert_eq!(artifacts, 2);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn artifact_key_covers_spec_and_options() {
+    let operations = vec![
+        Operation::Push((1_u8, 1_u8.into())),
+        Operation::Push((1_u8, 2_u8.into())),
+        Operation::Add,
+        Operation::Stop,
+    ];
+    let (env, db) = default_env_and_db_setup(operations);
+    let address = Address::left_padding_from(&[40]);
+    let mut vm = VM::new(VMContext::new(db, env, SpecId::CANCUN, compile_handler()));
+    assert!(vm.transact().unwrap().result.is_success());
+    let code_hash = vm.db.basic(address).unwrap().unwrap().code_hash;
+    let code = vm.db.code_by_hash(code_hash).unwrap();
+    let cancun_key = artifact_key(&code, code_hash, SpecId::CANCUN);
+    let shanghai_key = artifact_key(&code, code_hash, SpecId::SHANGHAI);
+    assert_ne!(cancun_key, shanghai_key);
+    assert_ne!(cancun_key.hash(), shanghai_key.hash());
+    assert!(vm.db.get_artifact(&cancun_key).unwrap().is_some());
+    // The artifact compiled for another spec is never hit across the hardfork boundary.
+    assert!(vm.db.get_artifact(&shanghai_key).unwrap().is_none());
+    let options_key = ArtifactKey::new(code_hash, SpecId::CANCUN, cancun_key.options ^ 1);
+    assert!(vm.db.get_artifact(&options_key).unwrap().is_none());
+}
+
+#[test]
+fn artifact_key_covers_opt_level() {
+    let operations = vec![
+        Operation::Push((1_u8, 1_u8.into())),
+        Operation::Push((1_u8, 2_u8.into())),
+        Operation::Add,
+        Operation::Push0,
+        Operation::MStore,
+        Operation::Push((1_u8, 32_u8.into())),
+        Operation::Push0,
+        Operation::Return,
+    ];
+    let address = Address::left_padding_from(&[40]);
+    let mut outputs = Vec::new();
+    for opt_level in [
+        OptimizationLevel::None,
+        OptimizationLevel::Less,
+        OptimizationLevel::Default,
+        OptimizationLevel::Aggressive,
+    ] {
+        let (env, db) = default_env_and_db_setup(operations.clone());
+        let opts = CompileOptions::default().opt_level(opt_level);
+        let handler = compile_handler_with_options(opts.clone());
+        let mut vm = VM::new(VMContext::new(db, env, SpecId::CANCUN, handler));
+        let result = vm.transact().unwrap().result;
+        assert!(result.is_success());
+        outputs.push(result.output().cloned());
+        let code_hash = vm.db.basic(address).unwrap().unwrap().code_hash;
+        let code = vm.db.code_by_hash(code_hash).unwrap();
+        let key = artifact_key_with_options(&code, code_hash, SpecId::CANCUN, &opts);
+        assert!(vm.db.get_artifact(&key).unwrap().is_some());
+        assert_eq!(
+            key == artifact_key(&code, code_hash, SpecId::CANCUN),
+            opt_level == OptimizationLevel::Default
+        );
+    }
+    // The optimization level never changes the execution result.
+    assert!(outputs.windows(2).all(|w| w[0] == w[1]));
+}
+
+#[test]
+fn artifact_cache_evicts_least_recently_used() {
+    let key = |i: u8| ArtifactKey::new(B256::repeat_byte(i), SpecId::CANCUN, 0);
+    let cache = ArtifactCache::<SymbolArtifact>::new(CacheLimit::entries(2));
+    cache.insert(key(1), SymbolArtifact::default());
+    cache.insert(key(2), SymbolArtifact::default());
+    // Touch the first artifact, thus the second one becomes the least recently used.
+    assert!(cache.get(&key(1)).is_some());
+    cache.insert(key(3), SymbolArtifact::default());
+    assert!(cache.contains(&key(1)));
+    assert!(!cache.contains(&key(2)));
+    assert!(cache.get(&key(2)).is_none());
+    let stats = cache.stats();
+    assert_eq!((stats.hits, stats.misses, stats.evictions), (1, 1, 1));
+    assert_eq!(stats.entries, 2);
+
+    // Lowering the limit evicts the least recently used artifacts until the limit fits.
+    cache.set_limit(CacheLimit::entries(1));
+    assert_eq!(cache.len(), 1);
+    assert!(cache.contains(&key(3)));
+    assert_eq!(cache.stats().evictions, 2);
+}
+
+#[test]
+fn artifact_cache_is_shared_by_databases() {
+    let operations = vec![
+        Operation::Push((1_u8, 1_u8.into())),
+        Operation::Push((1_u8, 2_u8.into())),
+        Operation::Add,
+        Operation::Stop,
+    ];
+    let cache = ArtifactCache::new(CacheLimit::entries(16));
+    for _ in 0..2 {
+        let (env, db) = default_env_and_db_setup(operations.clone());
+        let db = db.with_artifact_cache(cache.clone());
+        let mut vm = VM::new(VMContext::new(db, env, SpecId::CANCUN, compile_handler()));
+        assert!(vm.transact().unwrap().result.is_success());
+    }
+    // The second database hits the artifact compiled by the first one.
+    let stats = cache.stats();
+    assert_eq!(stats.entries, 1);
+    assert_eq!((stats.hits, stats.misses), (1, 1));
+}
+
+#[test]
+fn artifact_cache_is_shared_by_cloned_databases() {
+    let operations = vec![
+        Operation::Push((1_u8, 1_u8.into())),
+        Operation::Push((1_u8, 2_u8.into())),
+        Operation::Add,
+        Operation::Stop,
+    ];
+    let (env, db) = default_env_and_db_setup(operations);
+    let cloned = db.clone();
+    let mut vm = VM::new(VMContext::new(db, env, SpecId::CANCUN, compile_handler()));
+    assert!(vm.transact().unwrap().result.is_success());
+    // The artifact compiled with the database is visible to the clone.
+    assert_eq!(cloned.artifact_cache().len(), 1);
+}
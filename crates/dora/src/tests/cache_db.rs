@@ -0,0 +1,59 @@
+use dora_primitives::{Address, B256, U256};
+use dora_runtime::{CacheDB, Database, db::MemoryDB};
+
+use super::{CALLER, COUNTER_CODE_HEX, bytecode, call_vm, transact_commit_word};
+
+#[test]
+fn cache_db_absorbs_commits_until_flush() {
+    let counter = Address::left_padding_from(&[0x10, 0x00]);
+    let mut inner = MemoryDB::new().with_contract(counter, bytecode(COUNTER_CODE_HEX));
+    inner.sstore(counter, U256::ZERO, U256::from(5));
+    inner.insert_block_hash(U256::from(1), B256::repeat_byte(0x11));
+
+    let mut vm = call_vm(CacheDB::new(inner), counter);
+    assert_eq!(transact_commit_word(&mut vm), U256::from(6));
+    assert_eq!(transact_commit_word(&mut vm), U256::from(7));
+    assert_eq!(vm.db.block_hash(U256::from(1)), Ok(B256::repeat_byte(0x11)));
+
+    let db = &mut vm.db;
+    assert_eq!(db.inner().sload(counter, U256::ZERO), U256::from(5));
+    assert_eq!(db.storage(counter, U256::ZERO), Ok(U256::from(7)));
+    let slot = &db.diff().accounts[&counter].storage[&U256::ZERO];
+    assert_eq!(slot.original_value, U256::from(5));
+    assert_eq!(slot.present_value, U256::from(7));
+    assert_eq!(
+        db.diff().accounts[&CALLER]
+            .info
+            .as_ref()
+            .map(|info| info.nonce),
+        Some(2)
+    );
+
+    db.flush();
+    assert!(db.diff().is_empty());
+    assert_eq!(db.inner().sload(counter, U256::ZERO), U256::from(7));
+    assert_eq!(db.storage(counter, U256::ZERO), Ok(U256::from(7)));
+}
+
+#[test]
+fn layered_cache_db_keeps_lower_layer() {
+    let counter = Address::left_padding_from(&[0x10, 0x00]);
+    let inner = MemoryDB::new().with_contract(counter, bytecode(COUNTER_CODE_HEX));
+    let mut vm = call_vm(CacheDB::new(inner), counter);
+    assert_eq!(transact_commit_word(&mut vm), U256::from(1));
+
+    let mut layer = call_vm(CacheDB::new(vm.db.clone()), counter);
+    assert_eq!(transact_commit_word(&mut layer), U256::from(2));
+    assert_eq!(vm.db.storage(counter, U256::ZERO), Ok(U256::from(1)));
+
+    // Flushing the upper layer commits into the lower cache only.
+    layer.db.flush();
+    assert_eq!(
+        layer.db.inner().storage(counter, U256::ZERO),
+        Ok(U256::from(2))
+    );
+    assert_eq!(
+        layer.db.inner().inner().sload(counter, U256::ZERO),
+        U256::ZERO
+    );
+}
@@ -0,0 +1,107 @@
+use dora_primitives::{Address, B256, U256, spec::SpecId};
+use dora_runtime::{Database, DatabaseCommit, DiskDB};
+
+use super::{CALLER, COUNTER_CODE_HEX, bytecode, call_vm, transact_commit_word};
+use crate::{CompileOptions, DiskCache, artifact_key, compile_handler_with_options};
+
+fn increment(db: DiskDB, counter: Address) -> (DiskDB, U256) {
+    let mut vm = call_vm(db, counter);
+    let value = transact_commit_word(&mut vm);
+    (vm.into_context().db, value)
+}
+
+#[test]
+fn disk_db_resumes_committed_state() {
+    let path = std::env::temp_dir().join(format!("dora-disk-db-{}.redb", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let counter = Address::left_padding_from(&[0x10, 0x00]);
+    let destructed = Address::left_padding_from(&[0x11, 0x00]);
+    let code = bytecode(COUNTER_CODE_HEX);
+
+    {
+        let mut db = DiskDB::open(&path).unwrap();
+        db.insert_contract(counter, code.clone(), U256::from(7));
+        db.insert_block_hash(U256::from(1), B256::repeat_byte(0x11))
+            .unwrap();
+        let (db, value) = increment(db, counter);
+        assert_eq!(value, U256::from(1));
+        let (_, value) = increment(db, counter);
+        assert_eq!(value, U256::from(2));
+    }
+
+    let db = DiskDB::open(&path).unwrap();
+    assert_eq!(db.storage(counter, U256::ZERO).unwrap(), U256::from(2));
+    assert_eq!(db.code_by_address(counter).unwrap(), code);
+    assert_eq!(
+        db.block_hash(U256::from(1)).unwrap(),
+        B256::repeat_byte(0x11)
+    );
+    let info = db.basic(CALLER).unwrap().unwrap();
+    assert_eq!(info.nonce, 2);
+    let (db, value) = increment(db, counter);
+    assert_eq!(value, U256::from(3));
+
+    // The touched empty beneficiary is not stored.
+    let state = db.clone().into_state();
+    assert_eq!(state.len(), 2);
+    assert_eq!(state[&counter].info.balance, U256::from(7));
+    assert_eq!(
+        state[&counter].storage[&U256::ZERO].present_value,
+        U256::from(3)
+    );
+    assert_eq!(state[&CALLER].info.nonce, 3);
+
+    // The self-destructed account is removed with its storage.
+    let mut db = db;
+    db.set_account(
+        destructed,
+        1,
+        U256::ZERO,
+        [(U256::ZERO, U256::from(1))].into_iter().collect(),
+    );
+    let mut state = db.clone().into_state();
+    let mut account = state.remove(&destructed).unwrap();
+    assert_eq!(account.storage.len(), 1);
+    account.mark_selfdestruct();
+    account.mark_touch();
+    db.commit([(destructed, account)].into_iter().collect());
+    assert_eq!(db.basic(destructed).unwrap(), None);
+    assert_eq!(db.storage(destructed, U256::ZERO).unwrap(), U256::ZERO);
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn disk_db_writes_artifacts_through_to_disk_cache() {
+    let path = std::env::temp_dir().join(format!("dora-disk-db-cache-{}.redb", std::process::id()));
+    let dir = std::env::temp_dir().join(format!("dora-disk-db-cache-{}", std::process::id()));
+    let compile_dir =
+        std::env::temp_dir().join(format!("dora-disk-db-compile-cache-{}", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let _ = std::fs::remove_dir_all(&dir);
+    let _ = std::fs::remove_dir_all(&compile_dir);
+    let counter = Address::left_padding_from(&[0x10, 0x00]);
+    let code = bytecode(COUNTER_CODE_HEX);
+    let key = artifact_key(&code, code.hash_slow(), SpecId::CANCUN);
+
+    let mut db = DiskDB::open(&path)
+        .unwrap()
+        .with_disk_cache(DiskCache::new(&dir).unwrap());
+    db.insert_contract(counter, code, U256::ZERO);
+    // The handler stores the artifact into another disk cache, the database writes it through
+    // to its own disk cache.
+    let opts = CompileOptions::default().disk_cache(DiskCache::new(&compile_dir).unwrap());
+    let mut vm = call_vm(db, counter);
+    vm.context.handler = compile_handler_with_options(opts);
+    assert!(vm.transact_commit().unwrap().is_success());
+    drop(vm);
+    assert!(DiskCache::new(&dir).unwrap().contains(&key));
+
+    // The reopened database loads the artifact from the disk cache.
+    let db = DiskDB::open(&path)
+        .unwrap()
+        .with_disk_cache(DiskCache::new(&dir).unwrap());
+    assert!(db.get_artifact(&key).unwrap().is_some());
+    let _ = std::fs::remove_file(&path);
+    let _ = std::fs::remove_dir_all(&dir);
+    let _ = std::fs::remove_dir_all(&compile_dir);
+}
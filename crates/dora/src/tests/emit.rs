@@ -0,0 +1,44 @@
+use dora_primitives::{Bytecode, Bytes, OptimizationLevel, spec::SpecId};
+
+use super::COUNTER_BYTECODE_HEX;
+use crate::{EmitStage, compile_stages};
+
+#[test]
+fn compile_stages_emits_selected_stages_in_order() {
+    let code = Bytecode::new(Bytes::from(hex::decode(COUNTER_BYTECODE_HEX).unwrap()));
+    let stages = compile_stages(
+        &code,
+        SpecId::CANCUN,
+        OptimizationLevel::Default,
+        &EmitStage::ALL,
+    )
+    .unwrap();
+    let emitted: Vec<EmitStage> = stages.iter().map(|stage| stage.stage).collect();
+    assert_eq!(emitted, EmitStage::ALL);
+    let text =
+        |stage: EmitStage| String::from_utf8(stages[stage as usize].content.clone()).unwrap();
+    assert!(text(EmitStage::Source).contains("evm."));
+    assert!(text(EmitStage::Dora).contains("dora."));
+    assert!(!text(EmitStage::Builtin).contains("dora."));
+    assert!(text(EmitStage::Llvm).contains("llvm.func"));
+    assert!(text(EmitStage::LlvmIr).contains("define"));
+    assert!(!stages[EmitStage::Object as usize].content.is_empty());
+
+    let stages = compile_stages(
+        &code,
+        SpecId::CANCUN,
+        OptimizationLevel::None,
+        &[EmitStage::Dora],
+    )
+    .unwrap();
+    assert_eq!(stages.len(), 1);
+    assert_eq!(stages[0].stage, EmitStage::Dora);
+}
+
+#[test]
+fn emit_stage_parses_names() {
+    for stage in EmitStage::ALL {
+        assert_eq!(stage.name().parse::<EmitStage>(), Ok(stage));
+    }
+    assert!("asm".parse::<EmitStage>().is_err());
+}
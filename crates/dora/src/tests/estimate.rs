@@ -0,0 +1,84 @@
+use dora_primitives::{Address, Bytecode, Bytes, U256};
+use dora_runtime::db::MemoryDB;
+
+use super::{COUNTER_CODE_HEX, STORE_CODE_HEX, bytecode, call_vm};
+use crate::VM;
+
+/// Reverts with the word 0xaa.
+const REVERT_CODE_HEX: &str = "60aa5f5260205ffd";
+
+/// Calls the contract with all the gas and reverts if the call fails.
+fn proxy_code(callee: Address) -> Bytecode {
+    let mut code = hex::decode("5f5f5f5f5f73").unwrap();
+    code.extend_from_slice(callee.as_slice());
+    code.extend(hex::decode("5af115602157005b5f5ffd").unwrap());
+    Bytecode::new(Bytes::from(code))
+}
+
+fn vm(db: MemoryDB, to: Address) -> VM<'static, MemoryDB> {
+    let mut vm = call_vm(db, to);
+    vm.env.tx.gas_limit = 1_000_000;
+    vm
+}
+
+/// Asserts that the transaction succeeds with the estimated gas limit but not with less gas.
+fn assert_minimal_gas_limit(vm: &mut VM<'static, MemoryDB>, gas_limit: u64) {
+    vm.env.tx.gas_limit = gas_limit;
+    assert!(vm.transact().unwrap().result.is_success());
+    vm.env.tx.gas_limit = gas_limit - 1;
+    assert!(!vm.transact().is_ok_and(|output| output.result.is_success()));
+}
+
+#[test]
+fn estimate_gas_of_transfer() {
+    let recipient = Address::left_padding_from(&[0x30, 0x00]);
+    let mut vm = vm(MemoryDB::new(), recipient);
+    let estimate = vm.estimate_gas().unwrap();
+    assert!(estimate.is_success());
+    assert_eq!(estimate.gas_limit, 21_000);
+    assert_eq!(vm.env.tx.gas_limit, 1_000_000);
+}
+
+#[test]
+fn estimate_gas_with_call_gas_retention() {
+    let counter = Address::left_padding_from(&[0x10, 0x00]);
+    let proxy = Address::left_padding_from(&[0x11, 0x00]);
+    let db = MemoryDB::new()
+        .with_contract(counter, bytecode(COUNTER_CODE_HEX))
+        .with_contract(proxy, proxy_code(counter));
+    let mut vm = vm(db, proxy);
+    let estimate = vm.estimate_gas().unwrap();
+    assert!(estimate.is_success(), "{:?}", estimate.result);
+    // The proxy keeps 1/64 of the gas, which is not used.
+    assert!(estimate.gas_limit > estimate.result.gas_used());
+    assert_minimal_gas_limit(&mut vm, estimate.gas_limit);
+    assert_eq!(vm.db.sload(counter, U256::ZERO), U256::ZERO);
+}
+
+#[test]
+fn estimate_gas_with_refund() {
+    let contract = Address::left_padding_from(&[0x10, 0x00]);
+    let mut db = MemoryDB::new().with_contract(contract, bytecode(STORE_CODE_HEX));
+    db.sstore(contract, U256::ZERO, U256::from(1));
+    let mut vm = vm(db, contract);
+    vm.env.tx.data = Bytes::from(U256::ZERO.to_be_bytes::<32>());
+    let estimate = vm.estimate_gas().unwrap();
+    assert!(estimate.is_success(), "{:?}", estimate.result);
+    assert!(estimate.result.gas_refunded() > 0);
+    assert!(estimate.gas_limit > estimate.result.gas_used());
+    assert_minimal_gas_limit(&mut vm, estimate.gas_limit);
+}
+
+#[test]
+fn estimate_gas_returns_revert_data() {
+    let contract = Address::left_padding_from(&[0x10, 0x00]);
+    let db = MemoryDB::new().with_contract(contract, bytecode(REVERT_CODE_HEX));
+    let mut vm = vm(db, contract);
+    let estimate = vm.estimate_gas().unwrap();
+    assert!(!estimate.is_success());
+    assert_eq!(estimate.gas_limit, 1_000_000);
+    assert_eq!(
+        estimate.revert_data(),
+        Some(&Bytes::from(U256::from(0xaa).to_be_bytes::<32>()))
+    );
+}
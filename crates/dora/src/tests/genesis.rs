@@ -0,0 +1,52 @@
+use dora_primitives::{Address, B256, Env, TxKind, U256, spec::SpecId};
+use dora_runtime::genesis::Prestate;
+
+use crate::{VM, VMContext, compile_handler};
+
+#[test]
+fn prestate_run_and_dump_state() {
+    let json = r#"{
+        "alloc": {
+            "0x0000000000000000000000000000000000001000": {
+                "balance": "0x10",
+                "nonce": 1,
+                "code": "0x5f546001015f5500",
+                "storage": { "0x00": "0x05" }
+            },
+            "0x0000000000000000000000000000000000002000": {
+                "balance": "0xde0b6b3a7640000",
+                "nonce": "0x2"
+            }
+        },
+        "blockHashes": { "0x1": "0x1111111111111111111111111111111111111111111111111111111111111111" }
+    }"#;
+    let prestate = Prestate::from_json(json).unwrap();
+    assert_eq!(
+        prestate.block_hashes[&U256::from(1)],
+        B256::repeat_byte(0x11)
+    );
+    let contract = Address::left_padding_from(&[0x10, 0x00]);
+    let caller = Address::left_padding_from(&[0x20, 0x00]);
+    let mut env = Env::default();
+    env.tx.gas_limit = 100_000;
+    env.block.gas_limit = U256::from(100_000);
+    env.tx.caller = caller;
+    env.tx.transact_to = TxKind::Call(contract);
+    let mut vm = VM::new(VMContext::new(
+        prestate.into_db(),
+        env,
+        SpecId::CANCUN,
+        compile_handler(),
+    ));
+    let result = vm.transact_commit().unwrap();
+    assert!(result.is_success(), "{result:?}");
+
+    let alloc = vm.context.db.dump_state();
+    assert_eq!(alloc[&contract].storage[&U256::ZERO], U256::from(6));
+    assert_eq!(alloc[&contract].balance, U256::from(0x10));
+    assert_eq!(
+        alloc[&contract].code.as_ref(),
+        hex::decode("5f546001015f5500").unwrap()
+    );
+    assert_eq!(alloc[&caller].nonce, 3);
+}
@@ -0,0 +1,99 @@
+use dora_primitives::{Address, Bytes, U256, spec::SpecId};
+use dora_runtime::{
+    AccountOverride, BlockOverrides, Database, DatabaseCommit, OverlayDB, StateOverride,
+    db::MemoryDB,
+};
+
+use super::{CALLER, COUNTER_CODE_HEX, bytecode, call_vm, transact_word};
+use crate::artifact_key;
+
+/// Returns the block number, the timestamp, the base fee and the coinbase.
+const BLOCK_CODE_HEX: &str = "435f5242602052486040524160605260805ff3";
+
+#[test]
+fn overlay_overrides_storage_slots() {
+    let counter = Address::left_padding_from(&[0x10, 0x00]);
+    let mut db = MemoryDB::new().with_contract(counter, bytecode(COUNTER_CODE_HEX));
+    db.sstore(counter, U256::ZERO, U256::from(5));
+    db.sstore(counter, U256::from(1), U256::from(9));
+
+    let mut overrides = StateOverride::default();
+    overrides.insert(
+        counter,
+        AccountOverride {
+            state_diff: Some([(U256::ZERO, U256::from(41))].into_iter().collect()),
+            ..Default::default()
+        },
+    );
+    let mut vm = call_vm(OverlayDB::new(db.clone(), overrides), counter);
+    assert_eq!(transact_word(&mut vm), U256::from(42));
+    assert_eq!(vm.db.storage(counter, U256::from(1)), Ok(U256::from(9)));
+
+    // The committed state is kept by the overlay only.
+    let state = vm.transact().unwrap().state;
+    vm.db.commit(state);
+    assert_eq!(transact_word(&mut vm), U256::from(43));
+    assert_eq!(vm.db.inner().sload(counter, U256::ZERO), U256::from(5));
+
+    let mut overrides = StateOverride::default();
+    overrides.insert(
+        counter,
+        AccountOverride {
+            state: Some([(U256::from(2), U256::from(3))].into_iter().collect()),
+            ..Default::default()
+        },
+    );
+    let mut vm = call_vm(OverlayDB::new(db, overrides), counter);
+    assert_eq!(transact_word(&mut vm), U256::from(1));
+    assert_eq!(vm.db.storage(counter, U256::from(1)), Ok(U256::ZERO));
+    assert_eq!(vm.db.storage(counter, U256::from(2)), Ok(U256::from(3)));
+}
+
+#[test]
+fn overlay_overrides_account_and_block() {
+    let contract = Address::left_padding_from(&[0x10, 0x00]);
+    let coinbase = Address::left_padding_from(&[0x50, 0x00]);
+    let mut overrides = StateOverride::default();
+    overrides.insert(
+        contract,
+        AccountOverride {
+            code: Some(Bytes::from(hex::decode(BLOCK_CODE_HEX).unwrap())),
+            nonce: Some(7),
+            ..Default::default()
+        },
+    );
+    overrides.insert(
+        CALLER,
+        AccountOverride {
+            balance: Some(U256::from(1_000_000)),
+            ..Default::default()
+        },
+    );
+    let db = OverlayDB::new(MemoryDB::new(), overrides);
+    assert_eq!(db.basic(contract).unwrap().map(|info| info.nonce), Some(7));
+    assert_eq!(db.inner().get_balance(CALLER), None);
+
+    let mut vm = call_vm(db, contract);
+    vm.env.tx.gas_price = U256::from(2);
+    BlockOverrides {
+        number: Some(U256::from(100)),
+        timestamp: Some(U256::from(1_700_000_000)),
+        basefee: Some(U256::from(1)),
+        coinbase: Some(coinbase),
+    }
+    .apply(&mut vm.env);
+    let result = vm.transact().unwrap().result;
+    assert!(result.is_success(), "{result:?}");
+    let output = result.output().unwrap();
+    let word = |index: usize| U256::from_be_slice(&output[index * 32..(index + 1) * 32]);
+    assert_eq!(word(0), U256::from(100));
+    assert_eq!(word(1), U256::from(1_700_000_000));
+    assert_eq!(word(2), U256::from(1));
+    assert_eq!(word(3), U256::from_be_slice(coinbase.as_slice()));
+
+    // The artifact compiled for the overridden code is kept by the overlay only.
+    let code = bytecode(BLOCK_CODE_HEX);
+    let key = artifact_key(&code, code.hash_slow(), SpecId::CANCUN);
+    assert!(vm.db.get_artifact(&key).unwrap().is_some());
+    assert!(vm.db.inner().get_artifact(&key).unwrap().is_none());
+}
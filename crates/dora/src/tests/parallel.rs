@@ -0,0 +1,79 @@
+use dora_primitives::{Address, TxEnv, TxKind, U256};
+use dora_runtime::{Block, BlockTransaction, block::LEGACY_TX_TYPE, db::MemoryDB};
+
+use super::{COUNTER_CODE_HEX, block_executor, bytecode};
+use crate::CompileOptions;
+
+fn transaction(caller: Address, nonce: u64, to: Address, value: u64) -> BlockTransaction {
+    BlockTransaction {
+        tx_type: LEGACY_TX_TYPE,
+        tx: TxEnv {
+            caller,
+            nonce: Some(nonce),
+            gas_limit: 100_000,
+            gas_price: U256::from(2),
+            transact_to: TxKind::Call(to),
+            value: U256::from(value),
+            ..Default::default()
+        },
+    }
+}
+
+#[test]
+fn execute_block_parallel_matches_sequential_execution() {
+    let counter = Address::left_padding_from(&[0x10, 0x00]);
+    let coinbase = Address::left_padding_from(&[0x50, 0x00]);
+    let senders: Vec<Address> = (1..=8u8)
+        .map(|i| Address::left_padding_from(&[0x20, i]))
+        .collect();
+    let mut db = MemoryDB::new().with_contract(counter, bytecode(COUNTER_CODE_HEX));
+    for sender in &senders {
+        db.set_balance(*sender, U256::from(1_000_000_000u64));
+    }
+
+    let mut transactions = Vec::new();
+    for (i, sender) in senders.iter().enumerate() {
+        // Independent transfers, the counter calls conflicting on the same slot and the
+        // transfers of the received value.
+        let recipient = Address::left_padding_from(&[0x30, i as u8]);
+        transactions.push(transaction(*sender, 0, recipient, 100));
+        transactions.push(transaction(*sender, 1, counter, 0));
+        transactions.push(transaction(
+            *sender,
+            2,
+            senders[(i + 1) % senders.len()],
+            10,
+        ));
+    }
+    transactions.push(transaction(senders[0], 3, coinbase, 1_000));
+    let mut block = Block {
+        transactions,
+        ..Default::default()
+    };
+    block.env.coinbase = coinbase;
+    block.env.gas_limit = U256::from(30_000_000);
+
+    let mut sequential = block_executor(db.clone());
+    let expected = sequential.execute_block(&block).unwrap();
+    let mut parallel = block_executor(db);
+    let result = parallel
+        .execute_block_parallel(&block, CompileOptions::from_env(), 4)
+        .unwrap();
+
+    assert!(
+        result.receipts.iter().all(|receipt| receipt.status),
+        "{:?}",
+        result.results
+    );
+    assert_eq!(result, expected);
+    let (sequential_db, parallel_db) = (&sequential.vm().db, &parallel.vm().db);
+    assert_eq!(parallel_db.dump_state(), sequential_db.dump_state());
+    assert_eq!(
+        parallel_db.sload(counter, U256::ZERO),
+        U256::from(senders.len())
+    );
+    assert_eq!(
+        parallel_db.get_balance(coinbase),
+        Some(U256::from(1_000 + 2 * result.gas_used))
+    );
+}
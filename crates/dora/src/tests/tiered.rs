@@ -0,0 +1,123 @@
+use dora_primitives::{Bytecode, Bytes, Bytes32, Env, TxKind, spec::SpecId};
+use dora_runtime::artifact::SymbolArtifact;
+
+use super::{COUNTER_BYTECODE_HEX, INIT_GAS};
+use crate::{
+    ArtifactCache, CacheLimit, CompileOptions, MemoryDB, TieredCompiler, TieredStats, VM,
+    VMContext, compile_handler, tiered_handler,
+};
+
+fn counter_env_and_db() -> (Env, MemoryDB) {
+    let address = Bytes32::from(40_u32).to_address();
+    let mut env = Env::default();
+    env.tx.transact_to = TxKind::Call(address);
+    env.tx.gas_limit = INIT_GAS;
+    // increment()
+    env.tx.data = Bytes::from(hex::decode("d09de08a").unwrap());
+    env.tx.caller = Bytes32::from(10000_u32).to_address();
+    let code = Bytecode::new(Bytes::from(hex::decode(COUNTER_BYTECODE_HEX).unwrap()));
+    (env, MemoryDB::new().with_contract(address, code))
+}
+
+#[test]
+fn tiered_handler_interprets_cold_code_and_compiles_hot_code() {
+    let (env, db) = counter_env_and_db();
+    let expected = VM::new(VMContext::new(db, env, SpecId::CANCUN, compile_handler()))
+        .transact()
+        .unwrap();
+    assert!(expected.result.is_success());
+
+    let compiler = TieredCompiler::new(2, CompileOptions::default());
+    let cache = ArtifactCache::default();
+    let run = || {
+        let (env, db) = counter_env_and_db();
+        let db = db.with_artifact_cache(cache.clone());
+        let mut vm = VM::new(VMContext::new(
+            db,
+            env,
+            SpecId::CANCUN,
+            tiered_handler(compiler.clone()),
+        ));
+        assert_eq!(vm.transact().unwrap(), expected);
+    };
+    // The cold code is interpreted, and the second call crosses the threshold.
+    run();
+    run();
+    compiler.wait_idle();
+    // The compiled artifact replaces the interpreter transparently.
+    run();
+    run();
+    assert_eq!(
+        compiler.stats(),
+        TieredStats {
+            interpreted: 2,
+            compiled: 2,
+            compilations: 1,
+            failures: 0,
+        }
+    );
+    assert_eq!(cache.len(), 1);
+}
+
+#[test]
+fn tiered_handler_shares_compiled_code_across_databases() {
+    let compiler = TieredCompiler::new(1, CompileOptions::default());
+    let run = |cache: &ArtifactCache<SymbolArtifact>| {
+        let (env, db) = counter_env_and_db();
+        let db = db.with_artifact_cache(cache.clone());
+        let mut vm = VM::new(VMContext::new(
+            db,
+            env,
+            SpecId::CANCUN,
+            tiered_handler(compiler.clone()),
+        ));
+        assert!(vm.transact().unwrap().result.is_success());
+    };
+    run(&ArtifactCache::default());
+    compiler.wait_idle();
+    // The databases with separate artifact caches both execute the compiled code.
+    let (first, second) = (ArtifactCache::default(), ArtifactCache::default());
+    run(&first);
+    run(&second);
+    assert_eq!(
+        compiler.stats(),
+        TieredStats {
+            interpreted: 1,
+            compiled: 2,
+            compilations: 1,
+            failures: 0,
+        }
+    );
+    assert_eq!((first.len(), second.len()), (1, 1));
+}
+
+#[test]
+fn tiered_handler_compiles_evicted_code_again() {
+    let compiler =
+        TieredCompiler::new(1, CompileOptions::default()).cache_limit(CacheLimit::entries(0));
+    let run = || {
+        let (env, db) = counter_env_and_db();
+        let db = db.with_artifact_cache(ArtifactCache::default());
+        let mut vm = VM::new(VMContext::new(
+            db,
+            env,
+            SpecId::CANCUN,
+            tiered_handler(compiler.clone()),
+        ));
+        assert!(vm.transact().unwrap().result.is_success());
+    };
+    run();
+    compiler.wait_idle();
+    // The compiled code is evicted at once, thus the code is interpreted and compiled again.
+    run();
+    compiler.wait_idle();
+    assert_eq!(
+        compiler.stats(),
+        TieredStats {
+            interpreted: 2,
+            compiled: 0,
+            compilations: 2,
+            failures: 0,
+        }
+    );
+}
@@ -0,0 +1,108 @@
+use dora_primitives::{Address, B256, Bytes, U256, b256};
+use dora_runtime::{
+    Database, DatabaseCommit, Receipt, ResultAndState,
+    db::MemoryDB,
+    trie::{MerkleTrie, StateTrie, ordered_trie_root, state_root},
+};
+
+use super::{CALLER, STORE_CODE_HEX, bytecode, call_vm};
+
+/// The root of the empty trie.
+const EMPTY_ROOT_HASH: B256 =
+    b256!("56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421");
+
+#[test]
+fn state_trie_commit_matches_full_state_root() {
+    let contract = Address::left_padding_from(&[0x30, 0x00]);
+    let mut db = MemoryDB::new().with_contract(contract, bytecode(STORE_CODE_HEX));
+    db.set_balance(CALLER, U256::from(1_000));
+    let mut trie = StateTrie::from_state(&db.clone().into_state());
+    assert_eq!(trie.root(), state_root(&db.clone().into_state()));
+    assert_eq!(trie.storage_root(&contract), Some(EMPTY_ROOT_HASH));
+
+    let mut vm = call_vm(db, contract);
+    let mut storage_roots = Vec::new();
+    for value in [7u64, 0] {
+        vm.env.tx.data = Bytes::from(U256::from(value).to_be_bytes::<32>());
+        vm.env.tx.value = U256::from(value);
+        let ResultAndState { result, state } = vm.transact().unwrap();
+        assert!(result.is_success(), "{result:?}");
+        trie.commit(&state);
+        vm.db.commit(state);
+        assert_eq!(trie.root(), state_root(&vm.db.clone().into_state()));
+        storage_roots.push(trie.storage_root(&contract).unwrap());
+    }
+    assert_ne!(storage_roots[0], EMPTY_ROOT_HASH);
+    assert_eq!(storage_roots[1], EMPTY_ROOT_HASH);
+    assert_eq!(
+        trie.account(&contract).map(|leaf| leaf.balance),
+        Some(U256::from(7))
+    );
+}
+
+#[test]
+fn empty_state_root() {
+    let db = MemoryDB::new();
+    assert_eq!(state_root(&db.into_state()), EMPTY_ROOT_HASH);
+    assert_eq!(StateTrie::new().root(), EMPTY_ROOT_HASH);
+}
+
+#[test]
+fn merkle_trie_known_roots() {
+    let mut trie = MerkleTrie::new();
+    for (key, value) in [
+        ("doe", "reindeer"),
+        ("dog", "puppy"),
+        ("dogglesworth", "cat"),
+    ] {
+        trie.insert(key.as_bytes(), value.as_bytes().to_vec());
+    }
+    assert_eq!(
+        trie.root(),
+        b256!("8aad789dff2f538bca5d8ea56e8abe10f4c7ba3a5dea95fea4cd6e7c3a1168d3")
+    );
+    trie.remove(b"dogglesworth");
+    assert_eq!(
+        trie.root(),
+        b256!("05ae693aac2107336a79309e0c60b24a7aac6aa3edecaef593921500d33c63c4")
+    );
+
+    // The key `do` is the prefix of the other keys and stored as the branch value.
+    let mut trie = MerkleTrie::new();
+    for (key, value) in [
+        ("do", "verb"),
+        ("dog", "puppy"),
+        ("doge", "coin"),
+        ("horse", "stallion"),
+    ] {
+        trie.insert(key.as_bytes(), value.as_bytes().to_vec());
+    }
+    assert_eq!(
+        trie.root(),
+        b256!("5991bb8c6514148a29db676a14ac506cd2cd5775ace63c30a4fe457715e9ac84")
+    );
+    trie.remove(b"do");
+    assert_eq!(
+        trie.root(),
+        b256!("72543939c0b0dbc3bb86f81f14b9b7e7ea80eac1613ad59820b6d692ce1764d3")
+    );
+    for key in ["dog", "doge", "horse", "missing"] {
+        trie.remove(key.as_bytes());
+    }
+    assert!(trie.is_empty());
+    assert_eq!(trie.root(), EMPTY_ROOT_HASH);
+}
+
+#[test]
+fn receipts_root_of_transfer() {
+    let receipt = Receipt {
+        status: true,
+        cumulative_gas_used: 21_000,
+        gas_used: 21_000,
+        ..Default::default()
+    };
+    assert_eq!(
+        ordered_trie_root([receipt.encoded()]),
+        b256!("056b23fbba480696b65fe5a59b8f2148a1299103c4f57df839233af2cf4ca2d2")
+    );
+}
@@ -0,0 +1,250 @@
+//! Tiered execution, the cold code is executed by the interpreter, and the hot code which is
+//! called more than the threshold times is compiled on a background thread. Once the compiled
+//! artifact is ready, it replaces the interpreter for the later calls transparently.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, mpsc};
+
+use dora_primitives::{Bytecode, EVMBytecode, SpecId};
+use dora_runtime::RUNTIME_STACK_SIZE;
+use dora_runtime::artifact::SymbolArtifact;
+use dora_runtime::interpreter;
+use parking_lot::{Condvar, Mutex, MutexGuard};
+
+use crate::{
+    Artifact, ArtifactCache, ArtifactKey, CacheLimit, CallResult, CompileOptions, Database, Frame,
+    VMContext, VMError, artifact_key_with_options, build_evm_executor, cached_executor,
+    compile_call_handler, evm_compile_options, execute_artifact,
+};
+
+/// The default number of the calls after which the code is compiled.
+pub const DEFAULT_COMPILE_THRESHOLD: u64 = 16;
+
+/// The default number of the compiled artifacts kept by the [`TieredCompiler`].
+pub const DEFAULT_COMPILED_ENTRIES: usize = 1024;
+
+/// The statistics of the [`TieredCompiler`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TieredStats {
+    /// The number of the frames executed by the interpreter.
+    pub interpreted: u64,
+    /// The number of the frames executed by the compiled artifacts.
+    pub compiled: u64,
+    /// The number of the finished background compilations.
+    pub compilations: u64,
+    /// The number of the failed background compilations, the code is always interpreted.
+    pub failures: u64,
+}
+
+#[derive(Debug)]
+struct CompileJob {
+    key: ArtifactKey,
+    code: EVMBytecode,
+    spec_id: SpecId,
+}
+
+#[derive(Debug, Default)]
+struct TieredState {
+    /// The call counts of the interpreted code.
+    counts: HashMap<ArtifactKey, u64>,
+    /// The code which is being compiled in the background.
+    pending: HashSet<ArtifactKey>,
+    /// The code which fails to be compiled.
+    failed: HashSet<ArtifactKey>,
+    stats: TieredStats,
+}
+
+/// The tiered compiler, which tracks the call counts per code and compiles the hot code on a
+/// background thread, see [`crate::tiered_handler`].
+///
+/// Only the legacy EVM bytecode is interpreted. The EOF and WASM code, and the code executed with
+/// an inspector attached are always compiled before the execution, which is the same as the
+/// [`crate::compile_handler_with_options`]. The compiled code is kept in a bounded artifact cache
+/// of [`DEFAULT_COMPILED_ENTRIES`] entries, see [`TieredCompiler::cache_limit`]. The compiler can
+/// be cloned and shared across VMs and threads, and the background thread exits when all the
+/// clones are dropped.
+///
+/// # Example
+///
+/// ```ignore
+/// let compiler = TieredCompiler::new(16, CompileOptions::from_env());
+/// let db = MemoryDB::new().with_artifact_cache(cache.clone());
+/// let mut vm = VM::new(VMContext::new(db, env, spec_id, tiered_handler(compiler.clone())));
+/// vm.transact_commit()?;
+/// println!("{:?}", compiler.stats());
+/// ```
+#[derive(Debug, Clone)]
+pub struct TieredCompiler {
+    threshold: u64,
+    opts: CompileOptions,
+    state: Arc<(Mutex<TieredState>, Condvar)>,
+    /// The compiled code, which is shared by all the databases executing the code.
+    ready: ArtifactCache<SymbolArtifact>,
+    jobs: mpsc::Sender<CompileJob>,
+}
+
+impl Default for TieredCompiler {
+    fn default() -> Self {
+        Self::new(DEFAULT_COMPILE_THRESHOLD, CompileOptions::from_env())
+    }
+}
+
+impl TieredCompiler {
+    /// Creates a tiered compiler, the code is compiled in the background with the compile options
+    /// once it has been called `threshold` times.
+    pub fn new(threshold: u64, opts: CompileOptions) -> Self {
+        let state = Arc::new((Mutex::new(TieredState::default()), Condvar::new()));
+        let ready = ArtifactCache::new(CacheLimit::entries(DEFAULT_COMPILED_ENTRIES));
+        let (jobs, receiver) = mpsc::channel();
+        let worker = Worker {
+            state: Arc::clone(&state),
+            ready: ready.clone(),
+            opts: opts.clone(),
+        };
+        std::thread::Builder::new()
+            .name("dora-tiered-compiler".to_string())
+            // The compile pipeline recurses over the code, thus use the same stack size as the
+            // runtime.
+            .stack_size(RUNTIME_STACK_SIZE)
+            .spawn(move || worker.run(receiver))
+            .expect("failed to spawn the tiered compiler thread");
+        Self {
+            threshold,
+            opts,
+            state,
+            ready,
+            jobs,
+        }
+    }
+
+    /// Set the size limit of the compiled code kept by the compiler, which is shared by all the
+    /// clones. The evicted code is interpreted and compiled again once it becomes hot.
+    pub fn cache_limit(self, limit: CacheLimit) -> Self {
+        self.ready.set_limit(limit);
+        self
+    }
+
+    /// Returns the number of the calls after which the code is compiled.
+    #[inline]
+    pub fn threshold(&self) -> u64 {
+        self.threshold
+    }
+
+    /// Returns the tiered execution statistics.
+    pub fn stats(&self) -> TieredStats {
+        self.lock().stats
+    }
+
+    /// Blocks until all the pending background compilations are finished.
+    pub fn wait_idle(&self) {
+        let (_, idle) = &*self.state;
+        let mut state = self.lock();
+        while !state.pending.is_empty() {
+            idle.wait(&mut state);
+        }
+    }
+
+    /// Executes the frame with the compiled artifact if it is available, otherwise executes the
+    /// frame with the interpreter and records the call.
+    pub(crate) fn call<DB: Database>(
+        &self,
+        frame: Frame,
+        ctx: &mut VMContext<'_, DB>,
+    ) -> Result<CallResult, VMError> {
+        let code_hash = frame.contract.hash.unwrap_or_default();
+        if frame.contract.code.is_empty()
+            || code_hash.is_zero()
+            || ctx.inspector.is_some()
+            || !interpreter::is_interpretable(&frame.contract.code)
+        {
+            return compile_call_handler(frame, ctx, &self.opts);
+        }
+        let spec_id = ctx.spec_id();
+        let key = artifact_key_with_options(&frame.contract.code, code_hash, spec_id, &self.opts);
+        // The compiled code is kept by the compiler, thus every database executing the code gets
+        // the artifact rather than only the first one.
+        let artifact = match ctx.db.get_artifact(&key) {
+            Ok(Some(artifact)) => Some(artifact),
+            _ => self.ready.get(&key).map(|compiled| {
+                let artifact = DB::Artifact::new(compiled.executor().clone());
+                ctx.db.set_artifact(key, artifact.clone());
+                artifact
+            }),
+        };
+        if let Some(artifact) = artifact {
+            self.lock().stats.compiled += 1;
+            return execute_artifact(&artifact, frame, ctx);
+        }
+        self.record_call(key, &frame.contract.code, spec_id);
+        interpreter::execute_frame(frame, spec_id, ctx)
+    }
+
+    fn record_call(&self, key: ArtifactKey, code: &Bytecode, spec_id: SpecId) {
+        let mut state = self.lock();
+        state.stats.interpreted += 1;
+        let count = state.counts.entry(key).or_default();
+        *count += 1;
+        let count = *count;
+        if count < self.threshold || state.pending.contains(&key) || state.failed.contains(&key) {
+            return;
+        }
+        let Bytecode::EVM(code) = code else {
+            return;
+        };
+        state.pending.insert(key);
+        let job = CompileJob {
+            key,
+            code: code.clone(),
+            spec_id,
+        };
+        if self.jobs.send(job).is_err() {
+            state.pending.remove(&key);
+        }
+    }
+
+    #[inline]
+    fn lock(&self) -> MutexGuard<'_, TieredState> {
+        self.state.0.lock()
+    }
+}
+
+/// The background thread of the [`TieredCompiler`].
+struct Worker {
+    state: Arc<(Mutex<TieredState>, Condvar)>,
+    ready: ArtifactCache<SymbolArtifact>,
+    opts: CompileOptions,
+}
+
+impl Worker {
+    fn run(self, jobs: mpsc::Receiver<CompileJob>) {
+        let (state, idle) = &*self.state;
+        for job in jobs {
+            let opt_level = self.opts.opt_level;
+            let result = match &self.opts.disk_cache {
+                Some(disk_cache) => {
+                    cached_executor(disk_cache, &job.key, &job.code, job.spec_id, opt_level)
+                }
+                None => build_evm_executor(
+                    &job.code,
+                    evm_compile_options(job.spec_id, false, opt_level),
+                    false,
+                ),
+            };
+            let mut state = state.lock();
+            state.pending.remove(&job.key);
+            match result {
+                Ok(executor) => {
+                    // The compiled code is not interpreted again, thus the call count is dropped.
+                    state.counts.remove(&job.key);
+                    self.ready.insert(job.key, SymbolArtifact::new(executor));
+                    state.stats.compilations += 1;
+                }
+                Err(_) => {
+                    state.failed.insert(job.key);
+                    state.stats.failures += 1;
+                }
+            }
+            idle.notify_all();
+        }
+    }
+}
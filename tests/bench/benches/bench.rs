@@ -1,6 +1,9 @@
 #![allow(missing_docs)]
 
-use ::dora::{Artifact, WASMCompiler, build_artifact, run};
+use ::dora::{
+    Artifact, CompileOptions, VM, VMContext, WASMCompiler, artifact_key, build_artifact,
+    compile_handler, run,
+};
 use criterion::{
     BenchmarkGroup, Criterion, criterion_group, criterion_main, measurement::WallTime,
 };
@@ -13,19 +16,23 @@ use dora_compiler::{Compiler, Context, EVMCompiler, dora, evm, pass, wasm};
 use dora_primitives::{
     Address, B256, Bytecode, Env, TxEnv, TxKind, U256, WASM_MAGIC_BYTES, address, fixed_bytes, uint,
 };
-use dora_primitives::{Bytes, spec::SpecId};
+use dora_primitives::{Bytes, OptimizationLevel, spec::SpecId};
 use dora_runtime::artifact::SymbolArtifact;
+use dora_runtime::block::LEGACY_TX_TYPE;
 use dora_runtime::constants::env::DORA_DISABLE_CONSOLE;
 use dora_runtime::context::{Contract, RuntimeContext};
-use dora_runtime::db::{Database, MemoryDB};
+use dora_runtime::db::{Database, DbAccount, MemoryDB};
 use dora_runtime::executor::{ExecuteKind, Executor};
 use dora_runtime::host::DummyHost;
 use dora_runtime::stack::Stack;
+use dora_runtime::{Block, BlockExecutor, BlockTransaction};
 use rustc_hash::FxHashMap;
 use std::hint::black_box;
+use std::thread;
 use std::time::Duration;
 
 const UNISWAP_V3_ESTIMATE_GAS: u64 = 155934;
+const BLOCK_TX_COUNT: usize = 200;
 
 fn bench(c: &mut Criterion) {
     for bench in &get_benches() {
@@ -36,6 +43,7 @@ fn bench(c: &mut Criterion) {
         }
     }
     run_evm_uniswapv3_bench(c);
+    run_parallel_block_bench(c);
 }
 
 fn run_evm_bench(c: &mut Criterion, bench: &Bench) {
@@ -72,7 +80,12 @@ fn run_evm_bench(c: &mut Criterion, bench: &Bench) {
         },
     )
     .unwrap();
-    pass::run(&context.mlir_context, &mut module.mlir_module).unwrap();
+    pass::run(
+        &context.mlir_context,
+        &mut module.mlir_module,
+        OptimizationLevel::default(),
+    )
+    .unwrap();
     debug_assert!(module.mlir_module.as_operation().verify());
     let gas_limit = 2_000_000;
     // New the run environment.
@@ -135,7 +148,12 @@ fn run_wasm_bench(c: &mut Criterion, bench: &Bench) {
         },
     )
     .unwrap();
-    pass::run(&context.mlir_context, &mut module.mlir_module).unwrap();
+    pass::run(
+        &context.mlir_context,
+        &mut module.mlir_module,
+        OptimizationLevel::default(),
+    )
+    .unwrap();
     debug_assert!(module.mlir_module.as_operation().verify());
     let gas_limit = 2_000_000;
     // New the run environment.
@@ -179,7 +197,122 @@ fn run_wasm_bench(c: &mut Criterion, bench: &Bench) {
 fn run_evm_uniswapv3_bench(c: &mut Criterion) {
     let addresses = vec![Address::new(rand::random()); 5];
     let seller = addresses[0];
+    let (db, swap_address) = uniswapv3_db(&addresses);
+
+    let env = Env {
+        tx: uniswapv3_swap_tx(seller, swap_address),
+        ..Default::default()
+    };
+
+    let mut g = mk_group(c, "uniswapv3");
+    g.bench_function("dora", |b| {
+        b.iter(|| {
+            let result = run(env.clone(), db.clone(), SpecId::CANCUN).unwrap();
+            assert_eq!(result.gas_used(), UNISWAP_V3_ESTIMATE_GAS);
+            assert!(result.is_success());
+        })
+    });
+
+    g.finish();
+}
+
+fn run_parallel_block_bench(c: &mut Criterion) {
+    let senders: Vec<Address> = (0..BLOCK_TX_COUNT)
+        .map(|_| Address::new(rand::random()))
+        .collect();
+    let concurrency = thread::available_parallelism().map_or(4, |n| n.get());
+
+    // Independent ERC20 transfers between the distinct senders and recipients.
+    let (db, token_address) = erc20_db(&senders);
+    let transactions = senders
+        .iter()
+        .map(|sender| BlockTransaction {
+            tx_type: LEGACY_TX_TYPE,
+            tx: TxEnv {
+                caller: *sender,
+                gas_limit: 100_000,
+                gas_price: U256::from(0xb2d05e07u64),
+                transact_to: TxKind::Call(token_address),
+                data: ERC20Contract::transfer_abi_encode(
+                    Address::new(rand::random()),
+                    U256::from(1),
+                )
+                .into(),
+                nonce: Some(1),
+                ..Default::default()
+            },
+        })
+        .collect();
+    bench_block(c, "erc20_transfer_block", &db, transactions, concurrency);
 
+    // The DEX swaps of the distinct sellers, which conflict on the same pool.
+    let (db, swap_address) = uniswapv3_db(&senders);
+    let transactions = senders
+        .iter()
+        .map(|sender| BlockTransaction {
+            tx_type: LEGACY_TX_TYPE,
+            tx: uniswapv3_swap_tx(*sender, swap_address),
+        })
+        .collect();
+    bench_block(c, "uniswapv3_swap_block", &db, transactions, concurrency);
+}
+
+fn bench_block(
+    c: &mut Criterion,
+    name: &str,
+    db: &MemoryDB,
+    transactions: Vec<BlockTransaction>,
+    concurrency: usize,
+) {
+    let mut block = Block {
+        transactions,
+        ..Default::default()
+    };
+    block.env.coinbase = Address::new(rand::random());
+    block.env.gas_limit = U256::from(u64::MAX);
+    let executor = |db: MemoryDB| {
+        BlockExecutor::new(VM::new(VMContext::new(
+            db,
+            Env::default(),
+            SpecId::CANCUN,
+            compile_handler(),
+        )))
+    };
+
+    let mut g = mk_group(c, name);
+    g.bench_function("sequential", |b| {
+        b.iter(|| {
+            let result = executor(db.clone()).execute_block(&block).unwrap();
+            assert!(result.receipts.iter().all(|receipt| receipt.status));
+        })
+    });
+    g.bench_function("parallel", |b| {
+        b.iter(|| {
+            let result = executor(db.clone())
+                .execute_block_parallel(&block, CompileOptions::from_env(), concurrency)
+                .unwrap();
+            assert!(result.receipts.iter().all(|receipt| receipt.status));
+        })
+    });
+
+    g.finish();
+}
+
+/// Returns the database holding the ERC20 token with the balances of the addresses.
+fn erc20_db(addresses: &[Address]) -> (MemoryDB, Address) {
+    let token_address = Address::new(rand::random());
+    let token_account = ERC20Contract::new("DAI", "DAI", 18, 222_222_000_000_000_000_000_000u128)
+        .add_balances(addresses, uint!(1_000_000_000_000_000_000_U256))
+        .to_db_account();
+    let mut db = MemoryDB::new();
+    insert_account(&mut db, token_address, token_account);
+    fund_accounts(&mut db, addresses);
+    (db, token_address)
+}
+
+/// Returns the database holding the Uniswap V3 pool and the swap contract used by the addresses,
+/// and the swap contract address.
+fn uniswapv3_db(addresses: &[Address]) -> (MemoryDB, Address) {
     let (dai_address, usdc_address) = {
         let x = Address::new(rand::random());
         let y = Address::new(rand::random());
@@ -199,9 +332,9 @@ fn run_evm_uniswapv3_bench(c: &mut Criterion) {
 
     let dai_account = ERC20Contract::new("DAI", "DAI", 18, 222_222_000_000_000_000_000_000u128)
         .add_balances(&[pool_address], uint!(111_111_000_000_000_000_000_000_U256))
-        .add_balances(&addresses, uint!(1_000_000_000_000_000_000_U256))
+        .add_balances(addresses, uint!(1_000_000_000_000_000_000_U256))
         .add_allowances(
-            &addresses,
+            addresses,
             swap_address,
             uint!(1_000_000_000_000_000_000_U256),
         )
@@ -209,9 +342,9 @@ fn run_evm_uniswapv3_bench(c: &mut Criterion) {
 
     let usdc_account = ERC20Contract::new("USDC", "USDC", 18, 222_222_000_000_000_000_000_000u128)
         .add_balances(&[pool_address], uint!(111_111_000_000_000_000_000_000_U256))
-        .add_balances(&addresses, uint!(1_000_000_000_000_000_000_U256))
+        .add_balances(addresses, uint!(1_000_000_000_000_000_000_U256))
         .add_allowances(
-            &addresses,
+            addresses,
             swap_address,
             uint!(1_000_000_000_000_000_000_U256),
         )
@@ -271,51 +404,51 @@ fn run_evm_uniswapv3_bench(c: &mut Criterion) {
     ];
 
     let mut db = MemoryDB::new();
-    for (address, info) in state.clone() {
-        let code = Bytecode::new(info.0.into());
-        let artifact = build_artifact::<MemoryDB>(&code, SpecId::CANCUN).unwrap();
-        db.set_artifact(info.1.bytecode_hash, artifact);
-        db = db.with_contract(address, code);
-        db.set_account(address, info.1.nonce, info.1.balance, info.1.storage);
+    for (address, account) in state {
+        insert_account(&mut db, address, account);
     }
+    fund_accounts(&mut db, addresses);
+    (db, swap_address)
+}
+
+/// Inserts the contract account with its prebuilt artifact.
+fn insert_account(db: &mut MemoryDB, address: Address, (bytecode, account): (Vec<u8>, DbAccount)) {
+    let code = Bytecode::new(bytecode.into());
+    let artifact = build_artifact::<MemoryDB>(&code, SpecId::CANCUN).unwrap();
+    db.set_artifact(
+        artifact_key(&code, account.bytecode_hash, SpecId::CANCUN),
+        artifact,
+    );
+    db.insert_contract(address, code, U256::ZERO);
+    db.set_account(address, account.nonce, account.balance, account.storage);
+}
 
-    for address in addresses.clone() {
+fn fund_accounts(db: &mut MemoryDB, addresses: &[Address]) {
+    for address in addresses {
         db.set_account(
-            address,
+            *address,
             1,
             uint!(4_567_000_000_000_000_000_000_U256),
             FxHashMap::default(),
         );
     }
+}
 
-    let env = Env {
-        tx: TxEnv {
-            caller: seller,
-            gas_limit: 2_000_000,
-            gas_price: U256::from(0xb2d05e07u64),
-            transact_to: TxKind::Call(swap_address),
-            data: [
-                &fixed_bytes!("c92b0891")[..],
-                &B256::from(U256::from(2000))[..],
-            ]
-            .concat()
-            .into(),
-            nonce: Some(1),
-            ..Default::default()
-        },
+fn uniswapv3_swap_tx(caller: Address, swap_address: Address) -> TxEnv {
+    TxEnv {
+        caller,
+        gas_limit: 2_000_000,
+        gas_price: U256::from(0xb2d05e07u64),
+        transact_to: TxKind::Call(swap_address),
+        data: [
+            &fixed_bytes!("c92b0891")[..],
+            &B256::from(U256::from(2000))[..],
+        ]
+        .concat()
+        .into(),
+        nonce: Some(1),
         ..Default::default()
-    };
-
-    let mut g = mk_group(c, "uniswapv3");
-    g.bench_function("dora", |b| {
-        b.iter(|| {
-            let result = run(env.clone(), db.clone(), SpecId::CANCUN).unwrap();
-            assert_eq!(result.gas_used(), UNISWAP_V3_ESTIMATE_GAS);
-            assert!(result.is_success());
-        })
-    });
-
-    g.finish();
+    }
 }
 
 fn mk_group<'a>(c: &'a mut Criterion, name: &str) -> BenchmarkGroup<'a, WallTime> {